@@ -228,6 +228,10 @@ pub struct Config {
 
     #[dynamic(default = "default_true")]
     pub enable_kitty_graphics: bool,
+    /// Whether the response to a primary device attributes query
+    /// advertises support for sixel graphics
+    #[dynamic(default = "default_true")]
+    pub advertise_sixel_graphics: bool,
     #[dynamic(default)]
     pub enable_kitty_keyboard: bool,
 
@@ -239,6 +243,11 @@ pub struct Config {
     #[dynamic(default)]
     pub enable_title_reporting: bool,
 
+    /// Whether a backspace at the left margin moves the cursor to the
+    /// end of the prior line when reverse wraparound mode is not enabled
+    #[dynamic(default)]
+    pub backspace_wraps_to_previous_line: bool,
+
     /// Specifies the width of a new window, expressed in character cells
     #[dynamic(default = "default_initial_cols", validate = "validate_row_or_col")]
     pub initial_cols: u16,
@@ -395,6 +404,9 @@ pub struct Config {
     #[dynamic(default)]
     pub normalize_output_to_unicode_nfc: bool,
 
+    #[dynamic(default)]
+    pub attach_zero_width_graphemes: bool,
+
     #[dynamic(default)]
     pub disable_default_key_bindings: bool,
     pub leader: Option<LeaderKey>,
@@ -406,6 +418,21 @@ pub struct Config {
     #[dynamic(default = "default_alphabet")]
     pub quick_select_alphabet: String,
 
+    /// The maximum number of matches that copy mode search will
+    /// collect before it stops searching the scrollback
+    #[dynamic(default = "default_copy_mode_max_search_results")]
+    pub copy_mode_max_search_results: usize,
+
+    /// How many rows of context copy mode keeps between the cursor
+    /// and the top or bottom of the viewport when scrolling
+    #[dynamic(default = "default_copy_mode_scroll_gap")]
+    pub copy_mode_scroll_gap: usize,
+
+    /// The maximum number of tabs for which copy mode remembers
+    /// the most recent search pattern
+    #[dynamic(default = "default_copy_mode_max_saved_patterns")]
+    pub copy_mode_max_saved_patterns: usize,
+
     #[dynamic(default)]
     pub mouse_bindings: Vec<Mouse>,
     #[dynamic(default)]
@@ -703,6 +730,10 @@ pub struct Config {
     #[dynamic(default = "default_word_boundary")]
     pub selection_word_boundary: String,
 
+    /// Whether copying a block selection ends the text with a newline
+    #[dynamic(default)]
+    pub selection_block_trailing_newline: bool,
+
     #[dynamic(default = "default_enq_answerback")]
     pub enq_answerback: String,
 
@@ -783,9 +814,25 @@ pub struct Config {
     #[dynamic(default)]
     pub audible_bell: AudibleBell,
 
+    /// When true, the terminal ignores BEL entirely, rather than
+    /// raising an alert that the visual and audible bell respond to
+    #[dynamic(default)]
+    pub disable_terminal_bell: bool,
+
     #[dynamic(default)]
     pub canonicalize_pasted_newlines: Option<NewlineCanon>,
 
+    #[dynamic(default = "default_true")]
+    pub filter_bracketed_paste_markers: bool,
+
+    #[dynamic(default)]
+    pub tmux_title_terminator: TmuxTitleTerminator,
+
+    /// The number of bytes of printable text that the terminal buffers
+    /// before applying it to the screen without waiting for a control code
+    #[dynamic(default = "default_print_buffer_flush_threshold")]
+    pub print_buffer_flush_threshold: usize,
+
     #[dynamic(default = "default_unicode_version")]
     pub unicode_version: u8,
 
@@ -795,6 +842,9 @@ pub struct Config {
     #[dynamic(default = "default_true")]
     pub allow_download_protocols: bool,
 
+    #[dynamic(default)]
+    pub osc52_clipboard_policy: Osc52ClipboardPolicy,
+
     #[dynamic(default = "default_true")]
     pub allow_win32_input_mode: bool,
 
@@ -1617,6 +1667,22 @@ fn default_scrollback_lines() -> usize {
     3500
 }
 
+fn default_print_buffer_flush_threshold() -> usize {
+    4096
+}
+
+fn default_copy_mode_max_search_results() -> usize {
+    10_000
+}
+
+fn default_copy_mode_scroll_gap() -> usize {
+    5
+}
+
+fn default_copy_mode_max_saved_patterns() -> usize {
+    256
+}
+
 fn default_initial_rows() -> u16 {
     24
 }
@@ -1683,6 +1749,10 @@ pub fn default_write_timeout() -> Duration {
     Duration::from_secs(60)
 }
 
+pub fn default_connect_automatically_retry_delay() -> Duration {
+    Duration::from_secs(1)
+}
+
 pub fn default_local_echo_threshold_ms() -> Option<u64> {
     Some(100)
 }
@@ -1856,6 +1926,22 @@ pub enum NewlineCanon {
     CarriageReturnAndLineFeed,
 }
 
+#[derive(FromDynamic, ToDynamic, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TmuxTitleTerminator {
+    #[default]
+    StringTerminator,
+    LineFeed,
+    AnyControl,
+}
+
+#[derive(FromDynamic, ToDynamic, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Osc52ClipboardPolicy {
+    #[default]
+    Allow,
+    Deny,
+    Prompt,
+}
+
 #[derive(FromDynamic, ToDynamic, Clone, Copy, Debug, Default)]
 pub enum WindowCloseConfirmation {
     #[default]
@@ -1,5 +1,5 @@
 use crate::default_true;
-use crate::keys::KeyNoAction;
+use crate::keys::{DeferredKeyCode, KeyNoAction};
 use crate::window::WindowLevel;
 use luahelper::impl_lua_conversion_dynamic;
 use ordered_float::NotNan;
@@ -7,7 +7,7 @@ use portable_pty::CommandBuilder;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use wezterm_dynamic::{FromDynamic, FromDynamicOptions, ToDynamic, Value};
 use wezterm_input_types::{KeyCode, Modifiers};
 use wezterm_term::input::MouseButton;
@@ -195,6 +195,16 @@ pub struct SpawnCommand {
     pub domain: SpawnTabDomain,
 
     pub position: Option<crate::GuiPosition>,
+
+    /// Text to write to the pty of the newly spawned pane, once
+    /// it has been created; for example, a command for a shell
+    /// to run.
+    pub initial_input: Option<String>,
+
+    /// If true, initial_input is wrapped in bracketed paste
+    /// sequences when it is sent to the pane.
+    #[dynamic(default)]
+    pub bracket_initial_input: bool,
 }
 impl_lua_conversion_dynamic!(SpawnCommand);
 
@@ -220,6 +230,9 @@ impl std::fmt::Display for SpawnCommand {
         for (k, v) in &self.set_environment_variables {
             write!(fmt, " {}={}", k, v)?;
         }
+        if let Some(input) = &self.initial_input {
+            write!(fmt, " initial_input={:?}", input)?;
+        }
         Ok(())
     }
 }
@@ -235,6 +248,28 @@ impl SpawnCommand {
         }
     }
 
+    /// Check that the command is well-formed prior to spawning it.
+    /// A relative `cwd` is rejected, as the directory that it would
+    /// be relative to varies by domain.
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.cwd {
+            Some(cwd) => Self::validate_cwd(cwd),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that `cwd` is suitable for use as the cwd of a spawned
+    /// command; this is the check made on `cwd` by `validate`.
+    pub fn validate_cwd(cwd: &Path) -> Result<(), String> {
+        if cwd.is_relative() && !cwd.has_root() {
+            return Err(format!(
+                "SpawnCommand cwd must be an absolute path, but {} is relative",
+                cwd.display()
+            ));
+        }
+        Ok(())
+    }
+
     pub fn from_command_builder(cmd: &CommandBuilder) -> anyhow::Result<Self> {
         let mut args = vec![];
         let mut set_environment_variables = HashMap::new();
@@ -259,6 +294,8 @@ impl SpawnCommand {
             set_environment_variables,
             cwd,
             position: None,
+            initial_input: None,
+            bracket_initial_input: false,
         })
     }
 }
@@ -304,6 +341,20 @@ impl Default for ScrollbackEraseMode {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub enum DumpFormat {
+    /// Plain text, without any attributes
+    Text,
+    /// Text with the attributes encoded as ANSI escape sequences
+    Ansi,
+}
+
+impl Default for DumpFormat {
+    fn default() -> Self {
+        Self::Text
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic)]
 pub enum ClipboardCopyDestination {
     Clipboard,
@@ -462,6 +513,16 @@ pub struct PromptInputLine {
     pub description: String,
 }
 
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct PromptInputSelector {
+    /// The list of strings from which the user may choose
+    pub choices: Vec<String>,
+    pub action: Box<KeyAssignment>,
+    /// Descriptive text to show ahead of the list
+    #[dynamic(default)]
+    pub description: String,
+}
+
 #[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
 pub struct InputSelectorEntry {
     pub label: String,
@@ -501,6 +562,35 @@ fn default_fuzzy_description() -> String {
     "Fuzzy matching: ".to_string()
 }
 
+impl From<&PromptInputSelector> for InputSelector {
+    /// Presents the choices of a `PromptInputSelector` through the
+    /// `InputSelector` overlay, with fuzzy matching always enabled.
+    /// Each choice is used as the id of its entry, so that the event
+    /// callback receives the chosen string.
+    fn from(prompt: &PromptInputSelector) -> Self {
+        Self {
+            action: prompt.action.clone(),
+            title: String::new(),
+            choices: prompt
+                .choices
+                .iter()
+                .map(|choice| InputSelectorEntry {
+                    label: choice.clone(),
+                    id: Some(choice.clone()),
+                })
+                .collect(),
+            fuzzy: true,
+            alphabet: default_num_alphabet(),
+            fuzzy_description: if prompt.description.is_empty() {
+                default_fuzzy_description()
+            } else {
+                format!("{}: ", prompt.description)
+            },
+            description: prompt.description.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
 pub enum KeyAssignment {
     SpawnTab(SpawnTabDomain),
@@ -517,14 +607,27 @@ pub enum KeyAssignment {
     PasteFrom(ClipboardPasteSource),
     ActivateTabRelative(isize),
     ActivateTabRelativeNoWrap(isize),
+    ActivateTabMostRecentlyUsed(isize),
     IncreaseFontSize,
     DecreaseFontSize,
     ResetFontSize,
     ResetFontAndWindowSize,
     ActivateTab(isize),
+    ActivateTabByTitle {
+        substring: String,
+        #[dynamic(default)]
+        case_insensitive: bool,
+    },
     ActivateLastTab,
+    SetTabTitle(Option<String>),
+    SetWindowTitle(Option<String>),
     SendString(String),
     SendKey(KeyNoAction),
+    SendComposedKey {
+        key: DeferredKeyCode,
+        #[dynamic(default)]
+        mods: Modifiers,
+    },
     Nop,
     DisableDefaultAssignment,
     Hide,
@@ -539,6 +642,11 @@ pub enum KeyAssignment {
     ScrollByLine(isize),
     ScrollByCurrentEventWheelDelta,
     ScrollToPrompt(isize),
+    ScrollToPromptFiltered {
+        delta: isize,
+        #[dynamic(default)]
+        only_errors: bool,
+    },
     ScrollToTop,
     ScrollToBottom,
     ShowTabNavigator,
@@ -552,6 +660,10 @@ pub enum KeyAssignment {
     ShowLauncher,
     ShowLauncherArgs(LauncherActionArgs),
     ClearScrollback(ScrollbackEraseMode),
+    ClearScreenAndScrollback {
+        #[dynamic(default)]
+        send_redraw: bool,
+    },
     Search(Pattern),
     ActivateCopyMode,
 
@@ -603,6 +715,7 @@ pub enum KeyAssignment {
 
     CopyMode(CopyModeAssignment),
     RotatePanes(RotationDirection),
+    RotateTabs(RotationDirection),
     SplitPane(SplitPane),
     PaneSelect(PaneSelectArguments),
     CharSelect(CharSelectArguments),
@@ -615,9 +728,86 @@ pub enum KeyAssignment {
     ActivateWindowRelativeNoWrap(isize),
     PromptInputLine(PromptInputLine),
     InputSelector(InputSelector),
+    PromptInputSelector(PromptInputSelector),
+    DumpPane {
+        #[dynamic(default)]
+        scrollback: bool,
+        #[dynamic(default)]
+        format: DumpFormat,
+    },
 }
 impl_lua_conversion_dynamic!(KeyAssignment);
 
+impl KeyAssignment {
+    /// Returns a deterministic identifier for this assignment, such
+    /// as `CopyTo:ClipboardAndPrimarySelection`, that is suitable for
+    /// persisting and comparing assignments across runs.
+    /// Unlike the `Debug` representation, it is derived from the
+    /// dynamic encoding of the assignment, so struct fields always
+    /// appear sorted by name.  Delimiters that appear in strings are
+    /// escaped with a backslash, so distinct assignments never share
+    /// an id.
+    pub fn stable_id(&self) -> String {
+        let mut id = String::new();
+        append_stable_id(&mut id, &self.to_dynamic());
+        id
+    }
+}
+
+fn append_stable_id(id: &mut String, value: &Value) {
+    match value {
+        Value::Null => id.push_str("null"),
+        Value::Bool(b) => id.push_str(if *b { "true" } else { "false" }),
+        Value::String(s) => append_escaped(id, s),
+        Value::U64(n) => id.push_str(&n.to_string()),
+        Value::I64(n) => id.push_str(&n.to_string()),
+        Value::F64(n) => id.push_str(&n.to_string()),
+        Value::Array(array) => {
+            id.push('[');
+            for (idx, item) in array.iter().enumerate() {
+                if idx > 0 {
+                    id.push(',');
+                }
+                append_stable_id(id, item);
+            }
+            id.push(']');
+        }
+        Value::Object(object) => {
+            // An enum variant with a payload is encoded as a single
+            // entry object keyed by the variant name
+            if object.len() == 1 {
+                if let Some((Value::String(variant), payload)) = object.iter().next() {
+                    append_escaped(id, variant);
+                    id.push(':');
+                    append_stable_id(id, payload);
+                    return;
+                }
+            }
+            id.push('{');
+            for (idx, (k, v)) in object.iter().enumerate() {
+                if idx > 0 {
+                    id.push(',');
+                }
+                append_stable_id(id, k);
+                id.push('=');
+                append_stable_id(id, v);
+            }
+            id.push('}');
+        }
+    }
+}
+
+/// Escapes the characters that delimit the structure of a stable id,
+/// so that a string payload can't be mistaken for that structure.
+fn append_escaped(id: &mut String, s: &str) {
+    for c in s.chars() {
+        if matches!(c, '\\' | ':' | ',' | '=' | '[' | ']' | '{' | '}') {
+            id.push('\\');
+        }
+        id.push(c);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
 pub struct SplitPane {
     pub direction: PaneDirection,
@@ -662,6 +852,9 @@ pub enum CopyModeAssignment {
     MoveToStartOfNextLine,
     MoveToSelectionOtherEnd,
     MoveToSelectionOtherEndHoriz,
+    MoveToSelectionStart,
+    MoveToSelectionEnd,
+    SelectCurrentSemanticZone,
     MoveBackwardWord,
     MoveForwardWord,
     MoveForwardWordEnd,
@@ -679,6 +872,7 @@ pub enum CopyModeAssignment {
     NextMatchPage,
     CycleMatchType,
     ClearPattern,
+    CancelSearch,
     EditPattern,
     AcceptPattern,
     MoveBackwardSemanticZone,
@@ -703,3 +897,118 @@ pub struct KeyTables {
 pub struct KeyTableEntry {
     pub action: KeyAssignment,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stable_id() {
+        let cases = [
+            (
+                KeyAssignment::CopyTo(ClipboardCopyDestination::ClipboardAndPrimarySelection),
+                "CopyTo:ClipboardAndPrimarySelection",
+            ),
+            (
+                KeyAssignment::CopyTo(ClipboardCopyDestination::Clipboard),
+                "CopyTo:Clipboard",
+            ),
+            (
+                KeyAssignment::SpawnTab(SpawnTabDomain::DefaultDomain),
+                "SpawnTab:DefaultDomain",
+            ),
+            (
+                KeyAssignment::SpawnTab(SpawnTabDomain::DomainName("unix".to_string())),
+                "SpawnTab:DomainName:unix",
+            ),
+            (KeyAssignment::ActivateTab(-1), "ActivateTab:-1"),
+            (KeyAssignment::ActivateTab(1), "ActivateTab:1"),
+            (
+                KeyAssignment::ActivateTabRelative(1),
+                "ActivateTabRelative:1",
+            ),
+            (KeyAssignment::Nop, "Nop"),
+            (
+                KeyAssignment::RotatePanes(RotationDirection::Clockwise),
+                "RotatePanes:Clockwise",
+            ),
+        ];
+
+        for (assignment, expected) in &cases {
+            assert_eq!(assignment.stable_id(), *expected);
+            // Computing it again yields the same result
+            assert_eq!(assignment.stable_id(), assignment.clone().stable_id());
+        }
+
+        let mut ids: Vec<String> = cases.iter().map(|(a, _)| a.stable_id()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), cases.len());
+
+        // Struct fields are ordered by name, so the id doesn't depend
+        // upon the order in which they were declared or assigned
+        let id = KeyAssignment::DumpPane {
+            scrollback: true,
+            format: DumpFormat::default(),
+        }
+        .stable_id();
+        assert!(id.starts_with("DumpPane:{format="), "{id}");
+        assert!(id.ends_with(",scrollback=true}"), "{id}");
+
+        // String payloads can't be confused with the structure of the id
+        let split = KeyAssignment::Multiple(vec![
+            KeyAssignment::SendString("a".to_string()),
+            KeyAssignment::SendString("b".to_string()),
+        ]);
+        let joined = KeyAssignment::Multiple(vec![KeyAssignment::SendString(
+            "a,SendString:b".to_string(),
+        )]);
+        assert_eq!(split.stable_id(), "Multiple:[SendString:a,SendString:b]");
+        assert_eq!(
+            joined.stable_id(),
+            "Multiple:[SendString:a\\,SendString\\:b]"
+        );
+    }
+
+    #[test]
+    fn prompt_input_selector_into_input_selector() {
+        let prompt = PromptInputSelector {
+            choices: vec!["one".to_string(), "two".to_string()],
+            action: Box::new(KeyAssignment::EmitEvent("picked".to_string())),
+            description: "Pick a number".to_string(),
+        };
+        let selector: InputSelector = (&prompt).into();
+        assert!(selector.fuzzy);
+        assert_eq!(selector.action, prompt.action);
+        assert_eq!(selector.description, "Pick a number");
+        assert_eq!(selector.fuzzy_description, "Pick a number: ");
+        assert_eq!(
+            selector.choices,
+            vec![
+                InputSelectorEntry {
+                    label: "one".to_string(),
+                    id: Some("one".to_string()),
+                },
+                InputSelectorEntry {
+                    label: "two".to_string(),
+                    id: Some("two".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn spawn_command_validate() {
+        let spawn = |cwd: Option<&str>| SpawnCommand {
+            cwd: cwd.map(PathBuf::from),
+            ..Default::default()
+        };
+
+        assert_eq!(spawn(None).validate(), Ok(()));
+        assert_eq!(spawn(Some("/tmp")).validate(), Ok(()));
+        assert_eq!(
+            spawn(Some("src/foo")).validate(),
+            Err("SpawnCommand cwd must be an absolute path, but src/foo is relative".to_string())
+        );
+    }
+}
@@ -463,6 +463,18 @@ pub fn configuration_warnings_and_errors() -> Vec<String> {
     CONFIG.get_warnings_and_errors()
 }
 
+/// Returns the error, if any, that prevented the preferred
+/// configuration from being loaded
+pub fn configuration_error() -> Option<String> {
+    CONFIG.get_error()
+}
+
+/// Returns the non-fatal warnings encountered while loading
+/// the preferred configuration
+pub fn configuration_warnings() -> Vec<String> {
+    CONFIG.get_warnings()
+}
+
 struct ConfigInner {
     config: Arc<Config>,
     error: Option<String>,
@@ -751,6 +763,11 @@ impl Configuration {
         inner.error.as_ref().cloned()
     }
 
+    pub fn get_warnings(&self) -> Vec<String> {
+        let inner = self.inner.lock().unwrap();
+        inner.warnings.clone()
+    }
+
     pub fn get_warnings_and_errors(&self) -> Vec<String> {
         let mut result = vec![];
         let inner = self.inner.lock().unwrap();
@@ -68,9 +68,26 @@ pub struct SshDomain {
     #[dynamic(default)]
     pub connect_automatically: bool,
 
+    /// How many more times to try connecting automatically at startup
+    /// if the first attempt fails
+    #[dynamic(default)]
+    pub connect_automatically_retries: usize,
+
+    /// How long to wait before the first retry of a failed automatic
+    /// connection; the delay doubles after each subsequent failure
+    #[dynamic(default = "default_connect_automatically_retry_delay")]
+    pub connect_automatically_retry_delay: Duration,
+
     #[dynamic(default = "default_read_timeout")]
     pub timeout: Duration,
 
+    /// How long to wait for the remote child process to be handed
+    /// back after failing to obtain its exit status, before the pane
+    /// is considered to have exited.  The default of zero treats the
+    /// first error as fatal.
+    #[dynamic(default)]
+    pub exit_grace_period: Duration,
+
     #[dynamic(default = "default_local_echo_threshold_ms")]
     pub local_echo_threshold_ms: Option<u64>,
 
@@ -99,6 +116,11 @@ pub struct SshDomain {
 
     pub default_prog: Option<Vec<String>>,
 
+    /// Environment variables to set for commands spawned in this
+    /// domain, unless the command explicitly sets them itself
+    #[dynamic(default)]
+    pub default_env: HashMap<String, String>,
+
     #[dynamic(default)]
     pub assume_shell: Shell,
 }
@@ -1,10 +1,10 @@
 //! Bridge our gui config into the terminal crate configuration
 
-use crate::{configuration, ConfigHandle, NewlineCanon};
+use crate::{configuration, ConfigHandle, NewlineCanon, Osc52ClipboardPolicy, TmuxTitleTerminator};
 use std::sync::Mutex;
 use termwiz::cell::UnicodeVersion;
 use wezterm_term::color::ColorPalette;
-use wezterm_term::config::BidiMode;
+use wezterm_term::config::{BidiMode, DeviceCapabilities};
 
 #[derive(Debug)]
 pub struct TermConfig {
@@ -100,6 +100,10 @@ impl wezterm_term::TerminalConfiguration for TermConfig {
         }
     }
 
+    fn filter_bracketed_paste_markers(&self) -> bool {
+        self.configuration().filter_bracketed_paste_markers
+    }
+
     fn unicode_version(&self) -> UnicodeVersion {
         let config = self.configuration();
         UnicodeVersion {
@@ -116,10 +120,53 @@ impl wezterm_term::TerminalConfiguration for TermConfig {
         self.configuration().log_unknown_escape_sequences
     }
 
+    fn backspace_wraps_to_previous_line(&self) -> bool {
+        self.configuration().backspace_wraps_to_previous_line
+    }
+
+    fn tmux_title_terminator(&self) -> wezterm_term::config::TmuxTitleTerminator {
+        match self.configuration().tmux_title_terminator {
+            TmuxTitleTerminator::StringTerminator => {
+                wezterm_term::config::TmuxTitleTerminator::StringTerminator
+            }
+            TmuxTitleTerminator::LineFeed => wezterm_term::config::TmuxTitleTerminator::LineFeed,
+            TmuxTitleTerminator::AnyControl => {
+                wezterm_term::config::TmuxTitleTerminator::AnyControl
+            }
+        }
+    }
+
     fn normalize_output_to_unicode_nfc(&self) -> bool {
         self.configuration().normalize_output_to_unicode_nfc
     }
 
+    fn attach_zero_width_graphemes(&self) -> bool {
+        self.configuration().attach_zero_width_graphemes
+    }
+
+    fn bell_disabled(&self) -> bool {
+        self.configuration().disable_terminal_bell
+    }
+
+    fn print_buffer_flush_threshold(&self) -> usize {
+        self.configuration().print_buffer_flush_threshold
+    }
+
+    fn device_capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities {
+            sixel_graphics: self.configuration().advertise_sixel_graphics,
+            ..DeviceCapabilities::default()
+        }
+    }
+
+    fn osc52_clipboard_policy(&self) -> wezterm_term::config::Osc52ClipboardPolicy {
+        match self.configuration().osc52_clipboard_policy {
+            Osc52ClipboardPolicy::Allow => wezterm_term::config::Osc52ClipboardPolicy::Allow,
+            Osc52ClipboardPolicy::Deny => wezterm_term::config::Osc52ClipboardPolicy::Deny,
+            Osc52ClipboardPolicy::Prompt => wezterm_term::config::Osc52ClipboardPolicy::Prompt,
+        }
+    }
+
     fn bidi_mode(&self) -> BidiMode {
         let config = self.configuration();
         BidiMode {
@@ -76,6 +76,16 @@ pub struct TlsDomainClient {
     #[dynamic(default)]
     pub connect_automatically: bool,
 
+    /// How many more times to try connecting automatically at startup
+    /// if the first attempt fails
+    #[dynamic(default)]
+    pub connect_automatically_retries: usize,
+
+    /// How long to wait before the first retry of a failed automatic
+    /// connection; the delay doubles after each subsequent failure
+    #[dynamic(default = "default_connect_automatically_retry_delay")]
+    pub connect_automatically_retry_delay: Duration,
+
     #[dynamic(default = "default_read_timeout")]
     pub read_timeout: Duration,
 
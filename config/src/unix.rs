@@ -20,6 +20,16 @@ pub struct UnixDomain {
     #[dynamic(default)]
     pub connect_automatically: bool,
 
+    /// How many more times to try connecting automatically at startup
+    /// if the first attempt fails
+    #[dynamic(default)]
+    pub connect_automatically_retries: usize,
+
+    /// How long to wait before the first retry of a failed automatic
+    /// connection; the delay doubles after each subsequent failure
+    #[dynamic(default = "default_connect_automatically_retry_delay")]
+    pub connect_automatically_retry_delay: Duration,
+
     /// If true, do not attempt to start this server if we try and fail to
     /// connect to it.
     #[dynamic(default)]
@@ -70,6 +80,8 @@ impl Default for UnixDomain {
             name: String::new(),
             socket_path: None,
             connect_automatically: false,
+            connect_automatically_retries: 0,
+            connect_automatically_retry_delay: default_connect_automatically_retry_delay(),
             no_serve_automatically: false,
             serve_command: None,
             skip_permissions_check: false,
@@ -12,6 +12,8 @@ pub struct WslDomain {
     pub username: Option<String>,
     pub default_cwd: Option<PathBuf>,
     pub default_prog: Option<Vec<String>>,
+    #[dynamic(default)]
+    pub default_env: HashMap<String, String>,
 }
 impl_lua_conversion_dynamic!(WslDomain);
 
@@ -29,6 +31,7 @@ impl WslDomain {
                     username: None,
                     default_cwd: Some("~".into()),
                     default_prog: None,
+                    default_env: HashMap::new(),
                 });
             }
         }
@@ -20,7 +20,7 @@ mod tab;
 mod window;
 
 pub use domain::MuxDomain;
-pub use pane::MuxPane;
+pub use pane::{call_format_pane_title, emit_pane_text_event, MuxPane};
 pub use tab::MuxTab;
 pub use window::MuxWindow;
 
@@ -252,6 +252,7 @@ impl SpawnWindow {
                 None,
                 self.workspace.unwrap_or_else(|| mux.active_workspace()),
                 self.position,
+                None, // optional initial input
             )
             .await
             .map_err(|e| mlua::Error::external(format!("{:#?}", e)))?;
@@ -303,6 +304,7 @@ impl SpawnTab {
                 pane,
                 String::new(),
                 None, // optional gui window position
+                None, // optional initial input
             )
             .await
             .map_err(|e| mlua::Error::external(format!("{:#?}", e)))?;
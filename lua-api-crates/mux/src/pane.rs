@@ -1,10 +1,11 @@
 use super::*;
 use luahelper::{dynamic_to_lua_value, from_lua, to_lua};
-use mlua::Value;
+use mlua::{FromLua, Value};
 use std::cmp::Ordering;
 use std::sync::Arc;
 use termwiz::cell::SemanticType;
-use termwiz_funcs::lines_to_escapes;
+use termwiz::escape::{Action, ControlCode, Esc, EscCode, CSI};
+use termwiz_funcs::{format_as_escapes, lines_to_escapes, FormatItem};
 use url_funcs::Url;
 use wezterm_term::{SemanticZone, StableRowIndex};
 
@@ -82,6 +83,34 @@ impl MuxPane {
     }
 }
 
+/// Emits the synchronous event `name`, passing the pane and `text`
+/// to its handler, and returns whatever the handler returned
+pub fn emit_pane_text_event<'lua>(
+    lua: &'lua Lua,
+    name: &str,
+    pane_id: PaneId,
+    text: &str,
+) -> mlua::Result<Value<'lua>> {
+    config::lua::emit_sync_callback(
+        lua,
+        (name.to_string(), (MuxPane(pane_id), text.to_string())),
+    )
+}
+
+/// Emits `format-pane-title` for `title`.
+/// Returns None if there is no handler, or if it returned nil.
+pub fn call_format_pane_title(
+    lua: &Lua,
+    pane_id: PaneId,
+    title: &str,
+) -> mlua::Result<Option<String>> {
+    let v = emit_pane_text_event(lua, "format-pane-title", pane_id, title)?;
+    match &v {
+        Value::Nil => Ok(None),
+        _ => Ok(Some(String::from_lua(v, lua)?)),
+    }
+}
+
 impl UserData for MuxPane {
     fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
         methods.add_meta_method(mlua::MetaMethod::ToString, |_, this, _: ()| {
@@ -187,6 +216,17 @@ impl UserData for MuxPane {
             Ok(pane.copy_user_vars())
         });
 
+        methods.add_method("get_env", |_, this, name: String| {
+            if name.is_empty() || name.contains('=') {
+                return Err(mlua::Error::external(format!(
+                    "invalid environment variable name {name:?}"
+                )));
+            }
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            Ok(pane.get_spawn_environment_variable(&name))
+        });
+
         methods.add_method("has_unseen_output", |_, this, _: ()| {
             let mux = get_mux()?;
             let pane = this.resolve(&mux)?;
@@ -291,6 +331,15 @@ impl UserData for MuxPane {
             Ok(())
         });
 
+        methods.add_method("inject_changes", |_, this, items: Vec<FormatItem>| {
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            let actions = format_items_as_actions(items)
+                .map_err(|e| mlua::Error::external(format!("{:#}", e)))?;
+            pane.perform_actions(actions);
+            Ok(())
+        });
+
         methods.add_method("get_semantic_zones", |lua, this, of_type: Value| {
             let mux = get_mux()?;
             let pane = this.resolve(&mux)?;
@@ -477,10 +526,116 @@ impl SplitPane {
 
         let mux = get_mux()?;
         let (pane, _size) = mux
-            .split_pane(pane.0, request, source, self.domain.clone())
+            .split_pane(pane.0, request, source, self.domain.clone(), None)
             .await
             .map_err(|e| mlua::Error::external(format!("{:#?}", e)))?;
 
         Ok(MuxPane(pane.pane_id()))
     }
 }
+
+/// Renders the format items in the same way as `wezterm.format` and
+/// then parses the result back into the actions that the terminal
+/// would perform if it were to read that output from the pty.
+/// Since `Text` items may embed arbitrary escape sequences, anything
+/// other than printable text, line breaks and SGR attribute changes
+/// is rejected so that the rest of the terminal state is left alone.
+fn format_items_as_actions(items: Vec<FormatItem>) -> anyhow::Result<Vec<Action>> {
+    let text = format_as_escapes(items)?;
+    let mut parser = termwiz::escape::parser::Parser::new();
+    let mut actions = vec![];
+    parser.parse(text.as_bytes(), |action| actions.push(action));
+
+    for action in &actions {
+        match action {
+            Action::Print(_)
+            | Action::PrintString(_)
+            | Action::CSI(CSI::Sgr(_))
+            // sgr0 in the terminfo database also selects the ASCII charset
+            | Action::Esc(Esc::Code(EscCode::AsciiCharacterSetG0))
+            | Action::Control(
+                ControlCode::CarriageReturn | ControlCode::LineFeed | ControlCode::HorizontalTab,
+            ) => {}
+            _ => anyhow::bail!(
+                "only text and attribute changes can be injected, found {:?}",
+                action
+            ),
+        }
+    }
+
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use termwiz::cell::{AttributeChange, Intensity};
+    use termwiz::color::{AnsiColor, ColorAttribute};
+    use termwiz_funcs::FormatColor;
+    use wezterm_term::Terminal;
+
+    #[test]
+    fn inject_text_and_attribute() {
+        let actions = format_items_as_actions(vec![
+            FormatItem::Foreground(FormatColor::AnsiColor(AnsiColor::Maroon)),
+            FormatItem::Attribute(AttributeChange::Intensity(Intensity::Bold)),
+            FormatItem::Text("hi".to_string()),
+            FormatItem::ResetAttributes,
+            FormatItem::Text("!".to_string()),
+        ])
+        .unwrap();
+
+        let mut term = Terminal::new(
+            TerminalSize::default(),
+            Arc::new(config::TermConfig::new()),
+            "WezTerm",
+            "O_o",
+            Box::new(Vec::new()),
+        );
+        term.perform_actions(actions);
+
+        let lines = term.screen().visible_lines();
+        let line = &lines[0];
+        assert_eq!(&line.as_str()[0..3], "hi!");
+
+        let cell = line.get_cell(1).unwrap();
+        assert_eq!(cell.str(), "i");
+        assert_eq!(
+            cell.attrs().foreground(),
+            ColorAttribute::PaletteIndex(AnsiColor::Maroon as u8)
+        );
+        assert_eq!(cell.attrs().intensity(), Intensity::Bold);
+
+        let cell = line.get_cell(2).unwrap();
+        assert_eq!(cell.str(), "!");
+        assert_eq!(cell.attrs().foreground(), ColorAttribute::Default);
+        assert_eq!(cell.attrs().intensity(), Intensity::Normal);
+    }
+
+    #[test]
+    fn format_pane_title_handler() -> mlua::Result<()> {
+        let lua = Lua::new();
+        assert_eq!(call_format_pane_title(&lua, 1, "/home/user/src")?, None);
+
+        config::lua::register_event(
+            &lua,
+            (
+                "format-pane-title".to_string(),
+                lua.create_function(|_, (_pane, title): (Value, String)| {
+                    Ok(title.replace("/home/user", "~"))
+                })?,
+            ),
+        )?;
+        assert_eq!(
+            call_format_pane_title(&lua, 1, "/home/user/src")?,
+            Some("~/src".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn inject_rejects_cursor_movement() {
+        assert!(format_items_as_actions(vec![FormatItem::Text("\x1b[H".to_string())]).is_err());
+    }
+}
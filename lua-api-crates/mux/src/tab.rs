@@ -100,7 +100,17 @@ impl UserData for MuxTab {
                 let info = luahelper::dynamic_to_lua_value(lua, info.to_dynamic())?;
                 match &info {
                     LuaValue::Table(t) => {
-                        t.set("pane", MuxPane(pos.pane.pane_id()))?;
+                        let pane_id = pos.pane.pane_id();
+                        let raw_title = pos.pane.get_title();
+                        let title = match call_format_pane_title(lua, pane_id, &raw_title) {
+                            Ok(title) => title.unwrap_or(raw_title),
+                            Err(err) => {
+                                log::warn!("format-pane-title: {}", err);
+                                raw_title
+                            }
+                        };
+                        t.set("pane", MuxPane(pane_id))?;
+                        t.set("title", title)?;
                     }
                     _ => {}
                 }
@@ -5,6 +5,7 @@
 //! container or actually remote, running on the other end
 //! of an ssh session somewhere.
 
+use crate::connui::ConnectionUI;
 use crate::localpane::LocalPane;
 use crate::pane::{alloc_pane_id, Pane, PaneId};
 use crate::tab::{SplitRequest, Tab, TabId};
@@ -22,6 +23,7 @@ use std::ffi::OsString;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use wezterm_term::TerminalSize;
 
 static DOMAIN_ID: ::std::sync::atomic::AtomicUsize = ::std::sync::atomic::AtomicUsize::new(0);
@@ -33,6 +35,17 @@ pub enum DomainState {
     Attached,
 }
 
+/// Describes the progress of connecting to a domain, for reporting
+/// the connectivity of the domains that connect automatically at startup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Disconnected,
+    Connecting,
+    Connected,
+    /// The most recent attempt to connect failed with this error
+    Failed(String),
+}
+
 pub fn alloc_domain_id() -> DomainId {
     DOMAIN_ID.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed)
 }
@@ -178,17 +191,134 @@ pub trait Domain: Downcast + Send + Sync {
         self.domain_name().to_string()
     }
 
+    /// Returns environment variables that should be set for commands
+    /// spawned in this domain.  Variables explicitly set on the command
+    /// being spawned take precedence over these defaults.
+    fn default_env(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+
     /// Re-attach to any tabs that might be pre-existing in this domain
     async fn attach(&self, window_id: Option<WindowId>) -> anyhow::Result<()>;
 
+    /// Like `attach`, but reports its progress via `ui` rather than via
+    /// a connection window of its own, so that a series of attempts can
+    /// share the same window.
+    /// The default implementation ignores `ui` and calls `attach`.
+    async fn attach_with_ui(
+        &self,
+        window_id: Option<WindowId>,
+        _ui: &ConnectionUI,
+    ) -> anyhow::Result<()> {
+        self.attach(window_id).await
+    }
+
     /// Detach all tabs
     fn detach(&self) -> anyhow::Result<()>;
 
     /// Indicates the state of the domain
     fn state(&self) -> DomainState;
+
+    /// Returns true if the domain should be attached automatically
+    /// when the GUI starts up
+    fn connect_automatically(&self) -> bool {
+        false
+    }
+
+    /// Indicates the progress of connecting to the domain.
+    /// The default implementation is derived from `state`; domains
+    /// that connect asynchronously can provide more detail.
+    fn connection_status(&self) -> ConnectionStatus {
+        match self.state() {
+            DomainState::Attached => ConnectionStatus::Connected,
+            DomainState::Detached => ConnectionStatus::Disconnected,
+        }
+    }
 }
 impl_downcast!(Domain);
 
+/// Attaches `domain`, making up to `retries` further attempts if that
+/// fails.  The delay between attempts starts at `delay` and doubles
+/// after each failure.  The progress of every attempt is reported via
+/// `ui`.  Returns the error from the final attempt if none succeed.
+pub async fn attach_with_retries(
+    domain: &dyn Domain,
+    window_id: Option<WindowId>,
+    retries: usize,
+    mut delay: Duration,
+    ui: &ConnectionUI,
+) -> anyhow::Result<()> {
+    let mut remaining = retries;
+    loop {
+        match domain.attach_with_ui(window_id, ui).await {
+            Ok(()) => return Ok(()),
+            Err(err) if remaining > 0 => {
+                log::warn!(
+                    "attaching domain {}: {:#}; retrying in {:?}",
+                    domain.domain_name(),
+                    err,
+                    delay
+                );
+                ui.output_str(&format!("Will retry in {:?}\n", delay));
+                remaining -= 1;
+                smol::Timer::after(delay).await;
+                delay = delay.saturating_mul(2);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Merge a domain-provided default environment into cmd.
+/// Only variables that have not been explicitly set on cmd are applied,
+/// so that command-level overrides win over the domain defaults.
+pub fn apply_default_env(cmd: &mut CommandBuilder, default_env: &HashMap<String, String>) {
+    let is_explicit = |cmd: &CommandBuilder, k: &str| {
+        cmd.iter_extra_env_as_str().any(|(key, _)| {
+            if cfg!(windows) {
+                key.eq_ignore_ascii_case(k)
+            } else {
+                key == k
+            }
+        })
+    };
+    for (k, v) in default_env {
+        if !is_explicit(cmd, k) {
+            cmd.env(k, v);
+        }
+    }
+}
+
+/// Text to be sent to a freshly spawned pane, as though it had been
+/// typed by the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitialInput {
+    pub text: String,
+    /// If true, the text is wrapped in bracketed paste sequences
+    pub bracketed: bool,
+}
+
+impl InitialInput {
+    pub fn from_spawn_command(spawn: &SpawnCommand) -> Option<Self> {
+        spawn.initial_input.as_ref().map(|text| Self {
+            text: text.clone(),
+            bracketed: spawn.bracket_initial_input,
+        })
+    }
+
+    pub fn write_to(&self, writer: &mut dyn Write) -> anyhow::Result<()> {
+        if self.bracketed {
+            writer.write_all(b"\x1b[200~")?;
+        }
+        writer.write_all(self.text.as_bytes())?;
+        if self.bracketed {
+            writer.write_all(b"\x1b[201~")?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
 pub struct LocalDomain {
     pty_system: Mutex<Box<dyn PtySystem + Send>>,
     id: DomainId,
@@ -324,6 +454,8 @@ impl LocalDomain {
                 set_environment_variables,
                 cwd,
                 position: None,
+                initial_input: None,
+                bracket_initial_input: false,
             };
 
             let spawn_command = config::with_lua_config_on_main_thread(|lua| async {
@@ -450,7 +582,7 @@ impl LocalDomain {
             }
             None => {
                 let wsl = self.resolve_wsl_domain();
-                config.build_prog(
+                let mut cmd = config.build_prog(
                     None,
                     wsl.as_ref()
                         .map(|wsl| wsl.default_prog.as_ref())
@@ -458,7 +590,12 @@ impl LocalDomain {
                     wsl.as_ref()
                         .map(|wsl| wsl.default_cwd.as_ref())
                         .unwrap_or(config.default_cwd.as_ref()),
-                )?
+                )?;
+                // An explicit command has already had the domain
+                // defaults merged in by the mux; do the same for the
+                // default program here.
+                apply_default_env(&mut cmd, &self.default_env());
+                cmd
             }
         };
         if let Some(dir) = command_dir {
@@ -600,6 +737,7 @@ impl Domain for LocalDomain {
             },
             self.name
         );
+        let spawn_environment = spawn_environment(&cmd);
         let child_result = pair.slave.spawn_command(cmd);
         let mut writer = WriterWrapper::new(pair.master.take_writer()?);
 
@@ -615,15 +753,18 @@ impl Domain for LocalDomain {
         }
 
         let pane: Arc<dyn Pane> = match child_result {
-            Ok(child) => Arc::new(LocalPane::new(
-                pane_id,
-                terminal,
-                child,
-                pair.master,
-                Box::new(writer),
-                self.id,
-                command_description,
-            )),
+            Ok(child) => Arc::new(
+                LocalPane::new(
+                    pane_id,
+                    terminal,
+                    child,
+                    pair.master,
+                    Box::new(writer),
+                    self.id,
+                    command_description,
+                )
+                .with_spawn_environment(spawn_environment),
+            ),
             Err(err) => {
                 // Show the error to the user in the new pane
                 write!(writer, "{err:#}").ok();
@@ -657,6 +798,12 @@ impl Domain for LocalDomain {
         &self.name
     }
 
+    fn default_env(&self) -> HashMap<String, String> {
+        self.resolve_wsl_domain()
+            .map(|wsl| wsl.default_env)
+            .unwrap_or_default()
+    }
+
     async fn domain_label(&self) -> String {
         if let Some(ed) = self.resolve_exec_domain() {
             match &ed.label {
@@ -715,3 +862,333 @@ impl Domain for LocalDomain {
         DomainState::Attached
     }
 }
+
+/// Returns the environment that `cmd` will be spawned with
+fn spawn_environment(cmd: &CommandBuilder) -> HashMap<String, String> {
+    cmd.iter_full_env_as_str()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/// Returns the value of `name` from an environment that was captured
+/// by `spawn_environment`.  As with the environment itself, the name
+/// is matched case-insensitively on Windows.
+pub(crate) fn lookup_spawn_environment(
+    env: &HashMap<String, String>,
+    name: &str,
+) -> Option<String> {
+    if cfg!(windows) {
+        env.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    } else {
+        env.get(name).cloned()
+    }
+}
+
+/// A Domain whose connection status is set explicitly rather than
+/// by connecting to anything, for use in tests.
+/// The panes that it spawns are `FakePane`s, which it keeps track of.
+#[cfg(any(test, feature = "test-util"))]
+pub struct FakeDomain {
+    domain_id: DomainId,
+    name: String,
+    auto: bool,
+    status: Mutex<ConnectionStatus>,
+    panes: Mutex<Vec<Arc<crate::pane::FakePane>>>,
+    /// The number of upcoming calls to `attach` that will fail
+    attach_failures: Mutex<usize>,
+    /// The number of calls made to `attach`
+    pub attach_attempts: Mutex<usize>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl FakeDomain {
+    pub fn new(name: &str, auto: bool) -> Arc<Self> {
+        Arc::new(Self {
+            domain_id: alloc_domain_id(),
+            name: name.to_string(),
+            auto,
+            status: Mutex::new(ConnectionStatus::Disconnected),
+            panes: Mutex::new(vec![]),
+            attach_failures: Mutex::new(0),
+            attach_attempts: Mutex::new(0),
+        })
+    }
+
+    pub fn set_status(&self, status: ConnectionStatus) {
+        *self.status.lock() = status;
+    }
+
+    /// Causes the next `count` calls to `attach` to fail
+    pub fn fail_attach(&self, count: usize) {
+        *self.attach_failures.lock() = count;
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+#[async_trait(?Send)]
+impl Domain for FakeDomain {
+    async fn spawn(
+        &self,
+        size: TerminalSize,
+        command: Option<CommandBuilder>,
+        command_dir: Option<String>,
+        _window: WindowId,
+    ) -> anyhow::Result<Arc<Tab>> {
+        // Unlike the default implementation, this doesn't register
+        // the tab with the mux, which the caller may not have set up
+        let pane = self.spawn_pane(size, command, command_dir).await?;
+        let tab = Arc::new(Tab::new(&size));
+        tab.assign_pane(&pane);
+        Ok(tab)
+    }
+
+    async fn spawn_pane(
+        &self,
+        _size: TerminalSize,
+        _command: Option<CommandBuilder>,
+        _command_dir: Option<String>,
+    ) -> anyhow::Result<Arc<dyn Pane>> {
+        let pane = Arc::new(crate::pane::FakePane {
+            pane_id: alloc_pane_id(),
+            domain_id: self.domain_id,
+            ..Default::default()
+        });
+        self.panes.lock().push(Arc::clone(&pane));
+        Ok(pane)
+    }
+
+    fn detachable(&self) -> bool {
+        false
+    }
+
+    fn domain_id(&self) -> DomainId {
+        self.domain_id
+    }
+
+    fn domain_name(&self) -> &str {
+        &self.name
+    }
+
+    async fn attach(&self, _window_id: Option<WindowId>) -> anyhow::Result<()> {
+        *self.attach_attempts.lock() += 1;
+        let mut failures = self.attach_failures.lock();
+        if *failures > 0 {
+            *failures -= 1;
+            self.set_status(ConnectionStatus::Failed("refused".to_string()));
+            bail!("connection refused");
+        }
+        self.set_status(ConnectionStatus::Connected);
+        Ok(())
+    }
+
+    fn detach(&self) -> anyhow::Result<()> {
+        bail!("detach not implemented for FakeDomain");
+    }
+
+    fn state(&self) -> DomainState {
+        match *self.status.lock() {
+            ConnectionStatus::Connected => DomainState::Attached,
+            _ => DomainState::Detached,
+        }
+    }
+
+    fn connect_automatically(&self) -> bool {
+        self.auto
+    }
+
+    fn connection_status(&self) -> ConnectionStatus {
+        self.status.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn auto_connect_domain_status() {
+        let mux = Mux::new(None);
+        let auto = FakeDomain::new("auto", true);
+        let manual = FakeDomain::new("manual", false);
+        mux.add_domain(&(Arc::clone(&auto) as Arc<dyn Domain>));
+        mux.add_domain(&(Arc::clone(&manual) as Arc<dyn Domain>));
+
+        let status = |status| vec![(auto.domain_id, "auto".to_string(), status)];
+        assert_eq!(
+            mux.auto_connect_domain_status(),
+            status(ConnectionStatus::Disconnected)
+        );
+
+        for next in [
+            ConnectionStatus::Connecting,
+            ConnectionStatus::Failed("refused".to_string()),
+            ConnectionStatus::Connecting,
+            ConnectionStatus::Connected,
+            ConnectionStatus::Disconnected,
+        ] {
+            auto.set_status(next.clone());
+            assert_eq!(mux.auto_connect_domain_status(), status(next));
+        }
+    }
+
+    #[test]
+    fn attach_is_retried() {
+        let ui = ConnectionUI::new_headless();
+        let domain = FakeDomain::new("flaky", true);
+        domain.fail_attach(2);
+        smol::block_on(attach_with_retries(&*domain, None, 3, Duration::ZERO, &ui)).unwrap();
+        assert_eq!(*domain.attach_attempts.lock(), 3);
+        assert_eq!(domain.connection_status(), ConnectionStatus::Connected);
+    }
+
+    #[test]
+    fn attach_retries_are_limited() {
+        let ui = ConnectionUI::new_headless();
+        let domain = FakeDomain::new("down", true);
+        domain.fail_attach(5);
+        let err = smol::block_on(attach_with_retries(&*domain, None, 2, Duration::ZERO, &ui))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "connection refused");
+        assert_eq!(*domain.attach_attempts.lock(), 3);
+        assert_eq!(
+            domain.connection_status(),
+            ConnectionStatus::Failed("refused".to_string())
+        );
+
+        // With no retries, only a single attempt is made
+        let domain = FakeDomain::new("down", true);
+        domain.fail_attach(1);
+        assert!(
+            smol::block_on(attach_with_retries(&*domain, None, 0, Duration::ZERO, &ui)).is_err()
+        );
+        assert_eq!(*domain.attach_attempts.lock(), 1);
+    }
+
+    #[test]
+    fn spawn_environment_is_captured() {
+        let mut cmd = CommandBuilder::new("bash");
+        cmd.env("WEZTERM_TEST_VAR", "hello");
+        let env = spawn_environment(&cmd);
+        assert_eq!(
+            env.get("WEZTERM_TEST_VAR").map(String::as_str),
+            Some("hello")
+        );
+        assert_eq!(env.get("WEZTERM_TEST_UNSET_VAR"), None);
+    }
+
+    #[test]
+    fn spawn_environment_lookup() {
+        let mut cmd = CommandBuilder::new("bash");
+        cmd.env("WEZTERM_TEST_VAR", "hello");
+        let env = spawn_environment(&cmd);
+        assert_eq!(
+            lookup_spawn_environment(&env, "WEZTERM_TEST_VAR"),
+            Some("hello".to_string())
+        );
+        assert_eq!(
+            lookup_spawn_environment(&env, "WEZTERM_TEST_UNSET_VAR"),
+            None
+        );
+
+        // Only Windows treats environment variable names case-insensitively
+        assert_eq!(
+            lookup_spawn_environment(&env, "wezterm_test_var"),
+            if cfg!(windows) {
+                Some("hello".to_string())
+            } else {
+                None
+            }
+        );
+    }
+
+    #[test]
+    fn default_env_is_merged_under_command_env() {
+        let mut default_env = HashMap::new();
+        default_env.insert("TERM".to_string(), "xterm-256color".to_string());
+        default_env.insert("LANG".to_string(), "en_US.UTF-8".to_string());
+
+        let mut cmd = CommandBuilder::new("bash");
+        cmd.env("TERM", "wezterm");
+
+        apply_default_env(&mut cmd, &default_env);
+
+        // The command-level value wins over the domain default
+        assert_eq!(
+            cmd.get_env("TERM"),
+            Some(OsString::from("wezterm").as_os_str())
+        );
+        // while unset variables pick up the domain default
+        assert_eq!(
+            cmd.get_env("LANG"),
+            Some(OsString::from("en_US.UTF-8").as_os_str())
+        );
+    }
+
+    #[test]
+    fn empty_default_env_is_noop() {
+        let mut cmd = CommandBuilder::new("bash");
+        cmd.env("FOO", "bar");
+        let before: Vec<(String, String)> = cmd
+            .iter_extra_env_as_str()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        apply_default_env(&mut cmd, &HashMap::new());
+
+        let after: Vec<(String, String)> = cmd
+            .iter_extra_env_as_str()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn initial_input_is_written_on_spawn() {
+        let domain = FakeDomain::new("fake", false);
+        let mux = Arc::new(Mux::new(Some(Arc::clone(&domain) as Arc<dyn Domain>)));
+        Mux::set_mux(&mux);
+
+        let spawn = |initial_input| {
+            smol::block_on(mux.spawn_tab_or_window(
+                None,
+                SpawnTabDomain::DefaultDomain,
+                None,
+                None,
+                TerminalSize::default(),
+                None,
+                String::new(),
+                None,
+                initial_input,
+            ))
+            .unwrap()
+        };
+
+        spawn(None);
+        spawn(Some(InitialInput {
+            text: "ls -l\r".to_string(),
+            bracketed: false,
+        }));
+        spawn(Some(InitialInput {
+            text: "echo hello".to_string(),
+            bracketed: true,
+        }));
+        Mux::shutdown();
+
+        let written: Vec<Vec<u8>> = domain
+            .panes
+            .lock()
+            .iter()
+            .map(|pane| pane.written.lock().clone())
+            .collect();
+        assert_eq!(
+            written,
+            vec![
+                b"".to_vec(),
+                b"ls -l\r".to_vec(),
+                b"\x1b[200~echo hello\x1b[201~".to_vec(),
+            ]
+        );
+    }
+}
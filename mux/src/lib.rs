@@ -3,9 +3,11 @@ use crate::pane::{Pane, PaneId};
 use crate::tab::{SplitRequest, Tab, TabId};
 use crate::window::{Window, WindowId};
 use anyhow::{anyhow, Context, Error};
-use config::keyassignment::SpawnTabDomain;
+use config::keyassignment::{SpawnCommand, SpawnTabDomain};
 use config::{configuration, ExitBehavior, GuiPosition};
-use domain::{Domain, DomainId, DomainState, SplitSource};
+use domain::{
+    apply_default_env, ConnectionStatus, Domain, DomainId, DomainState, InitialInput, SplitSource,
+};
 use filedescriptor::{poll, pollfd, socketpair, AsRawSocketDescriptor, FileDescriptor, POLLIN};
 #[cfg(unix)]
 use libc::{SOL_SOCKET, SO_RCVBUF, SO_SNDBUF};
@@ -19,6 +21,7 @@ use portable_pty::{CommandBuilder, ExitStatus, PtySize};
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::io::{Read, Write};
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Weak};
 use std::thread;
@@ -26,7 +29,7 @@ use std::time::{Duration, Instant};
 use termwiz::escape::csi::{DecPrivateMode, DecPrivateModeCode, Device, Mode};
 use termwiz::escape::{Action, CSI};
 use thiserror::*;
-use wezterm_term::{Clipboard, ClipboardSelection, DownloadHandler, TerminalSize};
+use wezterm_term::{Clipboard, ClipboardSelection, DownloadHandler, StableRowIndex, TerminalSize};
 #[cfg(windows)]
 use winapi::um::winsock2::{SOL_SOCKET, SO_RCVBUF, SO_SNDBUF};
 
@@ -79,6 +82,7 @@ pub enum MuxNotification {
     },
     PaneFocused(PaneId),
     TabResized(TabId),
+    TabRemoved(TabId),
     TabTitleChanged {
         tab_id: TabId,
         title: String,
@@ -107,6 +111,8 @@ pub struct Mux {
     clients: RwLock<HashMap<ClientId, ClientInfo>>,
     identity: RwLock<Option<Arc<ClientId>>>,
     num_panes_by_workspace: RwLock<HashMap<String, usize>>,
+    pane_viewports: RwLock<HashMap<PaneId, StableRowIndex>>,
+    detached_viewports: RwLock<HashMap<(DomainId, PaneId), StableRowIndex>>,
     main_thread_id: std::thread::ThreadId,
 }
 
@@ -433,6 +439,8 @@ impl Mux {
             clients: RwLock::new(HashMap::new()),
             identity: RwLock::new(None),
             num_panes_by_workspace: RwLock::new(HashMap::new()),
+            pane_viewports: RwLock::new(HashMap::new()),
+            detached_viewports: RwLock::new(HashMap::new()),
             main_thread_id: std::thread::current().id(),
         }
     }
@@ -794,6 +802,7 @@ impl Mux {
 
     fn remove_pane_internal(&self, pane_id: PaneId) {
         log::debug!("removing pane {}", pane_id);
+        self.pane_viewports.write().remove(&pane_id);
         if let Some(pane) = self.panes.write().remove(&pane_id).clone() {
             log::debug!("killing pane {}", pane_id);
             pane.kill();
@@ -822,6 +831,7 @@ impl Mux {
             self.remove_pane_internal(pane_id);
         }
         self.recompute_pane_count();
+        self.notify(MuxNotification::TabRemoved(tab_id));
 
         Some(tab)
     }
@@ -1043,6 +1053,67 @@ impl Mux {
         self.domains.read().values().cloned().collect()
     }
 
+    /// Returns the id, name and connection status of each of the
+    /// domains that connect automatically at startup, ordered by id
+    pub fn auto_connect_domain_status(&self) -> Vec<(DomainId, String, ConnectionStatus)> {
+        let mut status: Vec<_> = self
+            .domains
+            .read()
+            .values()
+            .filter(|dom| dom.connect_automatically())
+            .map(|dom| {
+                (
+                    dom.domain_id(),
+                    dom.domain_name().to_string(),
+                    dom.connection_status(),
+                )
+            })
+            .collect();
+        status.sort_by_key(|(domain_id, _, _)| *domain_id);
+        status
+    }
+
+    /// Records the top row of the viewport of a pane that has been
+    /// scrolled back, or None if it is at the bottom of the scrollback
+    pub fn set_pane_viewport(&self, pane_id: PaneId, viewport: Option<StableRowIndex>) {
+        let mut viewports = self.pane_viewports.write();
+        match viewport {
+            Some(viewport) => viewports.insert(pane_id, viewport),
+            None => viewports.remove(&pane_id),
+        };
+    }
+
+    pub fn get_pane_viewport(&self, pane_id: PaneId) -> Option<StableRowIndex> {
+        self.pane_viewports.read().get(&pane_id).copied()
+    }
+
+    /// Called by a domain that is about to detach to remember the
+    /// viewport of `pane_id`.  `key` identifies the pane in a way that
+    /// survives detaching, such as its id on a remote mux server.
+    pub fn stash_pane_viewport(&self, domain_id: DomainId, key: PaneId, pane_id: PaneId) {
+        if let Some(viewport) = self.pane_viewports.write().remove(&pane_id) {
+            self.detached_viewports
+                .write()
+                .insert((domain_id, key), viewport);
+        }
+    }
+
+    /// Called by a domain that has reattached to restore the viewport
+    /// previously stashed under `key` to the pane that now represents it
+    pub fn restore_pane_viewport(&self, domain_id: DomainId, key: PaneId, pane_id: PaneId) {
+        if let Some(viewport) = self.detached_viewports.write().remove(&(domain_id, key)) {
+            self.pane_viewports.write().insert(pane_id, viewport);
+        }
+    }
+
+    /// Called by a domain that has resynced with its remote panes to
+    /// forget the viewports stashed for panes that no longer exist
+    pub fn prune_detached_viewports(&self, domain_id: DomainId, present: &HashSet<PaneId>) {
+        self.detached_viewports
+            .write()
+            .retain(|&(domain, key), _| domain != domain_id || present.contains(&key));
+    }
+
     pub fn resolve_pane_id(&self, pane_id: PaneId) -> Option<(DomainId, WindowId, TabId)> {
         let mut ids = None;
         for tab in self.tabs.read().values() {
@@ -1165,6 +1236,7 @@ impl Mux {
         request: SplitRequest,
         source: SplitSource,
         domain: config::keyassignment::SpawnTabDomain,
+        initial_input: Option<InitialInput>,
     ) -> anyhow::Result<(Arc<dyn Pane>, TerminalSize)> {
         let (_pane_domain_id, window_id, tab_id) = self
             .resolve_pane_id(pane_id)
@@ -1183,12 +1255,23 @@ impl Mux {
             .ok_or_else(|| anyhow!("pane_id {} is invalid", pane_id))?;
         let term_config = current_pane.get_config();
 
+        if let SplitSource::Spawn {
+            command_dir: Some(dir),
+            ..
+        } = &source
+        {
+            SpawnCommand::validate_cwd(Path::new(dir)).map_err(|err| anyhow!(err))?;
+        }
+
         let source = match source {
             SplitSource::Spawn {
                 command,
                 command_dir,
             } => SplitSource::Spawn {
-                command,
+                command: command.map(|mut cmd| {
+                    apply_default_env(&mut cmd, &domain.default_env());
+                    cmd
+                }),
                 command_dir: self.resolve_cwd(
                     command_dir,
                     Some(Arc::clone(&current_pane)),
@@ -1202,6 +1285,11 @@ impl Mux {
         if let Some(config) = term_config {
             pane.set_config(config);
         }
+        if let Some(input) = initial_input {
+            input
+                .write_to(&mut *pane.writer())
+                .context("writing initial_input")?;
+        }
 
         // FIXME: clipboard
 
@@ -1287,7 +1375,12 @@ impl Mux {
         current_pane_id: Option<PaneId>,
         workspace_for_new_window: String,
         window_position: Option<GuiPosition>,
+        initial_input: Option<InitialInput>,
     ) -> anyhow::Result<(Arc<Tab>, Arc<dyn Pane>, WindowId)> {
+        if let Some(dir) = &command_dir {
+            SpawnCommand::validate_cwd(Path::new(dir)).map_err(|err| anyhow!(err))?;
+        }
+
         let domain = self
             .resolve_spawn_tab_domain(current_pane_id, &domain)
             .context("resolve_spawn_tab_domain")?;
@@ -1320,6 +1413,11 @@ impl Mux {
             domain.attach(Some(window_id)).await?;
         }
 
+        let command = command.map(|mut cmd| {
+            apply_default_env(&mut cmd, &domain.default_env());
+            cmd
+        });
+
         let cwd = self.resolve_cwd(
             command_dir,
             match current_pane_id {
@@ -1357,6 +1455,11 @@ impl Mux {
         if let Some(config) = term_config {
             pane.set_config(config);
         }
+        if let Some(input) = initial_input {
+            input
+                .write_to(&mut *pane.writer())
+                .context("writing initial_input")?;
+        }
 
         // FIXME: clipboard?
 
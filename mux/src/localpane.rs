@@ -1,4 +1,4 @@
-use crate::domain::DomainId;
+use crate::domain::{lookup_spawn_environment, DomainId};
 use crate::pane::{
     CloseReason, ForEachPaneLogicalLine, LogicalLine, Pane, PaneId, Pattern, SearchResult,
     WithPaneLines,
@@ -17,7 +17,7 @@ use procinfo::LocalProcessInfo;
 use rangeset::RangeSet;
 use smol::channel::{bounded, Receiver, TryRecvError};
 use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::convert::TryInto;
 use std::io::{Result as IoResult, Write};
 use std::ops::Range;
@@ -133,6 +133,10 @@ pub struct LocalPane {
     #[cfg(unix)]
     leader: Arc<Mutex<Option<CachedLeaderInfo>>>,
     command_description: String,
+    /// Chunks of a large paste that have yet to be written
+    pending_paste: Mutex<VecDeque<String>>,
+    /// The environment that the command was spawned with
+    spawn_environment: HashMap<String, String>,
 }
 
 #[async_trait(?Send)]
@@ -219,6 +223,10 @@ impl Pane for LocalPane {
         self.terminal.lock().user_vars().clone()
     }
 
+    fn get_spawn_environment_variable(&self, name: &str) -> Option<String> {
+        lookup_spawn_environment(&self.spawn_environment, name)
+    }
+
     fn exit_behavior(&self) -> Option<ExitBehavior> {
         // If we are ssh, and we've not yet fully connected,
         // then override exit_behavior so that we can show
@@ -436,10 +444,25 @@ impl Pane for LocalPane {
     fn send_paste(&self, text: &str) -> Result<(), Error> {
         Mux::get().record_input_for_current_identity();
         if self.tmux_domain.lock().is_some() {
-            Ok(())
-        } else {
-            self.terminal.lock().send_paste(text)
+            return Ok(());
+        }
+
+        let chunks = self.terminal.lock().paste_chunks(text);
+        let mut pending = self.pending_paste.lock();
+        let idle = pending.is_empty();
+        if idle && chunks.len() == 1 {
+            drop(pending);
+            return self.terminal.lock().send_paste_chunk(&chunks[0]);
+        }
+
+        // Queue up the chunks behind any paste that is still in progress,
+        // and start writing them if nothing else is
+        pending.extend(chunks);
+        if idle {
+            let pane_id = self.pane_id;
+            promise::spawn::spawn(async move { write_pending_paste(pane_id).await }).detach();
         }
+        Ok(())
     }
 
     fn get_title(&self) -> String {
@@ -635,6 +658,16 @@ impl Pane for LocalPane {
         term.get_semantic_zones()
     }
 
+    fn get_prompt_rows(&self) -> anyhow::Result<Vec<StableRowIndex>> {
+        let term = self.terminal.lock();
+        Ok(term.get_prompt_rows())
+    }
+
+    fn get_error_prompt_rows(&self) -> anyhow::Result<Vec<StableRowIndex>> {
+        let term = self.terminal.lock();
+        Ok(term.get_error_prompt_rows())
+    }
+
     async fn search(
         &self,
         pattern: Pattern,
@@ -822,6 +855,44 @@ struct LocalPaneDCSHandler {
     tmux_domain: Option<Arc<TmuxDomainState>>,
 }
 
+/// Writes the queued chunks of a large paste to the terminal, yielding
+/// to the executor between chunks so that a large paste doesn't hold
+/// up the gui.
+async fn write_pending_paste(pane_id: PaneId) {
+    loop {
+        {
+            let mux = Mux::get();
+            let pane = match mux.get_pane(pane_id) {
+                Some(pane) => pane,
+                None => return,
+            };
+            let pane = match pane.downcast_ref::<LocalPane>() {
+                Some(pane) => pane,
+                None => return,
+            };
+
+            // The chunk remains at the front of the queue until it has been
+            // written, so that a concurrent send_paste will queue behind it
+            let chunk = match pane.pending_paste.lock().front().cloned() {
+                Some(chunk) => chunk,
+                None => return,
+            };
+            if let Err(err) = pane.terminal.lock().send_paste_chunk(&chunk) {
+                log::error!("error while pasting into pane {pane_id}: {err:#}");
+                pane.pending_paste.lock().clear();
+                return;
+            }
+
+            let mut pending = pane.pending_paste.lock();
+            pending.pop_front();
+            if pending.is_empty() {
+                return;
+            }
+        }
+        smol::future::yield_now().await;
+    }
+}
+
 pub(crate) fn emit_output_for_pane(pane_id: PaneId, message: &str) {
     let mut parser = termwiz::escape::parser::Parser::new();
     let mut actions = vec![Action::CSI(CSI::Sgr(Sgr::Reset))];
@@ -1011,9 +1082,18 @@ impl LocalPane {
             #[cfg(unix)]
             leader: Arc::new(Mutex::new(None)),
             command_description,
+            pending_paste: Mutex::new(VecDeque::new()),
+            spawn_environment: HashMap::new(),
         }
     }
 
+    /// Records the environment that the command was spawned with,
+    /// so that it can be queried via `get_spawn_environment_variable`
+    pub fn with_spawn_environment(mut self, spawn_environment: HashMap<String, String>) -> Self {
+        self.spawn_environment = spawn_environment;
+        self
+    }
+
     #[cfg(unix)]
     fn get_leader(&self) -> CachedLeaderInfo {
         let mut leader = self.leader.lock();
@@ -2,13 +2,14 @@ use crate::domain::DomainId;
 use crate::renderable::*;
 use crate::ExitBehavior;
 use async_trait::async_trait;
-use config::keyassignment::{KeyAssignment, ScrollbackEraseMode};
+use config::keyassignment::{DumpFormat, KeyAssignment, ScrollbackEraseMode};
 use downcast_rs::{impl_downcast, Downcast};
 use parking_lot::MappedMutexGuard;
 use rangeset::RangeSet;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use termwiz::hyperlink::Rule;
 use termwiz::input::KeyboardEncoding;
@@ -242,6 +243,12 @@ pub trait Pane: Downcast + Send + Sync {
         HashMap::new()
     }
 
+    /// Returns the value of the environment variable `name` as it was
+    /// set for the command when the pane was spawned, if known
+    fn get_spawn_environment_variable(&self, _name: &str) -> Option<String> {
+        None
+    }
+
     fn erase_scrollback(&self, _erase_mode: ScrollbackEraseMode) {}
 
     /// Called to advise on whether this tab has focus
@@ -284,6 +291,22 @@ pub trait Pane: Downcast + Send + Sync {
         Ok(vec![])
     }
 
+    /// Retrieve the stable rows on which OSC 133 prompts were started.
+    /// An empty list means that the pane doesn't track prompt rows
+    /// (or that there are none), in which case callers can fall back
+    /// to deriving them from the semantic zones.
+    fn get_prompt_rows(&self) -> anyhow::Result<Vec<StableRowIndex>> {
+        Ok(vec![])
+    }
+
+    /// Retrieve the subset of the prompt rows from which a command was
+    /// run that reported a non-zero exit status via OSC 133.
+    /// The exit status isn't relayed by the mux protocol, so panes from
+    /// a client domain always report an empty list.
+    fn get_error_prompt_rows(&self) -> anyhow::Result<Vec<StableRowIndex>> {
+        Ok(vec![])
+    }
+
     /// Returns true if the terminal has grabbed the mouse and wants to
     /// give the embedded application a chance to process events.
     /// In practice this controls whether the gui will perform local
@@ -511,129 +534,224 @@ pub fn impl_get_lines_via_with_lines<P: Pane + ?Sized>(
     (collector.first, collector.lines)
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use k9::snapshot;
-    use parking_lot::{MappedMutexGuard, Mutex};
-    use std::borrow::Cow;
-    use termwiz::surface::SEQ_ZERO;
+/// Returns the content of the viewport of the pane, or of the viewport
+/// and the scrollback above it if `scrollback` is true, in the requested
+/// format.
+pub fn dump_pane_content(
+    pane: &dyn Pane,
+    scrollback: bool,
+    format: DumpFormat,
+) -> anyhow::Result<String> {
+    let dims = pane.get_dimensions();
+    let top = if scrollback {
+        dims.scrollback_top
+    } else {
+        dims.physical_top
+    };
+    let bottom = dims.physical_top + dims.viewport_rows as StableRowIndex;
+    let (_first_row, lines) = pane.get_lines(top..bottom);
+
+    match format {
+        DumpFormat::Text => {
+            let mut text = String::new();
+            for line in lines {
+                text.push_str(line.as_str().trim_end());
+                text.push('\n');
+            }
+            Ok(text)
+        }
+        DumpFormat::Ansi => termwiz_funcs::lines_to_escapes(lines),
+    }
+}
 
-    struct FakePane {
-        lines: Mutex<Vec<Line>>,
+/// Writes the content of the pane to a new file in `dir`, as described
+/// by `dump_pane_content`, and returns the path to that file.
+pub fn dump_pane_to_file(
+    pane: &dyn Pane,
+    scrollback: bool,
+    format: DumpFormat,
+    dir: &Path,
+) -> anyhow::Result<PathBuf> {
+    let content = dump_pane_content(pane, scrollback, format)?;
+    let extension = match format {
+        DumpFormat::Text => "txt",
+        DumpFormat::Ansi => "ansi",
+    };
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis();
+    let path = dir.join(format!("pane-{}-{timestamp}.{extension}", pane.pane_id()));
+    config::create_user_owned_dirs(dir)?;
+    std::fs::write(&path, content)
+        .map_err(|err| anyhow::anyhow!("writing pane content to {}: {err:#}", path.display()))?;
+    Ok(path)
+}
+
+/// Erases the scrollback and viewport of the pane and then, if
+/// `send_redraw` is true, sends CTRL-L so that the application
+/// (typically the shell) redraws its prompt on the cleared screen.
+pub fn clear_screen_and_scrollback(pane: &dyn Pane, send_redraw: bool) -> anyhow::Result<()> {
+    pane.erase_scrollback(ScrollbackEraseMode::ScrollbackAndViewport);
+    if send_redraw {
+        pane.key_down(KeyCode::Char('l'), KeyModifiers::CTRL)?;
     }
+    Ok(())
+}
 
-    impl Pane for FakePane {
-        fn pane_id(&self) -> PaneId {
-            unimplemented!()
-        }
-        fn get_cursor_position(&self) -> StableCursorPosition {
-            unimplemented!()
-        }
+/// A Pane backed by a fixed set of lines, for use in tests.
+/// Scrollback erasure, key presses and anything written to the pane
+/// are recorded rather than acted upon.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Default)]
+pub struct FakePane {
+    pub pane_id: PaneId,
+    pub domain_id: DomainId,
+    pub lines: parking_lot::Mutex<Vec<Line>>,
+    pub viewport_rows: usize,
+    pub erased: parking_lot::Mutex<Vec<ScrollbackEraseMode>>,
+    pub keys: parking_lot::Mutex<Vec<(KeyCode, KeyModifiers)>>,
+    pub written: parking_lot::Mutex<Vec<u8>>,
+}
 
-        fn get_current_seqno(&self) -> SequenceNo {
-            unimplemented!()
-        }
+#[cfg(any(test, feature = "test-util"))]
+impl Pane for FakePane {
+    fn pane_id(&self) -> PaneId {
+        self.pane_id
+    }
+    fn get_cursor_position(&self) -> StableCursorPosition {
+        unimplemented!()
+    }
 
-        fn get_changed_since(
-            &self,
-            _: Range<StableRowIndex>,
-            _: SequenceNo,
-        ) -> RangeSet<StableRowIndex> {
-            unimplemented!()
-        }
+    fn get_current_seqno(&self) -> SequenceNo {
+        unimplemented!()
+    }
 
-        fn with_lines_mut(
-            &self,
-            stable_range: Range<StableRowIndex>,
-            with_lines: &mut dyn WithPaneLines,
-        ) {
-            let mut line_refs = vec![];
-            let mut lines = self.lines.lock();
-            for line in lines
-                .iter_mut()
-                .skip(stable_range.start as usize)
-                .take((stable_range.end - stable_range.start) as usize)
-            {
-                line_refs.push(line);
-            }
-            with_lines.with_lines_mut(stable_range.start, &mut line_refs);
-        }
+    fn get_changed_since(
+        &self,
+        _: Range<StableRowIndex>,
+        _: SequenceNo,
+    ) -> RangeSet<StableRowIndex> {
+        unimplemented!()
+    }
 
-        fn for_each_logical_line_in_stable_range_mut(
-            &self,
-            lines: Range<StableRowIndex>,
-            for_line: &mut dyn ForEachPaneLogicalLine,
-        ) {
-            crate::pane::impl_for_each_logical_line_via_get_logical_lines(self, lines, for_line)
+    fn with_lines_mut(
+        &self,
+        stable_range: Range<StableRowIndex>,
+        with_lines: &mut dyn WithPaneLines,
+    ) {
+        let mut line_refs = vec![];
+        let mut lines = self.lines.lock();
+        for line in lines
+            .iter_mut()
+            .skip(stable_range.start as usize)
+            .take((stable_range.end - stable_range.start) as usize)
+        {
+            line_refs.push(line);
         }
+        with_lines.with_lines_mut(stable_range.start, &mut line_refs);
+    }
 
-        fn get_logical_lines(&self, lines: Range<StableRowIndex>) -> Vec<LogicalLine> {
-            crate::pane::impl_get_logical_lines_via_get_lines(self, lines)
-        }
+    fn for_each_logical_line_in_stable_range_mut(
+        &self,
+        lines: Range<StableRowIndex>,
+        for_line: &mut dyn ForEachPaneLogicalLine,
+    ) {
+        crate::pane::impl_for_each_logical_line_via_get_logical_lines(self, lines, for_line)
+    }
 
-        fn get_lines(&self, lines: Range<StableRowIndex>) -> (StableRowIndex, Vec<Line>) {
-            let first = lines.start;
-            (
-                first,
-                self.lines
-                    .lock()
-                    .iter()
-                    .skip(lines.start as usize)
-                    .take((lines.end - lines.start) as usize)
-                    .cloned()
-                    .collect(),
-            )
-        }
-        fn get_dimensions(&self) -> RenderableDimensions {
-            unimplemented!()
-        }
+    fn get_logical_lines(&self, lines: Range<StableRowIndex>) -> Vec<LogicalLine> {
+        crate::pane::impl_get_logical_lines_via_get_lines(self, lines)
+    }
 
-        fn get_title(&self) -> String {
-            unimplemented!()
-        }
-        fn send_paste(&self, _: &str) -> anyhow::Result<()> {
-            unimplemented!()
-        }
-        fn reader(&self) -> anyhow::Result<Option<Box<dyn std::io::Read + Send>>> {
-            Ok(None)
-        }
-        fn writer(&self) -> MappedMutexGuard<dyn std::io::Write> {
-            unimplemented!()
-        }
-        fn resize(&self, _: TerminalSize) -> anyhow::Result<()> {
-            unimplemented!()
+    fn get_lines(&self, lines: Range<StableRowIndex>) -> (StableRowIndex, Vec<Line>) {
+        let first = lines.start;
+        (
+            first,
+            self.lines
+                .lock()
+                .iter()
+                .skip(lines.start as usize)
+                .take((lines.end - lines.start) as usize)
+                .cloned()
+                .collect(),
+        )
+    }
+    fn get_dimensions(&self) -> RenderableDimensions {
+        let scrollback_rows = self.lines.lock().len();
+        let viewport_rows = self.viewport_rows.min(scrollback_rows);
+        RenderableDimensions {
+            cols: 20,
+            viewport_rows,
+            scrollback_rows,
+            physical_top: (scrollback_rows - viewport_rows) as StableRowIndex,
+            scrollback_top: 0,
+            dpi: 0,
+            pixel_width: 0,
+            pixel_height: 0,
+            reverse_video: false,
         }
+    }
 
-        fn mouse_event(&self, _: MouseEvent) -> anyhow::Result<()> {
-            unimplemented!()
-        }
-        fn is_dead(&self) -> bool {
-            unimplemented!()
-        }
-        fn palette(&self) -> ColorPalette {
-            unimplemented!()
-        }
-        fn domain_id(&self) -> DomainId {
-            unimplemented!()
-        }
+    fn get_title(&self) -> String {
+        unimplemented!()
+    }
+    fn send_paste(&self, _: &str) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+    fn reader(&self) -> anyhow::Result<Option<Box<dyn std::io::Read + Send>>> {
+        Ok(None)
+    }
+    fn writer(&self) -> MappedMutexGuard<dyn std::io::Write> {
+        parking_lot::MutexGuard::map(self.written.lock(), |writer| {
+            let w: &mut dyn std::io::Write = writer;
+            w
+        })
+    }
+    fn resize(&self, _: TerminalSize) -> anyhow::Result<()> {
+        Ok(())
+    }
 
-        fn is_mouse_grabbed(&self) -> bool {
-            false
-        }
-        fn is_alt_screen_active(&self) -> bool {
-            false
-        }
-        fn get_current_working_dir(&self) -> Option<Url> {
-            None
-        }
-        fn key_down(&self, _: KeyCode, _: KeyModifiers) -> anyhow::Result<()> {
-            unimplemented!()
-        }
-        fn key_up(&self, _: KeyCode, _: KeyModifiers) -> anyhow::Result<()> {
-            unimplemented!()
-        }
+    fn mouse_event(&self, _: MouseEvent) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+    fn is_dead(&self) -> bool {
+        false
+    }
+    fn palette(&self) -> ColorPalette {
+        unimplemented!()
+    }
+    fn domain_id(&self) -> DomainId {
+        self.domain_id
+    }
+
+    fn is_mouse_grabbed(&self) -> bool {
+        false
     }
+    fn is_alt_screen_active(&self) -> bool {
+        false
+    }
+    fn get_current_working_dir(&self) -> Option<Url> {
+        None
+    }
+    fn erase_scrollback(&self, erase_mode: ScrollbackEraseMode) {
+        self.erased.lock().push(erase_mode);
+    }
+    fn key_down(&self, key: KeyCode, mods: KeyModifiers) -> anyhow::Result<()> {
+        self.keys.lock().push((key, mods));
+        Ok(())
+    }
+    fn key_up(&self, _: KeyCode, _: KeyModifiers) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use k9::snapshot;
+    use parking_lot::Mutex;
+    use std::borrow::Cow;
+    use termwiz::surface::SEQ_ZERO;
 
     fn physical_lines_from_text(text: &str, width: usize) -> Vec<Line> {
         let mut physical_lines = vec![];
@@ -693,6 +811,8 @@ mod test {
 
         let pane = FakePane {
             lines: Mutex::new(physical_lines),
+            viewport_rows: 5,
+            ..Default::default()
         };
 
         let logical = pane.get_logical_lines(0..30);
@@ -1024,6 +1144,49 @@ mod test {
         }
     }
 
+    #[test]
+    fn logical_line_coordinates() {
+        let text = "a soft wrapped line that spans rows\nshort\nanother wrapped line";
+        let pane = FakePane {
+            lines: Mutex::new(physical_lines_from_text(text, 10)),
+            viewport_rows: 5,
+            ..Default::default()
+        };
+
+        let logical = pane.get_logical_lines(0..20);
+        assert_eq!(
+            summarize_logical_lines(&logical),
+            vec![
+                (0, Cow::Borrowed("a soft wrapped line that spans rows")),
+                (4, Cow::Borrowed("short")),
+                (5, Cow::Borrowed("another wrapped line")),
+            ]
+        );
+
+        // Every logical cell maps back to the physical cell that holds it
+        let (_, physical) = pane.get_lines(0..20);
+        for line in &logical {
+            for x in 0..line.logical.len() {
+                let (y, phys_x) = line.logical_x_to_physical_coord(x);
+                assert!(line.contains_y(y));
+                assert_eq!(line.xy_to_logical_x(phys_x, y), x);
+                assert_eq!(
+                    physical[y as usize].get_cell(phys_x).unwrap().str(),
+                    line.logical.get_cell(x).unwrap().str()
+                );
+            }
+        }
+
+        // Asking for a row in the middle of a wrapped run yields
+        // the whole of its logical line
+        let middle = pane.get_logical_lines(2..3);
+        assert_eq!(
+            summarize_logical_lines(&middle),
+            vec![(0, Cow::Borrowed("a soft wrapped line that spans rows"))]
+        );
+        assert_eq!(middle[0].physical_lines.len(), 4);
+    }
+
     #[test]
     fn double_click() {
         let attr = Default::default();
@@ -1054,4 +1217,57 @@ mod test {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn dump_pane() {
+        let mut physical_lines = physical_lines_from_text("one\ntwo\nthree", 20);
+        let mut bold = termwiz::cell::CellAttributes::default();
+        bold.set_intensity(termwiz::cell::Intensity::Bold);
+        physical_lines.push(Line::from_text("four", &bold, 1, None));
+
+        let pane = FakePane {
+            lines: Mutex::new(physical_lines),
+            viewport_rows: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            dump_pane_content(&pane, false, DumpFormat::Text).unwrap(),
+            "three\nfour\n"
+        );
+        assert_eq!(
+            dump_pane_content(&pane, true, DumpFormat::Text).unwrap(),
+            "one\ntwo\nthree\nfour\n"
+        );
+
+        let ansi = dump_pane_content(&pane, false, DumpFormat::Ansi).unwrap();
+        assert!(ansi.starts_with("three\r\n"), "{ansi:?}");
+        assert!(ansi.contains("\x1b["), "{ansi:?}");
+        assert!(ansi.contains("four"), "{ansi:?}");
+
+        let dir = std::env::temp_dir().join(format!("wezterm-dump-pane-{}", std::process::id()));
+        let path = dump_pane_to_file(&pane, true, DumpFormat::Text, &dir).unwrap();
+        assert_eq!(path.extension().unwrap(), "txt");
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(content, "one\ntwo\nthree\nfour\n");
+    }
+
+    #[test]
+    fn clear_screen_and_scrollback_redraw() {
+        let pane = FakePane::default();
+        clear_screen_and_scrollback(&pane, false).unwrap();
+        assert_eq!(
+            *pane.erased.lock(),
+            vec![ScrollbackEraseMode::ScrollbackAndViewport]
+        );
+        assert!(pane.keys.lock().is_empty());
+
+        clear_screen_and_scrollback(&pane, true).unwrap();
+        assert_eq!(pane.erased.lock().len(), 2);
+        assert_eq!(
+            *pane.keys.lock(),
+            vec![(KeyCode::Char('l'), KeyModifiers::CTRL)]
+        );
+    }
 }
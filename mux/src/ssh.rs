@@ -1,5 +1,7 @@
 use crate::connui::ConnectionUI;
-use crate::domain::{alloc_domain_id, Domain, DomainId, DomainState, WriterWrapper};
+use crate::domain::{
+    alloc_domain_id, apply_default_env, Domain, DomainId, DomainState, WriterWrapper,
+};
 use crate::localpane::LocalPane;
 use crate::pane::{alloc_pane_id, Pane, PaneId};
 use crate::Mux;
@@ -251,13 +253,11 @@ impl RemoteSshDomain {
         command_dir: Option<String>,
     ) -> anyhow::Result<(Option<String>, HashMap<String, String>)> {
         let config = config::configuration();
-        let cmd = match command {
-            Some(mut cmd) => {
-                config.apply_cmd_defaults(&mut cmd, None);
-                cmd
-            }
-            None => config.build_prog(None, self.dom.default_prog.as_ref(), None)?,
-        };
+        let mut cmd = resolve_spawn_command(command, self.dom.default_prog.as_ref());
+        config.apply_cmd_defaults(&mut cmd, None);
+        // An explicit command has already had the domain defaults
+        // merged in by the mux; do the same for the default program here.
+        apply_default_env(&mut cmd, &self.dom.default_env);
         let mut env: HashMap<String, String> = cmd
             .iter_extra_env_as_str()
             .map(|(k, v)| (k.to_string(), v.to_string()))
@@ -269,57 +269,7 @@ impl RemoteSshDomain {
         // existing terminal connection
         env.insert("WEZTERM_REMOTE_PANE".to_string(), pane_id.to_string());
 
-        fn build_env_command(
-            dir: Option<String>,
-            cmd: &CommandBuilder,
-            env: &HashMap<String, String>,
-        ) -> anyhow::Result<String> {
-            // "Soft" chdir: if it doesn't exist then it doesn't matter
-            let cd_cmd = if let Some(dir) = dir {
-                format!("cd {};", shell_words::quote(&dir))
-            } else if let Some(dir) = cmd.get_cwd() {
-                let dir = dir.to_str().context("converting cwd to string")?;
-                format!("cd {};", shell_words::quote(&dir))
-            } else {
-                String::new()
-            };
-
-            let mut env_cmd = vec!["env".to_string()];
-
-            for (k, v) in env {
-                env_cmd.push(format!("{}={}", k, v));
-            }
-
-            let cmd = if cmd.is_default_prog() {
-                // We'd like to spawn a login shell, but since we are invoking env
-                // we end up in a regular shell.
-                // This guff tries to find a reasonably portable way to execute
-                // the shell as a login shell.
-                // Per: <https://unix.stackexchange.com/a/666850/123914>
-                // the most portable way is to use perl, but in case perl is not
-                // installed, zsh, bash and ksh all support `exec -a`.
-                // Other shells may support `exec -a` but there isn't a simple
-                // way to test for them, so we assume that if we have one of those
-                // three that we can use it, otherwise we fall back to just running
-                // the shell directly.
-                let login_shell = "command -v perl > /dev/null && \
-                  exec perl -e 'use File::Basename; $shell = basename($ENV{SHELL}); exec {$ENV{SHELL}} \"-$shell\"'; \
-                  case \"$SHELL\" in */zsh|*/bash|*/ksh ) exec -a \"-$(basename $SHELL)\" $SHELL ;; esac ; \
-                  exec $SHELL";
-
-                format!("$SHELL -c {}", shell_words::quote(login_shell))
-            } else {
-                cmd.as_unix_command_line()?
-            };
-
-            Ok(cd_cmd + &shell_words::join(env_cmd) + " " + &cmd)
-        }
-
-        let command_line = match (cmd.is_default_prog(), self.dom.assume_shell, command_dir) {
-            (_, Shell::Posix, dir) => Some(build_env_command(dir, &cmd, &env)?),
-            (true, _, _) => None,
-            (false, _, _) => Some(cmd.as_unix_command_line()?),
-        };
+        let command_line = ssh_command_line(&cmd, self.dom.assume_shell, command_dir, &env)?;
 
         Ok((command_line, env))
     }
@@ -359,17 +309,7 @@ impl RemoteSshDomain {
 
         let (child_tx, child_rx) = channel();
 
-        let child = Box::new(WrappedSshChild {
-            status: None,
-            rx: child_rx,
-            exited: None,
-            killer: WrappedSshChildKiller {
-                inner: Arc::new(Mutex::new(KillerInner {
-                    killer: None,
-                    pending_kill: false,
-                })),
-            },
-        });
+        let child = Box::new(WrappedSshChild::new(child_rx, self.dom.exit_grace_period));
 
         let (pty_tx, pty_rx) = channel();
 
@@ -417,6 +357,85 @@ struct StartNewSessionResult {
     writer: BoxedWriter,
 }
 
+/// Determines the command to run for a pane spawned in an ssh domain.
+/// An explicit command, such as a `SpawnCommand` with `args`, is used
+/// as-is.  Otherwise the domain's `default_prog`, if any, is used in
+/// place of the remote login shell, preserving the cwd and environment
+/// of the spawn request.
+fn resolve_spawn_command(
+    command: Option<CommandBuilder>,
+    default_prog: Option<&Vec<String>>,
+) -> CommandBuilder {
+    let mut cmd = command.unwrap_or_else(CommandBuilder::new_default_prog);
+    if cmd.is_default_prog() {
+        if let Some(prog) = default_prog.filter(|prog| !prog.is_empty()) {
+            *cmd.get_argv_mut() = prog.iter().map(Into::into).collect();
+        }
+    }
+    cmd
+}
+
+/// Computes the command line to pass to `request_pty` for `cmd`.
+/// Returns None when the remote login shell should be used.
+fn ssh_command_line(
+    cmd: &CommandBuilder,
+    assume_shell: Shell,
+    command_dir: Option<String>,
+    env: &HashMap<String, String>,
+) -> anyhow::Result<Option<String>> {
+    fn build_env_command(
+        dir: Option<String>,
+        cmd: &CommandBuilder,
+        env: &HashMap<String, String>,
+    ) -> anyhow::Result<String> {
+        // "Soft" chdir: if it doesn't exist then it doesn't matter
+        let cd_cmd = if let Some(dir) = dir {
+            format!("cd {};", shell_words::quote(&dir))
+        } else if let Some(dir) = cmd.get_cwd() {
+            let dir = dir.to_str().context("converting cwd to string")?;
+            format!("cd {};", shell_words::quote(&dir))
+        } else {
+            String::new()
+        };
+
+        let mut env_cmd = vec!["env".to_string()];
+
+        for (k, v) in env {
+            env_cmd.push(format!("{}={}", k, v));
+        }
+
+        let cmd = if cmd.is_default_prog() {
+            // We'd like to spawn a login shell, but since we are invoking env
+            // we end up in a regular shell.
+            // This guff tries to find a reasonably portable way to execute
+            // the shell as a login shell.
+            // Per: <https://unix.stackexchange.com/a/666850/123914>
+            // the most portable way is to use perl, but in case perl is not
+            // installed, zsh, bash and ksh all support `exec -a`.
+            // Other shells may support `exec -a` but there isn't a simple
+            // way to test for them, so we assume that if we have one of those
+            // three that we can use it, otherwise we fall back to just running
+            // the shell directly.
+            let login_shell = "command -v perl > /dev/null && \
+              exec perl -e 'use File::Basename; $shell = basename($ENV{SHELL}); exec {$ENV{SHELL}} \"-$shell\"'; \
+              case \"$SHELL\" in */zsh|*/bash|*/ksh ) exec -a \"-$(basename $SHELL)\" $SHELL ;; esac ; \
+              exec $SHELL";
+
+            format!("$SHELL -c {}", shell_words::quote(login_shell))
+        } else {
+            cmd.as_unix_command_line()?
+        };
+
+        Ok(cd_cmd + &shell_words::join(env_cmd) + " " + &cmd)
+    }
+
+    Ok(match (cmd.is_default_prog(), assume_shell, command_dir) {
+        (_, Shell::Posix, dir) => Some(build_env_command(dir, cmd, env)?),
+        (true, _, _) => None,
+        (false, _, _) => Some(cmd.as_unix_command_line()?),
+    })
+}
+
 /// Carry out the authentication process and create the initial pty.
 fn connect_ssh_session(
     session: Session,
@@ -790,6 +809,10 @@ impl Domain for RemoteSshDomain {
         &self.name
     }
 
+    fn default_env(&self) -> HashMap<String, String> {
+        self.dom.default_env.clone()
+    }
+
     async fn attach(&self, _window_id: Option<crate::WindowId>) -> anyhow::Result<()> {
         Ok(())
     }
@@ -824,23 +847,82 @@ struct WrappedSshChildKiller {
     inner: Arc<Mutex<KillerInner>>,
 }
 
+/// Tracks how long we have been without a child, so that a brief
+/// interruption doesn't immediately mark the pane as exited
+#[derive(Debug)]
+struct ExitGrace {
+    period: Duration,
+    /// When we lost the child
+    lost_at: Option<Instant>,
+}
+
+impl ExitGrace {
+    fn new(period: Duration) -> Self {
+        Self {
+            period,
+            lost_at: None,
+        }
+    }
+
+    /// Notes that the child was lost, returning true if it has been
+    /// lost for at least the grace period
+    fn lost(&mut self) -> bool {
+        let lost_at = *self.lost_at.get_or_insert_with(Instant::now);
+        lost_at.elapsed() >= self.period
+    }
+
+    /// Returns how much longer we are prepared to wait for the child
+    /// to come back, or None if it hasn't been lost
+    fn remaining(&self) -> Option<Duration> {
+        self.lost_at
+            .map(|lost_at| self.period.saturating_sub(lost_at.elapsed()))
+    }
+
+    fn recovered(&mut self) {
+        self.lost_at.take();
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct WrappedSshChild {
     status: Option<AsyncReceiver<ExitStatus>>,
     rx: Receiver<SshChildProcess>,
     exited: Option<ExitStatus>,
     killer: WrappedSshChildKiller,
+    grace: ExitGrace,
 }
 
 impl WrappedSshChild {
+    fn new(rx: Receiver<SshChildProcess>, grace_period: Duration) -> Self {
+        Self {
+            status: None,
+            rx,
+            exited: None,
+            killer: WrappedSshChildKiller {
+                inner: Arc::new(Mutex::new(KillerInner {
+                    killer: None,
+                    pending_kill: false,
+                })),
+            },
+            grace: ExitGrace::new(grace_period),
+        }
+    }
+
     fn check_connected(&mut self) {
         if self.status.is_none() {
             match self.rx.try_recv() {
                 Ok(c) => {
+                    self.grace.recovered();
                     self.got_child(c);
                 }
-                Err(TryRecvError::Empty) => {}
-                Err(err) => {
+                Err(TryRecvError::Empty) => {
+                    if self.grace.remaining() == Some(Duration::ZERO) {
+                        self.exited.replace(ExitStatus::with_exit_code(1));
+                    }
+                }
+                Err(err @ TryRecvError::Disconnected) => {
+                    // No child can arrive now, so there is nothing to
+                    // wait for
                     log::debug!("WrappedSshChild::check_connected err: {:#?}", err);
                     self.exited.replace(ExitStatus::with_exit_code(1));
                 }
@@ -848,6 +930,16 @@ impl WrappedSshChild {
         }
     }
 
+    /// Called when we failed to obtain the exit status of the child.
+    /// Rather than immediately treating that as an exit, wait for a
+    /// replacement child to arrive for up to the grace period.
+    fn lost_child(&mut self) {
+        self.status.take();
+        if self.grace.lost() {
+            self.exited.replace(ExitStatus::with_exit_code(1));
+        }
+    }
+
     fn got_child(&mut self, mut child: SshChildProcess) {
         {
             let mut killer = self.killer.inner.lock().unwrap();
@@ -881,52 +973,55 @@ impl portable_pty::Child for WrappedSshChild {
         if let Some(rx) = self.status.as_mut() {
             match rx.try_recv() {
                 Ok(status) => {
-                    self.exited.replace(status.clone());
-                    Ok(Some(status))
+                    self.exited.replace(status);
                 }
-                Err(smol::channel::TryRecvError::Empty) => Ok(None),
+                Err(smol::channel::TryRecvError::Empty) => {}
                 Err(err) => {
                     log::debug!("WrappedSshChild::try_wait err: {:#?}", err);
-                    let status = ExitStatus::with_exit_code(1);
-                    self.exited.replace(status.clone());
-                    Ok(Some(status))
+                    self.lost_child();
                 }
             }
-        } else {
-            Ok(None)
         }
+
+        Ok(self.exited.clone())
     }
 
     fn wait(&mut self) -> std::io::Result<portable_pty::ExitStatus> {
-        if let Some(status) = self.exited.as_ref() {
-            return Ok(status.clone());
-        }
+        loop {
+            if let Some(status) = self.exited.as_ref() {
+                return Ok(status.clone());
+            }
 
-        if self.status.is_none() {
-            match smol::block_on(async { self.rx.recv() }) {
-                Ok(c) => {
-                    self.got_child(c);
-                }
-                Err(err) => {
-                    log::debug!("WrappedSshChild err: {:#?}", err);
-                    let status = ExitStatus::with_exit_code(1);
-                    self.exited.replace(status.clone());
-                    return Ok(status);
+            if self.status.is_none() {
+                let child = match self.grace.remaining() {
+                    Some(remaining) => self.rx.recv_timeout(remaining).map_err(|err| {
+                        log::debug!("WrappedSshChild err: {:#?}", err);
+                    }),
+                    None => self.rx.recv().map_err(|err| {
+                        log::debug!("WrappedSshChild err: {:#?}", err);
+                    }),
+                };
+                match child {
+                    Ok(c) => {
+                        self.grace.recovered();
+                        self.got_child(c);
+                    }
+                    Err(()) => {
+                        self.exited.replace(ExitStatus::with_exit_code(1));
+                        continue;
+                    }
                 }
             }
-        }
 
-        let rx = self.status.as_mut().unwrap();
-        match smol::block_on(rx.recv()) {
-            Ok(status) => {
-                self.exited.replace(status.clone());
-                Ok(status)
-            }
-            Err(err) => {
-                log::error!("WrappedSshChild err: {:#?}", err);
-                let status = ExitStatus::with_exit_code(1);
-                self.exited.replace(status.clone());
-                Ok(status)
+            let rx = self.status.as_mut().unwrap();
+            match smol::block_on(rx.recv()) {
+                Ok(status) => {
+                    self.exited.replace(status);
+                }
+                Err(err) => {
+                    log::error!("WrappedSshChild err: {:#?}", err);
+                    self.lost_child();
+                }
             }
         }
     }
@@ -1146,3 +1241,140 @@ impl std::io::Read for PtyReader {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns a child whose exit status can no longer be received,
+    /// along with the sender for its replacement
+    fn child_without_status(grace_period: Duration) -> (Sender<SshChildProcess>, WrappedSshChild) {
+        let (tx, rx) = channel();
+        let mut child = WrappedSshChild::new(rx, grace_period);
+        let (_status_tx, status_rx) = bounded(1);
+        child.status.replace(status_rx);
+        (tx, child)
+    }
+
+    #[test]
+    fn lost_child_within_grace_period() {
+        let (_tx, mut child) = child_without_status(Duration::from_secs(60));
+        assert!(child.try_wait().unwrap().is_none());
+        assert!(child.try_wait().unwrap().is_none());
+        assert!(child.grace.remaining().is_some());
+    }
+
+    #[test]
+    fn lost_child_without_grace_period() {
+        let (_tx, mut child) = child_without_status(Duration::ZERO);
+        let status = child.try_wait().unwrap().expect("child to have exited");
+        assert_eq!(status.exit_code(), 1);
+
+        let (_tx, mut child) = child_without_status(Duration::ZERO);
+        assert_eq!(child.wait().unwrap().exit_code(), 1);
+    }
+
+    #[test]
+    fn disconnect_skips_grace_period() {
+        let (tx, mut child) = child_without_status(Duration::from_secs(60));
+        assert!(child.try_wait().unwrap().is_none());
+        // Once the sender is gone no replacement child can arrive
+        drop(tx);
+        let status = child.try_wait().unwrap().expect("child to have exited");
+        assert_eq!(status.exit_code(), 1);
+
+        let (tx, rx) = channel();
+        drop(tx);
+        let mut child = WrappedSshChild::new(rx, Duration::from_secs(60));
+        assert_eq!(child.wait().unwrap().exit_code(), 1);
+    }
+
+    #[test]
+    fn recovery_within_grace_period() {
+        let period = Duration::from_secs(60);
+        let mut grace = ExitGrace::new(period);
+        assert_eq!(grace.remaining(), None);
+
+        // Lost the child a while ago, but not long enough to give up
+        grace.lost_at = Instant::now().checked_sub(Duration::from_secs(50));
+        assert!(!grace.lost());
+
+        // Recovering resets the clock, so that a later loss gets
+        // the full grace period again
+        grace.recovered();
+        assert_eq!(grace.remaining(), None);
+        assert!(!grace.lost());
+        assert!(grace.remaining().unwrap() > Duration::from_secs(50));
+
+        grace.lost_at = Instant::now().checked_sub(period);
+        assert!(grace.lost());
+        assert_eq!(grace.remaining(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn spawn_command_line_for_request_pty() {
+        let env = HashMap::new();
+        let default_prog = vec!["zsh".to_string(), "-l".to_string()];
+
+        // An explicit command is passed through, regardless of the domain default
+        let cmd = resolve_spawn_command(
+            Some(CommandBuilder::from_argv(vec![
+                "htop".into(),
+                "-d".into(),
+                "5".into(),
+            ])),
+            Some(&default_prog),
+        );
+        assert_eq!(
+            ssh_command_line(&cmd, Shell::Unknown, None, &env).unwrap(),
+            Some("htop -d 5".to_string())
+        );
+
+        // An empty command uses the domain default
+        let cmd = resolve_spawn_command(
+            Some(CommandBuilder::new_default_prog()),
+            Some(&default_prog),
+        );
+        assert_eq!(
+            ssh_command_line(&cmd, Shell::Unknown, None, &env).unwrap(),
+            Some("zsh -l".to_string())
+        );
+        let cmd = resolve_spawn_command(None, Some(&default_prog));
+        assert_eq!(
+            ssh_command_line(&cmd, Shell::Unknown, None, &env).unwrap(),
+            Some("zsh -l".to_string())
+        );
+
+        // and without a domain default, the remote login shell
+        let cmd = resolve_spawn_command(None, None);
+        assert_eq!(
+            ssh_command_line(&cmd, Shell::Unknown, None, &env).unwrap(),
+            None
+        );
+    }
+    #[test]
+    fn default_env_reaches_request_pty() {
+        config::use_test_configuration();
+        let mut dom = SshDomain {
+            name: "test".to_string(),
+            ..Default::default()
+        };
+        dom.default_env
+            .insert("LANG".to_string(), "en_US.UTF-8".to_string());
+        dom.default_env
+            .insert("EDITOR".to_string(), "vi".to_string());
+        let domain = RemoteSshDomain::with_ssh_domain(&dom).unwrap();
+
+        // The domain defaults apply to the default program
+        let (_, env) = domain.build_command(1, None, None).unwrap();
+        assert_eq!(env.get("LANG").map(String::as_str), Some("en_US.UTF-8"));
+        assert_eq!(env.get("EDITOR").map(String::as_str), Some("vi"));
+
+        // while variables set by the command take precedence
+        let mut cmd = CommandBuilder::new("htop");
+        cmd.env("EDITOR", "nano");
+        let (_, env) = domain.build_command(1, Some(cmd), None).unwrap();
+        assert_eq!(env.get("LANG").map(String::as_str), Some("en_US.UTF-8"));
+        assert_eq!(env.get("EDITOR").map(String::as_str), Some("nano"));
+    }
+}
@@ -45,6 +45,8 @@ struct TabInner {
     active: usize,
     zoomed: Option<Arc<dyn Pane>>,
     title: String,
+    /// A title assigned by the user that takes precedence over `title`
+    title_override: Option<String>,
     recency: Recency,
 }
 
@@ -516,20 +518,33 @@ impl Tab {
         }
     }
 
+    /// Returns the title of the tab; the override title, if any,
+    /// takes precedence over the title assigned via `set_title`.
     pub fn get_title(&self) -> String {
-        self.inner.lock().title.clone()
+        self.inner.lock().effective_title().to_string()
     }
 
     pub fn set_title(&self, title: &str) {
         let mut inner = self.inner.lock();
         if inner.title != title {
+            let prior = inner.effective_title().to_string();
             inner.title = title.to_string();
-            Mux::try_get().map(|mux| {
-                mux.notify(MuxNotification::TabTitleChanged {
-                    tab_id: inner.id,
-                    title: title.to_string(),
-                })
-            });
+            inner.notify_title_changed(&prior);
+        }
+    }
+
+    pub fn get_title_override(&self) -> Option<String> {
+        self.inner.lock().title_override.clone()
+    }
+
+    /// Pins the title of the tab to `title`, ignoring subsequent calls
+    /// to `set_title` until the override is cleared by passing `None`.
+    pub fn set_title_override(&self, title: Option<String>) {
+        let mut inner = self.inner.lock();
+        if inner.title_override != title {
+            let prior = inner.effective_title().to_string();
+            inner.title_override = title;
+            inner.notify_title_changed(&prior);
         }
     }
 
@@ -763,10 +778,27 @@ impl TabInner {
             active: 0,
             zoomed: None,
             title: String::new(),
+            title_override: None,
             recency: Recency::default(),
         }
     }
 
+    fn effective_title(&self) -> &str {
+        self.title_override.as_deref().unwrap_or(&self.title)
+    }
+
+    fn notify_title_changed(&self, prior: &str) {
+        let title = self.effective_title();
+        if title != prior {
+            Mux::try_get().map(|mux| {
+                mux.notify(MuxNotification::TabTitleChanged {
+                    tab_id: self.id,
+                    title: title.to_string(),
+                })
+            });
+        }
+    }
+
     fn sync_with_pane_tree<F>(&mut self, size: TerminalSize, root: PaneNode, mut make_pane: F)
     where
         F: FnMut(PaneEntry) -> Arc<dyn Pane>,
@@ -2505,6 +2537,24 @@ mod test {
         assert_eq!(600, panes[2].pixel_height);
     }
 
+    #[test]
+    fn title_override() {
+        let tab = Tab::new(&TerminalSize::default());
+        tab.set_title("from app");
+        assert_eq!(tab.get_title(), "from app");
+
+        tab.set_title_override(Some("pinned".to_string()));
+        assert_eq!(tab.get_title(), "pinned");
+
+        tab.set_title("updated by app");
+        assert_eq!(tab.get_title(), "pinned");
+        assert_eq!(tab.get_title_override(), Some("pinned".to_string()));
+
+        tab.set_title_override(None);
+        assert_eq!(tab.get_title(), "updated by app");
+        assert_eq!(tab.get_title_override(), None);
+    }
+
     fn is_send_and_sync<T: Send + Sync>() -> bool {
         true
     }
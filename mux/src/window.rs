@@ -11,8 +11,12 @@ pub struct Window {
     tabs: Vec<Arc<Tab>>,
     active: usize,
     last_active: Option<TabId>,
+    /// The ids of the tabs that have been activated, most recent first
+    mru: Vec<TabId>,
     workspace: String,
     title: String,
+    /// A title assigned by the user that takes precedence over `title`
+    title_override: Option<String>,
     initial_position: Option<GuiPosition>,
 }
 
@@ -23,7 +27,9 @@ impl Window {
             tabs: vec![],
             active: 0,
             last_active: None,
+            mru: vec![],
             title: String::new(),
+            title_override: None,
             workspace: workspace.unwrap_or_else(|| Mux::get().active_workspace()),
             initial_position,
         }
@@ -39,7 +45,35 @@ impl Window {
 
     pub fn set_title(&mut self, title: &str) {
         if self.title != title {
+            let prior = self.get_title().to_string();
             self.title = title.to_string();
+            self.notify_title_changed(&prior);
+        }
+    }
+
+    /// Returns the title of the window; the override title, if any,
+    /// takes precedence over the title assigned via `set_title`.
+    pub fn get_title(&self) -> &str {
+        self.title_override.as_deref().unwrap_or(&self.title)
+    }
+
+    pub fn get_title_override(&self) -> Option<&str> {
+        self.title_override.as_deref()
+    }
+
+    /// Pins the title of the window to `title`, ignoring subsequent calls
+    /// to `set_title` until the override is cleared by passing `None`.
+    pub fn set_title_override(&mut self, title: Option<String>) {
+        if self.title_override != title {
+            let prior = self.get_title().to_string();
+            self.title_override = title;
+            self.notify_title_changed(&prior);
+        }
+    }
+
+    fn notify_title_changed(&self, prior: &str) {
+        let title = self.get_title();
+        if title != prior {
             Mux::try_get().map(|mux| {
                 mux.notify(MuxNotification::WindowTitleChanged {
                     window_id: self.id,
@@ -49,10 +83,6 @@ impl Window {
         }
     }
 
-    pub fn get_title(&self) -> &str {
-        &self.title
-    }
-
     pub fn set_workspace(&mut self, workspace: &str) {
         if workspace == self.workspace {
             return;
@@ -118,6 +148,30 @@ impl Window {
         None
     }
 
+    /// Returns the index of the first tab whose title contains `substring`.
+    /// If the tab has no explicitly assigned title, the title of its
+    /// active pane is used instead, matching what the tab bar displays.
+    pub fn idx_by_title(&self, substring: &str, case_insensitive: bool) -> Option<usize> {
+        let substring = if case_insensitive {
+            substring.to_lowercase()
+        } else {
+            substring.to_string()
+        };
+        self.tabs.iter().position(|tab| {
+            let mut title = tab.get_title();
+            if title.is_empty() {
+                if let Some(pane) = tab.get_active_pane() {
+                    title = pane.get_title();
+                }
+            }
+            if case_insensitive {
+                title.to_lowercase().contains(&substring)
+            } else {
+                title.contains(&substring)
+            }
+        })
+    }
+
     fn fixup_active_tab_after_removal(&mut self, active: Option<Arc<Tab>>) {
         let len = self.tabs.len();
         if let Some(active) = active {
@@ -160,6 +214,7 @@ impl Window {
             }
         }
         let tab = self.tabs.remove(idx);
+        self.mru.retain(|&id| id != tab.tab_id());
         self.fixup_active_tab_after_removal(active);
         tab
     }
@@ -200,6 +255,11 @@ impl Window {
     /// Make `idx` the active tab position.
     /// The saved tab id is not changed.
     pub fn set_active_without_saving(&mut self, idx: usize) {
+        self.do_set_active(idx);
+        self.invalidate();
+    }
+
+    fn do_set_active(&mut self, idx: usize) {
         assert!(idx < self.tabs.len());
         if self.active != idx {
             if let Some(tab) = self.tabs.get(self.active) {
@@ -209,9 +269,75 @@ impl Window {
             }
         }
         self.active = idx;
+
+        let tab_id = self.tabs[idx].tab_id();
+        self.mru.retain(|&id| id != tab_id);
+        self.mru.insert(0, tab_id);
+    }
+
+    /// Returns the tab indices ordered by how recently they were
+    /// activated, starting with the active tab.  Tabs that have never
+    /// been activated follow, in their positional order.
+    pub fn mru_tab_indices(&self) -> Vec<usize> {
+        let mut order = vec![];
+        if self.active < self.tabs.len() {
+            order.push(self.active);
+        }
+        for &tab_id in &self.mru {
+            if let Some(idx) = self.idx_by_id(tab_id) {
+                if !order.contains(&idx) {
+                    order.push(idx);
+                }
+            }
+        }
+        for idx in 0..self.tabs.len() {
+            if !order.contains(&idx) {
+                order.push(idx);
+            }
+        }
+        order
+    }
+
+    /// Returns the index of the tab that is `delta` steps away from
+    /// the active tab in most recently used order, wrapping around
+    /// at either end.  `1` is the previously used tab.
+    pub fn get_mru_tab_idx(&self, delta: isize) -> Option<usize> {
+        let order = self.mru_tab_indices();
+        if order.is_empty() {
+            return None;
+        }
+        let idx = delta.rem_euclid(order.len() as isize) as usize;
+        Some(order[idx])
+    }
+
+    /// Shift each tab one position to the right, with the last
+    /// tab moving to the front.  The active tab follows its content.
+    pub fn rotate_tabs_clockwise(&mut self) {
+        self.do_rotate_tabs(true);
+        self.invalidate();
+    }
+
+    /// Shift each tab one position to the left, with the first
+    /// tab moving to the end.  The active tab follows its content.
+    pub fn rotate_tabs_counter_clockwise(&mut self) {
+        self.do_rotate_tabs(false);
         self.invalidate();
     }
 
+    fn do_rotate_tabs(&mut self, clockwise: bool) {
+        let len = self.tabs.len();
+        if len < 2 {
+            return;
+        }
+        if clockwise {
+            self.tabs.rotate_right(1);
+            self.active = (self.active + 1) % len;
+        } else {
+            self.tabs.rotate_left(1);
+            self.active = (self.active + len - 1) % len;
+        }
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &Arc<Tab>> {
         self.tabs.iter()
     }
@@ -264,3 +390,137 @@ impl Window {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use wezterm_term::TerminalSize;
+
+    fn window_with_titles(titles: &[&str]) -> Window {
+        let size = TerminalSize::default();
+        Window {
+            id: 0,
+            tabs: titles
+                .iter()
+                .map(|title| {
+                    let tab = Tab::new(&size);
+                    tab.set_title(title);
+                    Arc::new(tab)
+                })
+                .collect(),
+            active: 0,
+            last_active: None,
+            mru: vec![],
+            workspace: "default".to_string(),
+            title: String::new(),
+            title_override: None,
+            initial_position: None,
+        }
+    }
+
+    #[test]
+    fn title_override() {
+        let mut window = window_with_titles(&[]);
+        window.set_title("from app");
+        assert_eq!(window.get_title(), "from app");
+
+        window.set_title_override(Some("pinned".to_string()));
+        assert_eq!(window.get_title(), "pinned");
+
+        window.set_title("updated by app");
+        assert_eq!(window.get_title(), "pinned");
+        assert_eq!(window.get_title_override(), Some("pinned"));
+
+        window.set_title_override(None);
+        assert_eq!(window.get_title(), "updated by app");
+        assert_eq!(window.get_title_override(), None);
+    }
+
+    #[test]
+    fn idx_by_title() {
+        let window = window_with_titles(&["editor", "Build Logs", "logs", "shell"]);
+
+        assert_eq!(window.idx_by_title("logs", false), Some(2));
+        assert_eq!(window.idx_by_title("logs", true), Some(1));
+        assert_eq!(window.idx_by_title("LOGS", true), Some(1));
+        assert_eq!(window.idx_by_title("LOGS", false), None);
+        assert_eq!(window.idx_by_title("sh", false), Some(3));
+        assert_eq!(window.idx_by_title("nope", true), None);
+    }
+
+    fn tab_titles(window: &Window) -> Vec<String> {
+        window.iter().map(|tab| tab.get_title()).collect()
+    }
+
+    #[test]
+    fn rotate_tabs() {
+        let mut window = window_with_titles(&["a", "b", "c"]);
+        window.active = 1;
+
+        window.do_rotate_tabs(true);
+        assert_eq!(tab_titles(&window), vec!["c", "a", "b"]);
+        assert_eq!(window.get_active().unwrap().get_title(), "b");
+
+        window.do_rotate_tabs(true);
+        assert_eq!(tab_titles(&window), vec!["b", "c", "a"]);
+        assert_eq!(window.get_active_idx(), 0);
+
+        window.do_rotate_tabs(false);
+        assert_eq!(tab_titles(&window), vec!["c", "a", "b"]);
+        assert_eq!(window.get_active().unwrap().get_title(), "b");
+
+        window.do_rotate_tabs(false);
+        window.do_rotate_tabs(false);
+        assert_eq!(tab_titles(&window), vec!["b", "c", "a"]);
+        assert_eq!(window.get_active_idx(), 0);
+    }
+
+    fn mru_titles(window: &Window) -> Vec<String> {
+        window
+            .mru_tab_indices()
+            .into_iter()
+            .map(|idx| window.get_by_idx(idx).unwrap().get_title())
+            .collect()
+    }
+
+    #[test]
+    fn mru_order() {
+        let mut window = window_with_titles(&["a", "b", "c", "d"]);
+
+        // Tabs that were never activated follow in positional order
+        assert_eq!(mru_titles(&window), vec!["a", "b", "c", "d"]);
+
+        window.do_set_active(2);
+        window.do_set_active(1);
+        window.do_set_active(3);
+        assert_eq!(mru_titles(&window), vec!["d", "b", "c", "a"]);
+
+        // Reactivating a tab moves it to the front
+        window.do_set_active(2);
+        assert_eq!(mru_titles(&window), vec!["c", "d", "b", "a"]);
+    }
+
+    #[test]
+    fn mru_stepping() {
+        let mut window = window_with_titles(&["a", "b", "c"]);
+        window.do_set_active(1);
+        window.do_set_active(2);
+        window.do_set_active(0);
+
+        // MRU order is now a, c, b
+        fn title(window: &Window, delta: isize) -> String {
+            let idx = window.get_mru_tab_idx(delta).unwrap();
+            window.get_by_idx(idx).unwrap().get_title()
+        }
+        assert_eq!(title(&window, 0), "a");
+        assert_eq!(title(&window, 1), "c");
+        assert_eq!(title(&window, 2), "b");
+        assert_eq!(title(&window, 3), "a");
+        assert_eq!(title(&window, -1), "b");
+
+        // Stepping back to the previous tab makes it the most recent
+        let idx = window.get_mru_tab_idx(1).unwrap();
+        window.do_set_active(idx);
+        assert_eq!(title(&window, 1), "a");
+    }
+}
@@ -12,6 +12,90 @@ pub enum NewlineCanon {
     CarriageReturnAndLineFeed,
 }
 
+/// The capabilities that the terminal advertises in response to
+/// device attribute queries
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceCapabilities {
+    pub sixel_graphics: bool,
+    pub selective_erase: bool,
+    pub windowing_extensions: bool,
+    pub ansi_color: bool,
+    /// Reported as the firmware version in the DA2 response.
+    /// Because our default TERM is xterm, this is considered to be
+    /// equivalent to xterm's patch level, with the following effects:
+    /// pv < 95 -> ttymouse=xterm
+    /// pv >= 95 < 277 -> ttymouse=xterm2
+    /// pv >= 277 -> ttymouse=sgr
+    /// pv >= 279 - xterm will probe for additional device settings.
+    pub firmware_version: u16,
+}
+
+impl Default for DeviceCapabilities {
+    fn default() -> Self {
+        Self {
+            sixel_graphics: true,
+            selective_erase: true,
+            windowing_extensions: true,
+            ansi_color: true,
+            firmware_version: 277,
+        }
+    }
+}
+
+impl DeviceCapabilities {
+    /// Returns the parameters of the primary device attributes (DA1)
+    /// response: the conformance level followed by the extensions
+    /// that are supported
+    pub fn primary_attributes(&self) -> Vec<u16> {
+        let mut params = vec![65]; // Vt500
+        for (supported, param) in [
+            (self.sixel_graphics, 4),
+            (self.selective_erase, 6),
+            (self.windowing_extensions, 18),
+            (self.ansi_color, 22), // vt525
+        ] {
+            if supported {
+                params.push(param);
+            }
+        }
+        params
+    }
+
+    /// Returns the parameters of the secondary device attributes (DA2)
+    /// response: Pp ; Pv ; Pc, where Pp=1 means vt220, Pv is the
+    /// firmware version and Pc is always 0.
+    pub fn secondary_attributes(&self) -> Vec<u16> {
+        vec![1, self.firmware_version, 0]
+    }
+}
+
+/// Controls whether applications may write to the clipboard
+/// using the OSC 52 escape sequence
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Osc52ClipboardPolicy {
+    /// The write is applied
+    #[default]
+    Allow,
+    /// The write is discarded
+    Deny,
+    /// The write is passed on to the clipboard, which is expected
+    /// to ask the user before applying it
+    Prompt,
+}
+
+/// Controls how control codes that are received while accumulating
+/// a tmux style `ESC k TITLE ST` title are treated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TmuxTitleTerminator {
+    /// Only ST completes the title; any control code abandons it
+    #[default]
+    StringTerminator,
+    /// A line feed also completes the title
+    LineFeed,
+    /// Any C0 control code also completes the title
+    AnyControl,
+}
+
 impl NewlineCanon {
     fn target(self) -> Option<&'static str> {
         match self {
@@ -164,6 +248,13 @@ pub trait TerminalConfiguration: Downcast + std::fmt::Debug + Send + Sync {
         NewlineCanon::default()
     }
 
+    /// Whether to remove any bracketed paste start and end markers
+    /// that are embedded in pasted text, so that the pasted text
+    /// cannot prematurely terminate a bracketed paste
+    fn filter_bracketed_paste_markers(&self) -> bool {
+        true
+    }
+
     fn alternate_buffer_wheel_scroll_speed(&self) -> u8 {
         3
     }
@@ -200,6 +291,19 @@ pub trait TerminalConfiguration: Downcast + std::fmt::Debug + Send + Sync {
         false
     }
 
+    /// Whether zero-width graphemes, such as a combining mark that
+    /// arrives separately from its base character, are appended to
+    /// the grapheme in the preceding cell rather than being elided
+    fn attach_zero_width_graphemes(&self) -> bool {
+        false
+    }
+
+    /// When true, BEL is ignored entirely; no `Alert::Bell` is
+    /// raised and nothing is logged
+    fn bell_disabled(&self) -> bool {
+        false
+    }
+
     fn debug_key_events(&self) -> bool {
         false
     }
@@ -222,6 +326,38 @@ pub trait TerminalConfiguration: Downcast + std::fmt::Debug + Send + Sync {
     fn log_unknown_escape_sequences(&self) -> bool {
         false
     }
+
+    /// Whether a backspace at the left margin moves the cursor to the
+    /// end of the prior line when reverse wraparound is not enabled
+    fn backspace_wraps_to_previous_line(&self) -> bool {
+        false
+    }
+
+    /// Which control codes, in addition to ST, complete a pending
+    /// tmux style `ESC k TITLE ST` title sequence
+    fn tmux_title_terminator(&self) -> TmuxTitleTerminator {
+        TmuxTitleTerminator::default()
+    }
+
+    /// The number of bytes of printable text that may be buffered
+    /// before it is applied to the screen, even though no control
+    /// code has been received to complete the run of text.
+    /// This bounds the memory used by very long runs of text.
+    fn print_buffer_flush_threshold(&self) -> usize {
+        4096
+    }
+
+    /// The capabilities to advertise in response to device
+    /// attribute queries
+    fn device_capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities::default()
+    }
+
+    /// Whether applications may set or clear the clipboard
+    /// using OSC 52
+    fn osc52_clipboard_policy(&self) -> Osc52ClipboardPolicy {
+        Osc52ClipboardPolicy::default()
+    }
 }
 impl_downcast!(TerminalConfiguration);
 
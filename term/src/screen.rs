@@ -382,6 +382,57 @@ impl Screen {
         }
     }
 
+    /// Insert `num_cols` blank columns at column `x` of each of the
+    /// specified rows.  Cells between `x` and the right margin are shifted
+    /// to the right, and those shifted past the right margin are lost.
+    pub fn insert_columns(
+        &mut self,
+        x: usize,
+        rows: Range<VisibleRowIndex>,
+        right_margin: usize,
+        num_cols: usize,
+        seqno: SequenceNo,
+        blank_attr: CellAttributes,
+    ) {
+        let phys_cols = self.physical_cols;
+        for y in rows {
+            let line_idx = self.phys_row(y);
+            let line = self.line_mut(line_idx);
+            for _ in 0..num_cols {
+                line.insert_cell(
+                    x,
+                    Cell::blank_with_attrs(blank_attr.clone()),
+                    right_margin,
+                    seqno,
+                );
+            }
+            if line.len() > phys_cols {
+                line.resize(phys_cols, seqno);
+            }
+        }
+    }
+
+    /// Delete `num_cols` columns at column `x` of each of the specified
+    /// rows.  Cells between `x` and the right margin are shifted to the
+    /// left, and blank cells are inserted at the right margin.
+    pub fn delete_columns(
+        &mut self,
+        x: usize,
+        rows: Range<VisibleRowIndex>,
+        right_margin: usize,
+        num_cols: usize,
+        seqno: SequenceNo,
+        blank_attr: CellAttributes,
+    ) {
+        for y in rows {
+            let line_idx = self.phys_row(y);
+            let line = self.line_mut(line_idx);
+            for _ in 0..num_cols {
+                line.erase_cell_with_margin(x, right_margin, seqno, blank_attr.clone());
+            }
+        }
+    }
+
     pub fn erase_cell(
         &mut self,
         x: usize,
@@ -447,6 +498,33 @@ impl Screen {
         line.fill_range(cols, &Cell::blank_with_attrs(attr.clone()), seqno);
     }
 
+    /// Like `clear_line`, but leaves cells that have been marked as
+    /// protected by DECSCA untouched.  Used to implement DECSED and DECSEL.
+    pub fn clear_unprotected(
+        &mut self,
+        y: VisibleRowIndex,
+        cols: Range<usize>,
+        attr: &CellAttributes,
+        seqno: SequenceNo,
+    ) {
+        let line_idx = self.phys_row(y);
+        let line = self.line_mut(line_idx);
+        let protected: Vec<bool> = line
+            .cells_mut()
+            .iter()
+            .map(|cell| cell.attrs().protected())
+            .collect();
+        let blank = Cell::blank_with_attrs(attr.clone());
+        for x in cols {
+            match protected.get(x) {
+                Some(false) => line.set_cell_clearing_image_placements(x, blank.clone(), seqno),
+                Some(true) => {}
+                // Cells beyond the end of the line are implicitly blank
+                None => break,
+            }
+        }
+    }
+
     /// Ensure that row is within the range of the physical portion of
     /// the screen; 0 .. physical_rows by clamping it to the nearest
     /// boundary.
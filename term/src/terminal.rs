@@ -59,6 +59,12 @@ pub enum Alert {
     /// When something bumps the seqno in the terminal model and
     /// the terminal is not focused
     OutputSinceFocusLost,
+    /// The application requested that its window be focused,
+    /// via iTerm2's `OSC 1337 ; StealFocus`
+    StealFocus,
+    /// The application set (`true`) or reset (`false`) synchronized
+    /// output mode (DEC mode 2026) to bracket an update of the screen
+    SynchronizedOutputChanged(bool),
 }
 
 pub trait AlertHandler: Send + Sync {
@@ -14,10 +14,11 @@ use terminfo::{Database, Value};
 use termwiz::cell::UnicodeVersion;
 use termwiz::escape::csi::{
     Cursor, CursorStyle, DecPrivateMode, DecPrivateModeCode, Device, Edit, EraseInDisplay,
-    EraseInLine, Mode, Sgr, TabulationClear, TerminalMode, TerminalModeCode, Window, XtSmGraphics,
-    XtSmGraphicsAction, XtSmGraphicsItem, XtSmGraphicsStatus, XtermKeyModifierResource,
+    EraseInLine, FocusReport, Mode, Sgr, TabulationClear, TerminalMode, TerminalModeCode, Window,
+    XtSmGraphics, XtSmGraphicsAction, XtSmGraphicsItem, XtSmGraphicsStatus,
+    XtermKeyModifierResource,
 };
-use termwiz::escape::{OneBased, OperatingSystemCommand, CSI};
+use termwiz::escape::{OneBased, OperatingSystemCommand, TcapName, CSI};
 use termwiz::image::ImageData;
 use termwiz::input::KeyboardEncoding;
 use termwiz::surface::{CursorShape, CursorVisibility, SequenceNo};
@@ -84,6 +85,20 @@ impl TabStop {
         None
     }
 
+    /// Returns the DECTABSR representation of the tab stops that lie
+    /// within the first `screen_width` columns: the 1-based column
+    /// numbers separated by `/`.
+    fn report(&self, screen_width: usize) -> String {
+        self.tabs
+            .iter()
+            .take(screen_width)
+            .enumerate()
+            .filter(|(_, &is_stop)| is_stop)
+            .map(|(col, _)| (col + 1).to_string())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
     fn find_next_tab_stop(&self, col: usize) -> Option<usize> {
         for i in col + 1..self.tabs.len() {
             if self.tabs[i] {
@@ -246,6 +261,69 @@ impl ScreenOrAlt {
     }
 }
 
+/// A prompt that was started via OSC 133, along with the exit status
+/// that the shell reported for the command run from it, if any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PromptRow {
+    row: StableRowIndex,
+    status: Option<i32>,
+}
+
+/// Formats the parameters of a device attributes response
+fn join_params(params: &[u16]) -> String {
+    params
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Removes the bracketed paste start and end markers, in both their
+/// 7-bit and 8-bit forms, from `text`.  Removing one marker can cause
+/// the surrounding text to form another, so this repeats until there
+/// are none left.
+fn remove_bracketed_paste_markers(text: &str) -> String {
+    const MARKERS: [&str; 4] = ["\x1b[200~", "\x1b[201~", "\u{9b}200~", "\u{9b}201~"];
+    let mut text = text.to_string();
+    while let Some(marker) = MARKERS.iter().find(|marker| text.contains(**marker)) {
+        text = text.replace(marker, "");
+    }
+    text
+}
+
+/// A snapshot of the modes that are in effect in the terminal,
+/// as returned by `TerminalState::mode_flags`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalModeFlags {
+    /// IRM: characters are inserted rather than replacing
+    pub insert: bool,
+    /// DECAWM
+    pub dec_auto_wrap: bool,
+    pub reverse_wraparound_mode: bool,
+    /// DECSCNM
+    pub reverse_video_mode: bool,
+    /// DECOM
+    pub dec_origin_mode: bool,
+    /// DECLRMM
+    pub left_and_right_margin_mode: bool,
+    /// DECCKM
+    pub application_cursor_keys: bool,
+    /// DECNKM
+    pub application_keypad: bool,
+    /// LNM
+    pub newline_mode: bool,
+    pub bracketed_paste: bool,
+    pub focus_tracking: bool,
+    pub mouse_tracking: bool,
+    pub button_event_mouse: bool,
+    pub any_event_mouse: bool,
+    /// DECTCEM
+    pub cursor_visible: bool,
+    /// DECSDM
+    pub sixel_display_mode: bool,
+    pub alt_screen: bool,
+}
+
 /// Manages the state for the terminal
 pub struct TerminalState {
     config: Arc<dyn TerminalConfiguration>,
@@ -257,6 +335,9 @@ pub struct TerminalState {
     /// The current cursor position, relative to the top left
     /// of the screen.  0-based index.
     cursor: CursorPosition,
+    /// The cursor shape that was in effect on the primary screen
+    /// when the alternate screen was activated
+    primary_cursor_shape: Option<CursorShape>,
 
     /// if true, implicitly move to the next line on the next
     /// printed character
@@ -264,6 +345,14 @@ pub struct TerminalState {
 
     clear_semantic_attribute_on_newline: bool,
 
+    /// The prompts that were started on the primary screen via
+    /// OSC 133, in ascending row order.
+    prompt_rows: Vec<PromptRow>,
+
+    /// The rows of the primary screen that were marked via iTerm2's
+    /// `OSC 1337 ; SetMark`, in ascending order.
+    mark_rows: Vec<StableRowIndex>,
+
     /// If true, writing a character inserts a new cell
     insert: bool,
 
@@ -311,6 +400,11 @@ pub struct TerminalState {
     /// designated marker characters.
     bracketed_paste: bool,
 
+    /// DEC mode 2026; when set, the application is in the middle of
+    /// updating the screen and would prefer that it not be rendered
+    /// until the update is complete.
+    synchronized_output: bool,
+
     /// Movement events enabled
     any_event_mouse: bool,
     focus_tracking: bool,
@@ -338,6 +432,9 @@ pub struct TerminalState {
     title: String,
     /// The icon title string (OSC 1)
     icon_title: Option<String>,
+    /// true if `title` was set by the application via OSC 0 or 2,
+    /// rather than being the default
+    title_is_explicit: bool,
 
     palette: Option<ColorPalette>,
 
@@ -495,6 +592,11 @@ impl std::io::Write for ThreadedWriter {
     }
 }
 
+/// Pastes larger than this many bytes are split into chunks
+pub const PASTE_CHUNK_THRESHOLD: usize = 64 * 1024;
+/// The maximum size, in bytes, of each chunk of a large paste
+pub const PASTE_CHUNK_SIZE: usize = 16 * 1024;
+
 impl TerminalState {
     /// Constructs the terminal state.
     /// You generally want the `Terminal` struct rather than this one;
@@ -519,11 +621,14 @@ impl TerminalState {
             screen,
             pen: CellAttributes::default(),
             cursor: CursorPosition::default(),
+            primary_cursor_shape: None,
             top_and_bottom_margins: 0..size.rows as VisibleRowIndex,
             left_and_right_margins: 0..size.cols,
             left_and_right_margin_mode: false,
             wrap_next: false,
             clear_semantic_attribute_on_newline: false,
+            prompt_rows: vec![],
+            mark_rows: vec![],
             // We default auto wrap to true even though the default for
             // a dec terminal is false, because it is more useful this way.
             dec_auto_wrap: true,
@@ -539,6 +644,7 @@ impl TerminalState {
             color_map,
             application_keypad: false,
             bracketed_paste: false,
+            synchronized_output: false,
             focus_tracking: false,
             mouse_encoding: MouseEncoding::X10,
             keyboard_encoding: KeyboardEncoding::Xterm,
@@ -556,6 +662,7 @@ impl TerminalState {
             tabs: TabStop::new(size.cols, 8),
             title: "wezterm".to_string(),
             icon_title: None,
+            title_is_explicit: false,
             palette: None,
             pixel_height: size.pixel_height,
             pixel_width: size.pixel_width,
@@ -643,6 +750,23 @@ impl TerminalState {
         self.icon_title.as_ref().unwrap_or(&self.title)
     }
 
+    /// Returns the unicode version that is currently in effect for
+    /// width calculations.  This starts out as the configured
+    /// `unicode_version` but may be changed by the application via
+    /// `OSC 1337 ; UnicodeVersion=N ST`.
+    pub fn unicode_version(&self) -> UnicodeVersion {
+        self.unicode_version
+    }
+
+    /// Returns true if the title returned by `get_title` was explicitly
+    /// set by the application via an OSC sequence, rather than being
+    /// the default title.  This allows the gui to prefer titles set by
+    /// the application over those derived from eg: the process name.
+    /// The flag is cleared by a full reset (RIS).
+    pub fn has_explicit_title(&self) -> bool {
+        self.title_is_explicit || self.icon_title.is_some()
+    }
+
     /// Returns the current working directory associated with the
     /// terminal session.  The working directory can be changed by
     /// the applicaiton using the OSC 7 escape sequence.
@@ -689,6 +813,19 @@ impl TerminalState {
         }
     }
 
+    /// Called after resetting some or all of the colors in the palette.
+    /// If that changed the effective palette, notifies the alert handler
+    /// and invalidates the display so that the reset takes effect.
+    fn palette_changed_since(&mut self, before: &ColorPalette) {
+        if self.palette() == *before {
+            return;
+        }
+        if let Some(handler) = self.alert_handler.as_mut() {
+            handler.alert(Alert::PaletteChanged);
+        }
+        self.make_all_lines_dirty();
+    }
+
     /// Returns a reference to the active screen (either the primary or
     /// the alternate screen).
     pub fn screen(&self) -> &Screen {
@@ -760,6 +897,48 @@ impl TerminalState {
         self.bracketed_paste
     }
 
+    /// Returns true if the associated application has enabled
+    /// synchronized output mode, indicating that it is part way
+    /// through updating the screen; the hosting GUI application
+    /// may defer rendering until the mode is reset.
+    pub fn synchronized_output_enabled(&self) -> bool {
+        self.synchronized_output
+    }
+
+    fn set_synchronized_output(&mut self, enabled: bool) {
+        if self.synchronized_output != enabled {
+            self.synchronized_output = enabled;
+            if let Some(handler) = self.alert_handler.as_mut() {
+                handler.alert(Alert::SynchronizedOutputChanged(enabled));
+            }
+        }
+    }
+
+    /// Returns a snapshot of the modes that are currently in effect.
+    /// This is intended for diagnostic purposes, such as displaying
+    /// the state of the terminal in a debug overlay.
+    pub fn mode_flags(&self) -> TerminalModeFlags {
+        TerminalModeFlags {
+            insert: self.insert,
+            dec_auto_wrap: self.dec_auto_wrap,
+            reverse_wraparound_mode: self.reverse_wraparound_mode,
+            reverse_video_mode: self.reverse_video_mode,
+            dec_origin_mode: self.dec_origin_mode,
+            left_and_right_margin_mode: self.left_and_right_margin_mode,
+            application_cursor_keys: self.application_cursor_keys,
+            application_keypad: self.application_keypad,
+            newline_mode: self.newline_mode,
+            bracketed_paste: self.bracketed_paste,
+            focus_tracking: self.focus_tracking,
+            mouse_tracking: self.mouse_tracking,
+            button_event_mouse: self.button_event_mouse,
+            any_event_mouse: self.any_event_mouse,
+            cursor_visible: self.cursor_visible,
+            sixel_display_mode: self.sixel_display_mode,
+            alt_screen: self.screen.is_alt_screen_active(),
+        }
+    }
+
     /// Advise the terminal about a change in its focus state
     pub fn focus_changed(&mut self, focused: bool) {
         if focused == self.focused {
@@ -782,7 +961,12 @@ impl TerminalState {
             }
         }
         if self.focus_tracking {
-            write!(self.writer, "{}{}", CSI, if focused { "I" } else { "O" }).ok();
+            let report = if focused {
+                FocusReport::In
+            } else {
+                FocusReport::Out
+            };
+            write!(self.writer, "{}", CSI::Focus(report)).ok();
             self.writer.flush().ok();
         }
         self.focused = focused;
@@ -816,13 +1000,25 @@ impl TerminalState {
     /// If bracketed paste mode is enabled, the paste is enclosed
     /// in the bracketing, otherwise it is fed to the writer as-is.
     /// De-fang the text by removing any embedded bracketed paste
-    /// sequence that may be present.
+    /// sequence that may be present, unless disabled via
+    /// `filter_bracketed_paste_markers`.
+    /// Large pastes are written in a series of chunks; see `paste_chunks`.
     pub fn send_paste(&mut self, text: &str) -> Result<(), Error> {
-        let mut buf = String::new();
-        if self.bracketed_paste {
-            buf.push_str("\x1b[200~");
+        for chunk in self.paste_chunks(text) {
+            self.send_paste_chunk(&chunk)?;
         }
+        Ok(())
+    }
 
+    /// Prepares text that is the result of pasting in the same way as
+    /// `send_paste`, but rather than writing it, returns it as a list of
+    /// chunks to be passed to `send_paste_chunk` in order.
+    /// Text longer than `PASTE_CHUNK_THRESHOLD` is split into chunks of
+    /// at most `PASTE_CHUNK_SIZE` bytes, so that the caller can spread
+    /// the writes out over time.  When bracketed paste is enabled, the
+    /// opening bracket is at the start of the first chunk and the closing
+    /// bracket at the end of the last chunk.
+    pub fn paste_chunks(&self, text: &str) -> Vec<String> {
         let canon = if self.bracketed_paste {
             NewlineCanon::None
         } else {
@@ -830,14 +1026,43 @@ impl TerminalState {
         };
 
         let canon = canon.canonicalize(text);
-        let de_fanged = canon.replace("\x1b[200~", "").replace("\x1b[201~", "");
-        buf.push_str(&de_fanged);
+        let de_fanged = if self.config.filter_bracketed_paste_markers() {
+            remove_bracketed_paste_markers(&canon)
+        } else {
+            canon
+        };
+
+        let mut chunks = vec![];
+        if de_fanged.len() <= PASTE_CHUNK_THRESHOLD {
+            chunks.push(de_fanged);
+        } else {
+            let mut remaining = de_fanged.as_str();
+            while !remaining.is_empty() {
+                let mut len = PASTE_CHUNK_SIZE.min(remaining.len());
+                while !remaining.is_char_boundary(len) {
+                    len -= 1;
+                }
+                let (chunk, rest) = remaining.split_at(len);
+                chunks.push(chunk.to_string());
+                remaining = rest;
+            }
+        }
 
         if self.bracketed_paste {
-            buf.push_str("\x1b[201~");
+            if let Some(first) = chunks.first_mut() {
+                first.insert_str(0, "\x1b[200~");
+            }
+            if let Some(last) = chunks.last_mut() {
+                last.push_str("\x1b[201~");
+            }
         }
 
-        self.writer.write_all(buf.as_bytes())?;
+        chunks
+    }
+
+    /// Writes a chunk produced by `paste_chunks` to the terminal
+    pub fn send_paste_chunk(&mut self, chunk: &str) -> Result<(), Error> {
+        self.writer.write_all(chunk.as_bytes())?;
         self.writer.flush()?;
         Ok(())
     }
@@ -848,6 +1073,7 @@ impl TerminalState {
     /// the cursor positions of both accordingly.
     pub fn resize(&mut self, size: TerminalSize) {
         self.increment_seqno();
+        let width_changed = size.cols != self.screen.physical_cols;
         let (cursor_main, cursor_alt) = if self.screen.alt_screen_is_active {
             (
                 self.screen
@@ -875,6 +1101,12 @@ impl TerminalState {
             self.seqno,
             self.enable_conpty_quirks,
         );
+        self.rebuild_prompt_rows();
+        if width_changed {
+            // Rewrapping invalidates the rows of the marks, and unlike
+            // prompts there is nothing in the cells to recompute them from
+            self.mark_rows.clear();
+        }
         self.top_and_bottom_margins = 0..size.rows as i64;
         self.left_and_right_margins = 0..size.cols;
         self.pixel_height = size.pixel_height;
@@ -1174,12 +1406,22 @@ impl TerminalState {
 
     /// <https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h4-Device-Control-functions:DCS-plus-q-Pt-ST.F95>
     /// XTGETTCAP
-    fn xt_get_tcap(&mut self, names: Vec<String>) {
+    fn xt_get_tcap(&mut self, names: Vec<TcapName>) {
         let mut res = String::new();
 
-        for name in &names {
+        for TcapName { name, valid } in &names {
             res.push_str("\x1bP");
 
+            if !*valid {
+                // Echo back what we received; it wasn't something
+                // that we can meaningfully hex encode
+                log::trace!("xt_get_tcap: malformed name {}", name);
+                res.push_str("0+r");
+                res.push_str(name);
+                res.push_str("\x1b\\");
+                continue;
+            }
+
             let encoded_name = hex::encode_upper(&name);
             match name.as_str() {
                 "TN" | "name" => {
@@ -1245,8 +1487,12 @@ impl TerminalState {
                 }
             }
             Device::SoftReset => {
-                // TODO: see https://vt100.net/docs/vt510-rm/DECSTR.html
+                // See https://vt100.net/docs/vt510-rm/DECSTR.html
+                // The screen contents, scrollback and cursor position
+                // are preserved.
                 self.pen = CellAttributes::default();
+                self.wrap_next = false;
+                self.cursor_visible = true;
                 self.insert = false;
                 self.dec_origin_mode = false;
                 // Note that xterm deviates from the documented DECSTR
@@ -1258,10 +1504,16 @@ impl TerminalState {
                 self.top_and_bottom_margins = 0..self.screen().physical_rows as i64;
                 self.left_and_right_margins = 0..self.screen().physical_cols;
                 self.left_and_right_margin_mode = false;
+                // Forget the saved cursor for both screens, remaining
+                // on whichever one is currently active
+                let alt_screen_is_active = self.screen.is_alt_screen_active();
                 self.screen.activate_alt_screen(self.seqno);
                 self.screen.saved_cursor().take();
                 self.screen.activate_primary_screen(self.seqno);
                 self.screen.saved_cursor().take();
+                if alt_screen_is_active {
+                    self.screen.activate_alt_screen(self.seqno);
+                }
                 self.kitty_remove_all_placements(true);
 
                 self.reverse_wraparound_mode = false;
@@ -1271,31 +1523,17 @@ impl TerminalState {
 
                 self.g0_charset = CharSet::Ascii;
                 self.g1_charset = CharSet::Ascii;
+                self.shift_out = false;
+                self.set_synchronized_output(false);
             }
             Device::RequestPrimaryDeviceAttributes => {
-                let mut ident = "\x1b[?65".to_string(); // Vt500
-                ident.push_str(";4"); // Sixel graphics
-                ident.push_str(";6"); // Selective erase
-                ident.push_str(";18"); // windowing extensions
-                ident.push_str(";22"); // ANSI color, vt525
-                ident.push('c');
-
-                self.writer.write(ident.as_bytes()).ok();
+                let params = self.config.device_capabilities().primary_attributes();
+                write!(self.writer, "\x1b[?{}c", join_params(&params)).ok();
                 self.writer.flush().ok();
             }
             Device::RequestSecondaryDeviceAttributes => {
-                // Response is: Pp ; Pv ; Pc
-                // Where Pp=1 means vt220
-                // and Pv is the firmware version.
-                // Pc is always 0.
-                // Because our default TERM is xterm, the firmware
-                // version will be considered to be equialent to xterm's
-                // patch levels, with the following effects:
-                // pv < 95 -> ttymouse=xterm
-                // pv >= 95 < 277 -> ttymouse=xterm2
-                // pv >= 277 -> ttymouse=sgr
-                // pv >= 279 - xterm will probe for additional device settings.
-                self.writer.write(b"\x1b[>1;277;0c").ok();
+                let params = self.config.device_capabilities().secondary_attributes();
+                write!(self.writer, "\x1b[>{}c", join_params(&params)).ok();
                 self.writer.flush().ok();
             }
             Device::RequestTertiaryDeviceAttributes => {
@@ -1323,6 +1561,12 @@ impl TerminalState {
                 self.writer.write(b"\x1b[0n").ok();
                 self.writer.flush().ok();
             }
+            Device::RequestTabStopReport => {
+                // DECTABSR - https://vt100.net/docs/vt510-rm/DECTABSR.html
+                let stops = self.tabs.report(self.screen().physical_cols);
+                write!(self.writer, "{}2$u{}{}", DCS, stops, ST).ok();
+                self.writer.flush().ok();
+            }
             Device::XtSmGraphics(g) => {
                 let response = if matches!(g.item, XtSmGraphicsItem::Unspecified(_)) {
                     XtSmGraphics {
@@ -1557,22 +1801,22 @@ impl TerminalState {
                 );
             }
 
+            // wezterm's mux also holds back the output that is received
+            // while this mode is set, so that it is applied in one batch
             Mode::SetDecPrivateMode(DecPrivateMode::Code(
                 DecPrivateModeCode::SynchronizedOutput,
             )) => {
-                // This is handled in wezterm's mux
+                self.set_synchronized_output(true);
             }
             Mode::ResetDecPrivateMode(DecPrivateMode::Code(
                 DecPrivateModeCode::SynchronizedOutput,
             )) => {
-                // This is handled in wezterm's mux
+                self.set_synchronized_output(false);
             }
             Mode::QueryDecPrivateMode(DecPrivateMode::Code(
                 DecPrivateModeCode::SynchronizedOutput,
             )) => {
-                // This is handled in wezterm's mux; if we get here, then it isn't enabled,
-                // so we always report false
-                self.decqrm_response(mode, true, false);
+                self.decqrm_response(mode, true, self.synchronized_output);
             }
 
             Mode::SetDecPrivateMode(DecPrivateMode::Code(DecPrivateModeCode::SmoothScroll))
@@ -1592,6 +1836,10 @@ impl TerminalState {
                 self.reverse_video_mode = false;
             }
 
+            Mode::QueryDecPrivateMode(DecPrivateMode::Code(DecPrivateModeCode::ReverseVideo)) => {
+                self.decqrm_response(mode, true, self.reverse_video_mode);
+            }
+
             Mode::SetDecPrivateMode(DecPrivateMode::Code(DecPrivateModeCode::Select132Columns))
             | Mode::ResetDecPrivateMode(DecPrivateMode::Code(
                 DecPrivateModeCode::Select132Columns,
@@ -1664,7 +1912,7 @@ impl TerminalState {
                 DecPrivateModeCode::EnableAlternateScreen,
             )) => {
                 if !self.screen.is_alt_screen_active() {
-                    self.screen.activate_alt_screen(self.seqno);
+                    self.enter_alt_screen();
                     self.pen = CellAttributes::default();
                 }
             }
@@ -1674,7 +1922,7 @@ impl TerminalState {
                 if self.screen.is_alt_screen_active() {
                     self.pen = CellAttributes::default();
                     self.erase_in_display(EraseInDisplay::EraseDisplay);
-                    self.screen.activate_primary_screen(self.seqno);
+                    self.leave_alt_screen();
                 }
             }
 
@@ -1682,11 +1930,24 @@ impl TerminalState {
                 DecPrivateModeCode::EnableAlternateScreen,
             )) => {
                 if self.screen.is_alt_screen_active() {
-                    self.screen.activate_primary_screen(self.seqno);
+                    self.leave_alt_screen();
                     self.pen = CellAttributes::default();
                 }
             }
 
+            Mode::QueryDecPrivateMode(DecPrivateMode::Code(
+                DecPrivateModeCode::EnableAlternateScreen,
+            ))
+            | Mode::QueryDecPrivateMode(DecPrivateMode::Code(
+                DecPrivateModeCode::OptEnableAlternateScreen,
+            ))
+            | Mode::QueryDecPrivateMode(DecPrivateMode::Code(
+                DecPrivateModeCode::ClearAndEnableAlternateScreen,
+            )) => {
+                let active = self.screen.is_alt_screen_active();
+                self.decqrm_response(mode, true, active);
+            }
+
             Mode::SetDecPrivateMode(DecPrivateMode::Code(
                 DecPrivateModeCode::ApplicationCursorKeys,
             )) => {
@@ -1879,7 +2140,7 @@ impl TerminalState {
             )) => {
                 if !self.screen.is_alt_screen_active() {
                     self.dec_save_cursor();
-                    self.screen.activate_alt_screen(self.seqno);
+                    self.enter_alt_screen();
                     self.set_cursor_pos(&Position::Absolute(0), &Position::Absolute(0));
                     self.pen = CellAttributes::default();
                     self.erase_in_display(EraseInDisplay::EraseDisplay);
@@ -1889,7 +2150,7 @@ impl TerminalState {
                 DecPrivateModeCode::ClearAndEnableAlternateScreen,
             )) => {
                 if self.screen.is_alt_screen_active() {
-                    self.screen.activate_primary_screen(self.seqno);
+                    self.leave_alt_screen();
                     self.dec_restore_cursor();
                 }
             }
@@ -2021,8 +2282,9 @@ impl TerminalState {
 
             Window::ReportCellSizePixels => {
                 let screen = self.screen();
-                let height = screen.physical_rows;
-                let width = screen.physical_cols;
+                // Guard against a degenerate zero sized screen
+                let height = screen.physical_rows.max(1);
+                let width = screen.physical_cols.max(1);
                 let response = Box::new(Window::ReportCellSizePixelsResponse {
                     width: Some((self.pixel_width / width) as i64),
                     height: Some((self.pixel_height / height) as i64),
@@ -2122,6 +2384,49 @@ impl TerminalState {
         }
     }
 
+    /// DECSED: erase the unprotected cells in the display
+    fn selective_erase_in_display(&mut self, erase: EraseInDisplay) {
+        let seqno = self.seqno;
+        let cy = self.cursor.y;
+        let pen = self.pen.clone_sgr_only();
+        let rows = self.screen().physical_rows as VisibleRowIndex;
+        let col_range = 0..self.screen().physical_cols;
+        let row_range = match erase {
+            EraseInDisplay::EraseToEndOfDisplay => {
+                self.selective_erase_in_line(EraseInLine::EraseToEndOfLine);
+                cy + 1..rows
+            }
+            EraseInDisplay::EraseToStartOfDisplay => {
+                self.selective_erase_in_line(EraseInLine::EraseToStartOfLine);
+                0..cy
+            }
+            EraseInDisplay::EraseDisplay => 0..rows,
+            // Scrollback is not subject to selective erase
+            EraseInDisplay::EraseScrollback => return,
+        };
+
+        let screen = self.screen_mut();
+        for y in row_range {
+            screen.clear_unprotected(y, col_range.clone(), &pen, seqno);
+        }
+    }
+
+    /// DECSEL: erase the unprotected cells in the current line
+    fn selective_erase_in_line(&mut self, erase: EraseInLine) {
+        let seqno = self.seqno;
+        let cx = self.cursor.x;
+        let cy = self.cursor.y;
+        let pen = self.pen.clone_sgr_only();
+        let cols = self.screen().physical_cols;
+        let range = match erase {
+            EraseInLine::EraseToEndOfLine => cx + if self.wrap_next { 1 } else { 0 }..cols,
+            EraseInLine::EraseToStartOfLine => 0..cx + 1,
+            EraseInLine::EraseLine => 0..cols,
+        };
+
+        self.screen_mut().clear_unprotected(cy, range, &pen, seqno);
+    }
+
     fn get_bidi_mode(&self) -> BidiMode {
         let mut mode = self.config.bidi_mode();
         if let Some(enabled) = &self.bidi_enabled {
@@ -2240,9 +2545,31 @@ impl TerminalState {
                     );
                 }
             }
+            Edit::InsertColumn(n) | Edit::DeleteColumn(n) => {
+                // https://vt100.net/docs/vt510-rm/DECIC.html
+                // https://vt100.net/docs/vt510-rm/DECDC.html
+                // These have no effect if the cursor is outside the margins
+                let x = self.cursor.x;
+                if self.top_and_bottom_margins.contains(&self.cursor.y)
+                    && self.left_and_right_margins.contains(&x)
+                {
+                    let right_margin = self.left_and_right_margins.end;
+                    let num_cols = (n as usize).min(right_margin - x);
+                    let rows = self.top_and_bottom_margins.clone();
+                    let blank_attr = self.pen.clone_sgr_only();
+                    let screen = self.screen_mut();
+                    if matches!(edit, Edit::InsertColumn(_)) {
+                        screen.insert_columns(x, rows, right_margin, num_cols, seqno, blank_attr);
+                    } else {
+                        screen.delete_columns(x, rows, right_margin, num_cols, seqno, blank_attr);
+                    }
+                }
+            }
             Edit::ScrollDown(n) => self.scroll_down(n as usize),
             Edit::ScrollUp(n) => self.scroll_up(n as usize),
             Edit::EraseInDisplay(erase) => self.erase_in_display(erase),
+            Edit::SelectiveEraseInDisplay(erase) => self.selective_erase_in_display(erase),
+            Edit::SelectiveEraseInLine(erase) => self.selective_erase_in_line(erase),
             Edit::Repeat(n) => {
                 let mut y = self.cursor.y;
                 let mut x = self.cursor.x;
@@ -2601,6 +2928,22 @@ impl TerminalState {
         *self.screen.saved_cursor() = Some(saved);
     }
 
+    /// Switches to the alternate screen, remembering the cursor shape
+    /// of the primary screen so that `leave_alt_screen` can restore it
+    fn enter_alt_screen(&mut self) {
+        self.primary_cursor_shape.replace(self.cursor.shape);
+        self.screen.activate_alt_screen(self.seqno);
+    }
+
+    /// Switches back to the primary screen, restoring the cursor shape
+    /// that was in effect when the alternate screen was activated
+    fn leave_alt_screen(&mut self) {
+        self.screen.activate_primary_screen(self.seqno);
+        if let Some(shape) = self.primary_cursor_shape.take() {
+            self.cursor.shape = shape;
+        }
+    }
+
     /// https://vt100.net/docs/vt510-rm/DECRC.html
     fn dec_restore_cursor(&mut self) {
         let saved = self
@@ -2641,9 +2984,12 @@ impl TerminalState {
             Sgr::Reset => {
                 let link = self.pen.hyperlink().map(Arc::clone);
                 let semantic_type = self.pen.semantic_type();
+                // DECSCA is not an SGR attribute and survives SGR 0
+                let protected = self.pen.protected();
                 self.pen = CellAttributes::default();
                 self.pen.set_hyperlink(link);
                 self.pen.set_semantic_type(semantic_type);
+                self.pen.set_protected(protected);
             }
             Sgr::Intensity(intensity) => {
                 self.pen.set_intensity(intensity);
@@ -2695,46 +3041,120 @@ impl TerminalState {
     /// By default, all screen data is of type Output.  The shell needs to
     /// employ OSC 133 escapes to markup its output.
     pub fn get_semantic_zones(&mut self) -> anyhow::Result<Vec<SemanticZone>> {
-        let screen = self.screen_mut();
-
-        let mut current_zone: Option<SemanticZone> = None;
-        let mut zones = vec![];
-
-        let first_stable_row = screen.phys_to_stable_row_index(0);
-        screen.for_each_phys_line_mut(|idx, line| {
-            let stable_row = first_stable_row + idx as StableRowIndex;
-
-            for zone_range in line.semantic_zone_ranges() {
-                let new_zone = match current_zone.as_ref() {
-                    None => true,
-                    Some(zone) => zone.semantic_type != zone_range.semantic_type,
-                };
+        Ok(semantic_zones_for_screen(self.screen_mut()))
+    }
 
-                if new_zone {
-                    if let Some(zone) = current_zone.take() {
-                        zones.push(zone);
-                    }
+    /// Records the cursor row as the start of a prompt.
+    /// Prompts on the alternate screen are not tracked.
+    fn record_prompt_row(&mut self) {
+        if self.screen.alt_screen_is_active {
+            return;
+        }
+        let row = self.screen.visible_row_to_stable_row(self.cursor.y);
+        // Prompts that have been evicted from the scrollback are of no
+        // further use, and if the screen was cleared and a prompt drawn
+        // over the rows of earlier prompts, those no longer exist either
+        let oldest = self.screen.phys_to_stable_row_index(0);
+        self.prompt_rows
+            .retain(|prompt| prompt.row >= oldest && prompt.row < row);
+        self.prompt_rows.push(PromptRow { row, status: None });
+    }
+
+    /// Records the cursor row as a mark.
+    /// Marks on the alternate screen are not tracked.
+    fn record_mark_row(&mut self) {
+        if self.screen.alt_screen_is_active {
+            return;
+        }
+        let row = self.screen.visible_row_to_stable_row(self.cursor.y);
+        // Marks that have been evicted from the scrollback are of no
+        // further use
+        let oldest = self.screen.phys_to_stable_row_index(0);
+        let evicted = self.mark_rows.partition_point(|&mark| mark < oldest);
+        self.mark_rows.drain(..evicted);
+        if let Err(idx) = self.mark_rows.binary_search(&row) {
+            self.mark_rows.insert(idx, row);
+        }
+    }
 
-                    current_zone.replace(SemanticZone {
-                        start_x: zone_range.range.start as usize,
-                        start_y: stable_row,
-                        end_x: zone_range.range.end as usize,
-                        end_y: stable_row,
-                        semantic_type: zone_range.semantic_type,
-                    });
-                }
+    /// Records the exit status reported via OSC 133;D against the
+    /// most recent prompt, which is the one the command was run from.
+    fn record_command_status(&mut self, status: i32) {
+        if self.screen.alt_screen_is_active {
+            return;
+        }
+        if let Some(prompt) = self.prompt_rows.last_mut() {
+            prompt.status = Some(status);
+        }
+    }
 
-                if let Some(zone) = current_zone.as_mut() {
-                    zone.end_x = zone_range.range.end as usize;
-                    zone.end_y = stable_row;
-                }
-            }
-        });
-        if let Some(zone) = current_zone.take() {
-            zones.push(zone);
+    /// Resizing may rewrap the primary screen, invalidating the
+    /// recorded stable rows; recompute them from the prompt zones.
+    /// Command statuses are carried over by matching prompts up
+    /// from the most recent one.
+    fn rebuild_prompt_rows(&mut self) {
+        let mut rows: Vec<StableRowIndex> = semantic_zones_for_screen(&mut self.screen.screen)
+            .into_iter()
+            .filter(|zone| zone.semantic_type == SemanticType::Prompt)
+            .map(|zone| zone.start_y)
+            .collect();
+        rows.dedup();
+        let statuses = self
+            .prompt_rows
+            .iter()
+            .rev()
+            .map(|prompt| prompt.status)
+            .chain(std::iter::repeat(None));
+        let mut prompt_rows: Vec<PromptRow> = rows
+            .into_iter()
+            .rev()
+            .zip(statuses)
+            .map(|(row, status)| PromptRow { row, status })
+            .collect();
+        prompt_rows.reverse();
+        self.prompt_rows = prompt_rows;
+    }
+
+    /// Returns the stable row indices at which OSC 133 prompts were
+    /// started on the primary screen, in ascending order.
+    /// Rows that have since been evicted from the scrollback are omitted,
+    /// and the list is empty while the alternate screen is active.
+    pub fn get_prompt_rows(&self) -> Vec<StableRowIndex> {
+        self.filter_prompt_rows(|_| true)
+    }
+
+    /// Like `get_prompt_rows`, but only includes the prompts from
+    /// which a command was run that reported a non-zero exit status
+    /// via OSC 133;D.
+    pub fn get_error_prompt_rows(&self) -> Vec<StableRowIndex> {
+        self.filter_prompt_rows(|prompt| matches!(prompt.status, Some(status) if status != 0))
+    }
+
+    /// Returns the stable row indices that were marked via iTerm2's
+    /// `OSC 1337 ; SetMark` on the primary screen, in ascending order.
+    /// Rows that have since been evicted from the scrollback are omitted,
+    /// and the list is empty while the alternate screen is active.
+    pub fn get_mark_rows(&self) -> Vec<StableRowIndex> {
+        if self.screen.alt_screen_is_active {
+            return vec![];
         }
+        self.mark_rows
+            .iter()
+            .copied()
+            .filter(|&row| self.screen.stable_row_to_phys(row).is_some())
+            .collect()
+    }
 
-        Ok(zones)
+    fn filter_prompt_rows(&self, filter: impl Fn(&PromptRow) -> bool) -> Vec<StableRowIndex> {
+        if self.screen.alt_screen_is_active {
+            return vec![];
+        }
+        self.prompt_rows
+            .iter()
+            .filter(|prompt| filter(prompt))
+            .map(|prompt| prompt.row)
+            .filter(|&row| self.screen.stable_row_to_phys(row).is_some())
+            .collect()
     }
 
     #[inline]
@@ -2750,3 +3170,44 @@ impl TerminalState {
             .unwrap_or(self.keyboard_encoding)
     }
 }
+
+fn semantic_zones_for_screen(screen: &mut Screen) -> Vec<SemanticZone> {
+    let mut current_zone: Option<SemanticZone> = None;
+    let mut zones = vec![];
+
+    let first_stable_row = screen.phys_to_stable_row_index(0);
+    screen.for_each_phys_line_mut(|idx, line| {
+        let stable_row = first_stable_row + idx as StableRowIndex;
+
+        for zone_range in line.semantic_zone_ranges() {
+            let new_zone = match current_zone.as_ref() {
+                None => true,
+                Some(zone) => zone.semantic_type != zone_range.semantic_type,
+            };
+
+            if new_zone {
+                if let Some(zone) = current_zone.take() {
+                    zones.push(zone);
+                }
+
+                current_zone.replace(SemanticZone {
+                    start_x: zone_range.range.start as usize,
+                    start_y: stable_row,
+                    end_x: zone_range.range.end as usize,
+                    end_y: stable_row,
+                    semantic_type: zone_range.semantic_type,
+                });
+            }
+
+            if let Some(zone) = current_zone.as_mut() {
+                zone.end_x = zone_range.range.end as usize;
+                zone.end_y = stable_row;
+            }
+        }
+    });
+    if let Some(zone) = current_zone.take() {
+        zones.push(zone);
+    }
+
+    zones
+}
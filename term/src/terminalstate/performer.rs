@@ -1,3 +1,4 @@
+use crate::config::{Osc52ClipboardPolicy, TmuxTitleTerminator};
 use crate::terminal::Alert;
 use crate::terminalstate::{
     default_color_map, CharSet, MouseEncoding, TabStop, UnicodeVersionStackEntry,
@@ -12,7 +13,8 @@ use std::io::Write as _;
 use std::ops::{Deref, DerefMut};
 use termwiz::cell::{grapheme_column_width, Cell, CellAttributes, SemanticType};
 use termwiz::escape::csi::{
-    CharacterPath, EraseInDisplay, Keyboard, KittyKeyboardFlags, KittyKeyboardMode,
+    CharacterPath, CharacterProtection, EraseInDisplay, Keyboard, KittyKeyboardFlags,
+    KittyKeyboardMode,
 };
 use termwiz::escape::osc::{
     ChangeColorPair, ColorOrQuery, FinalTermSemanticPrompt, ITermProprietary,
@@ -22,7 +24,9 @@ use termwiz::escape::{
     Action, ControlCode, DeviceControlMode, Esc, EscCode, OperatingSystemCommand, CSI,
 };
 use termwiz::input::KeyboardEncoding;
+use termwiz::surface::SequenceNo;
 use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
 use url::Url;
 use wezterm_bidi::ParagraphDirectionHint;
 
@@ -138,7 +142,11 @@ impl<'a> Performer<'a> {
                 // We used to force them into a cell to guarantee that we
                 // preserved them in the model, but it introduces presentation
                 // problems, such as <https://github.com/wez/wezterm/issues/1422>
-                log::trace!("Eliding zero-width grapheme {:?}", g);
+                if self.config.attach_zero_width_graphemes() {
+                    self.attach_zero_width_grapheme(g, seqno);
+                } else {
+                    log::trace!("Eliding zero-width grapheme {:?}", g);
+                }
                 continue;
             }
 
@@ -218,6 +226,47 @@ impl<'a> Performer<'a> {
         self.print.clear();
     }
 
+    /// Appends a zero-width grapheme to the grapheme cluster of the cell
+    /// that was most recently printed, which is the one to the left of
+    /// the cursor, or under it if we're about to wrap.
+    /// If there is no such cell on the current line, it is elided.
+    fn attach_zero_width_grapheme(&mut self, g: &str, seqno: SequenceNo) {
+        let x = if self.wrap_next {
+            self.cursor.x
+        } else {
+            match self.cursor.x.checked_sub(1) {
+                Some(x) => x,
+                None => {
+                    log::trace!("Eliding zero-width grapheme {:?} at start of line", g);
+                    return;
+                }
+            }
+        };
+        let y = self.cursor.y;
+        let screen = self.screen_mut();
+        let line_idx = screen.phys_row(y);
+        let line = screen.line_mut(line_idx);
+
+        // x may refer to the second half of a double-width cell
+        let prior = line
+            .visible_cells()
+            .find(|cell| cell.cell_index() <= x && x < cell.cell_index() + cell.width())
+            .map(|cell| {
+                (
+                    cell.cell_index(),
+                    format!("{}{}", cell.str(), g),
+                    cell.width(),
+                    cell.attrs().clone(),
+                )
+            });
+        match prior {
+            Some((idx, text, width, attrs)) => {
+                line.set_cell_grapheme(idx, &text, width, attrs, seqno);
+            }
+            None => log::trace!("Eliding zero-width grapheme {:?}", g),
+        }
+    }
+
     /// ConPTY, at the time of writing, does something horrible to rewrite
     /// `ESC k TITLE ST` into something completely different and out-of-order,
     /// and critically, removes the ST.
@@ -352,12 +401,56 @@ impl<'a> Performer<'a> {
             title.push(c);
         } else {
             self.print.push(c);
+            if self.print.len() >= self.config.print_buffer_flush_threshold() {
+                self.flush_print_complete_graphemes();
+            }
+        }
+    }
+
+    /// Flushes all but the last grapheme of the buffered text; the
+    /// last grapheme is retained as it may yet be extended by
+    /// combining characters that have not been received.
+    /// If the buffer holds just a single grapheme that has grown past
+    /// the threshold, it is flushed as-is rather than rescanned on
+    /// every subsequent character.
+    fn flush_print_complete_graphemes(&mut self) {
+        let last_len = match self.print.graphemes(true).next_back() {
+            Some(g) => g.len(),
+            None => return,
+        };
+        if last_len == self.print.len() {
+            self.flush_print();
+            return;
+        }
+        let tail = self.print.split_off(self.print.len() - last_len);
+        self.flush_print();
+        self.print.push_str(&tail);
+    }
+
+    /// Called when a control code is received while a tmux title may
+    /// be accumulating.  Depending on the configuration, the control
+    /// code either completes the title or abandons it.
+    fn tmux_title_control(&mut self, control: ControlCode) {
+        if self.accumulating_title.is_none() {
+            return;
+        }
+        let complete = match self.config.tmux_title_terminator() {
+            TmuxTitleTerminator::StringTerminator => false,
+            TmuxTitleTerminator::LineFeed => control == ControlCode::LineFeed,
+            TmuxTitleTerminator::AnyControl => (control as u8) < 0x20,
+        };
+        if complete {
+            if let Some(title) = self.accumulating_title.take() {
+                self.osc_dispatch(OperatingSystemCommand::SetIconNameAndWindowTitle(title));
+            }
+        } else {
+            self.pop_tmux_title_state();
         }
     }
 
     fn control(&mut self, control: ControlCode) {
         let seqno = self.seqno;
-        self.pop_tmux_title_state();
+        self.tmux_title_control(control);
         self.flush_print();
         match control {
             ControlCode::LineFeed | ControlCode::VerticalTab | ControlCode::FormFeed => {
@@ -421,6 +514,15 @@ impl<'a> Performer<'a> {
                     // just output and reverse-wraparound is on then backspace
                     // by 1 cancels the pending wrap.
                     self.wrap_next = false;
+                } else if self.cursor.x == self.left_and_right_margins.start
+                    && self.cursor.y > self.top_and_bottom_margins.start
+                    && self.config.backspace_wraps_to_previous_line()
+                {
+                    // Backspace off the left moves to the end of the prior
+                    // line, but doesn't wrap from the top to the bottom
+                    let x_pos = Position::Absolute(self.left_and_right_margins.end as i64 - 1);
+                    let y_pos = Position::Relative(-1);
+                    self.set_cursor_pos(&x_pos, &y_pos);
                 } else if self.cursor.x == self.left_and_right_margins.start {
                     // Respect the left margin and don't BS outside it
                 } else {
@@ -431,6 +533,7 @@ impl<'a> Performer<'a> {
             ControlCode::HTS => self.c1_hts(),
             ControlCode::IND => self.c1_index(),
             ControlCode::NEL => self.c1_nel(),
+            ControlCode::Bell if self.config.bell_disabled() => {}
             ControlCode::Bell => {
                 if let Some(handler) = self.alert_handler.as_mut() {
                     handler.alert(Alert::Bell);
@@ -489,6 +592,7 @@ impl<'a> Performer<'a> {
             CSI::Mode(mode) => self.state.perform_csi_mode(mode),
             CSI::Device(dev) => self.state.perform_device(*dev),
             CSI::Mouse(mouse) => error!("mouse report sent by app? {:?}", mouse),
+            CSI::Focus(focus) => error!("focus report sent by app? {:?}", focus),
             CSI::Window(window) => self.state.perform_csi_window(*window),
             CSI::SelectCharacterPath(CharacterPath::ImplementationDefault, _) => {
                 self.state.bidi_hint.take();
@@ -503,6 +607,10 @@ impl<'a> Performer<'a> {
                     .bidi_hint
                     .replace(ParagraphDirectionHint::RightToLeft);
             }
+            CSI::SelectCharacterProtection(protection) => {
+                self.pen
+                    .set_protected(protection == CharacterProtection::Protected);
+            }
             CSI::Keyboard(Keyboard::SetKittyState { flags, mode }) => {
                 if self.config.enable_kitty_keyboard() {
                     let current_flags = match self.screen().keyboard_stack.last() {
@@ -677,6 +785,7 @@ impl<'a> Performer<'a> {
                 self.dec_ansi_mode = false;
                 self.application_keypad = false;
                 self.bracketed_paste = false;
+                self.set_synchronized_output(false);
                 self.focus_tracking = false;
                 self.mouse_tracking = false;
                 self.mouse_encoding = MouseEncoding::X10;
@@ -698,6 +807,11 @@ impl<'a> Performer<'a> {
                 self.unicode_version_stack.clear();
                 self.suppress_initial_title_change = false;
                 self.accumulating_title.take();
+                self.icon_title.take();
+                self.primary_cursor_shape.take();
+                self.title_is_explicit = false;
+                self.prompt_rows.clear();
+                self.mark_rows.clear();
 
                 self.screen.full_reset();
                 self.screen.activate_alt_screen(seqno);
@@ -736,6 +850,7 @@ impl<'a> Performer<'a> {
             }
             OperatingSystemCommand::SetIconNameAndWindowTitle(title) => {
                 self.icon_title.take();
+                self.title_is_explicit = !title.is_empty();
                 self.title = title.clone();
                 if let Some(handler) = self.alert_handler.as_mut() {
                     handler.alert(Alert::WindowTitleChanged(title.clone()));
@@ -745,6 +860,7 @@ impl<'a> Performer<'a> {
 
             OperatingSystemCommand::SetWindowTitleSun(title)
             | OperatingSystemCommand::SetWindowTitle(title) => {
+                self.title_is_explicit = !title.is_empty();
                 self.title = title.clone();
                 if let Some(handler) = self.alert_handler.as_mut() {
                     handler.alert(Alert::WindowTitleChanged(title));
@@ -765,6 +881,12 @@ impl<'a> Performer<'a> {
                 }
             }
 
+            OperatingSystemCommand::ClearSelection(_)
+            | OperatingSystemCommand::SetSelection(..)
+                if self.config.osc52_clipboard_policy() == Osc52ClipboardPolicy::Deny =>
+            {
+                debug!("ignoring OSC 52 clipboard write as osc52_clipboard_policy is Deny");
+            }
             OperatingSystemCommand::ClearSelection(selection) => {
                 let selection = selection_to_selection(selection);
                 self.set_clipboard_contents(selection, None).ok();
@@ -810,6 +932,12 @@ impl<'a> Performer<'a> {
                     self.writer.flush().ok();
                 }
                 ITermProprietary::File(image) => self.set_image(*image),
+                ITermProprietary::SetMark => self.record_mark_row(),
+                ITermProprietary::StealFocus => {
+                    if let Some(handler) = self.alert_handler.as_mut() {
+                        handler.alert(Alert::StealFocus);
+                    }
+                }
                 ITermProprietary::SetUserVar { name, value } => {
                     self.user_vars.insert(name.clone(), value.clone());
                     if let Some(handler) = self.alert_handler.as_mut() {
@@ -819,6 +947,14 @@ impl<'a> Performer<'a> {
                 ITermProprietary::UnicodeVersion(ITermUnicodeVersionOp::Set(n)) => {
                     self.unicode_version.version = n;
                 }
+                ITermProprietary::UnicodeVersion(ITermUnicodeVersionOp::Query) => {
+                    let response =
+                        OperatingSystemCommand::ITermProprietary(ITermProprietary::UnicodeVersion(
+                            ITermUnicodeVersionOp::Set(self.unicode_version.version),
+                        ));
+                    write!(self.writer, "{}", response).ok();
+                    self.writer.flush().ok();
+                }
                 ITermProprietary::UnicodeVersion(ITermUnicodeVersionOp::Push(label)) => {
                     let vers = self.unicode_version;
                     self.unicode_version_stack
@@ -852,11 +988,13 @@ impl<'a> Performer<'a> {
             ) => {
                 self.fresh_line();
                 self.pen.set_semantic_type(SemanticType::Prompt);
+                self.record_prompt_row();
             }
             OperatingSystemCommand::FinalTermSemanticPrompt(
                 FinalTermSemanticPrompt::StartPrompt(_),
             ) => {
                 self.pen.set_semantic_type(SemanticType::Prompt);
+                self.record_prompt_row();
             }
             OperatingSystemCommand::FinalTermSemanticPrompt(
                 FinalTermSemanticPrompt::MarkEndOfCommandWithFreshLine { .. },
@@ -882,8 +1020,10 @@ impl<'a> Performer<'a> {
             }
 
             OperatingSystemCommand::FinalTermSemanticPrompt(
-                FinalTermSemanticPrompt::CommandStatus { .. },
-            ) => {}
+                FinalTermSemanticPrompt::CommandStatus { status, .. },
+            ) => {
+                self.record_command_status(status);
+            }
 
             OperatingSystemCommand::SystemNotification(message) => {
                 if let Some(handler) = self.alert_handler.as_mut() {
@@ -896,6 +1036,9 @@ impl<'a> Performer<'a> {
                     log::info!("Application sends SystemNotification: {}", message);
                 }
             }
+            OperatingSystemCommand::Progress { state, percent } => {
+                log::trace!("Application reports progress {:?} {:?}", state, percent);
+            }
             OperatingSystemCommand::RxvtExtension(params) => {
                 if let Some("notify") = params.get(0).map(String::as_str) {
                     let title = params.get(1);
@@ -952,6 +1095,7 @@ impl<'a> Performer<'a> {
 
             OperatingSystemCommand::ResetColors(colors) => {
                 log::trace!("ResetColors: {:?}", colors);
+                let before = self.palette();
                 if colors.is_empty() {
                     // Reset all colors
                     self.palette.take();
@@ -968,9 +1112,7 @@ impl<'a> Performer<'a> {
                     }
                 }
                 self.implicit_palette_reset_if_same_as_configured();
-                if let Some(handler) = self.alert_handler.as_mut() {
-                    handler.alert(Alert::PaletteChanged);
-                }
+                self.palette_changed_since(&before);
             }
 
             OperatingSystemCommand::ChangeDynamicColors(first_color, colors) => {
@@ -1033,6 +1175,7 @@ impl<'a> Performer<'a> {
 
             OperatingSystemCommand::ResetDynamicColor(color) => {
                 log::trace!("ResetDynamicColor: {:?}", color);
+                let before = self.palette();
                 use termwiz::escape::osc::DynamicColorNumber;
                 let which_color: Option<DynamicColorNumber> = FromPrimitive::from_u8(color as u8);
                 if let Some(which_color) = which_color {
@@ -1065,10 +1208,7 @@ impl<'a> Performer<'a> {
                     }
                 }
                 self.implicit_palette_reset_if_same_as_configured();
-                if let Some(handler) = self.alert_handler.as_mut() {
-                    handler.alert(Alert::PaletteChanged);
-                }
-                self.make_all_lines_dirty();
+                self.palette_changed_since(&before);
             }
         }
     }
@@ -2,6 +2,7 @@ use crate::terminalstate::image::*;
 use crate::terminalstate::{default_color_map, ImageAttachParams};
 use crate::TerminalState;
 use ::image::RgbaImage;
+use std::collections::HashMap;
 use termwiz::color::RgbColor;
 use termwiz::escape::{Sixel, SixelData};
 use termwiz::image::ImageDataType;
@@ -23,93 +24,7 @@ impl TerminalState {
             &mut self.color_map
         };
 
-        let mut image = if sixel.background_is_transparent {
-            RgbaImage::new(width, height)
-        } else {
-            let background_color = color_map
-                .get(&0)
-                .cloned()
-                .unwrap_or(RgbColor::new_8bpc(0, 0, 0));
-            let (red, green, blue) = background_color.to_tuple_rgb8();
-            RgbaImage::from_pixel(width, height, [red, green, blue, 0xffu8].into())
-        };
-
-        let mut x = 0;
-        let mut y = 0;
-        let mut foreground_color = RgbColor::new_8bpc(0, 0xff, 0);
-
-        let mut emit_sixel = |d: &u8, foreground_color: &RgbColor, x: u32, y: u32| {
-            if x >= width {
-                return;
-            }
-            let (red, green, blue) = foreground_color.to_tuple_rgb8();
-            for bitno in 0..6 {
-                if y + bitno >= height {
-                    break;
-                }
-                let on = (d & (1 << bitno)) != 0;
-                if on {
-                    image.get_pixel_mut(x, y + bitno).0 = [red, green, blue, 0xffu8];
-                }
-            }
-        };
-
-        for d in &sixel.data {
-            match d {
-                SixelData::Data(d) => {
-                    emit_sixel(d, &foreground_color, x, y);
-                    x += 1;
-                }
-
-                SixelData::Repeat { repeat_count, data } => {
-                    for _ in 0..*repeat_count {
-                        emit_sixel(data, &foreground_color, x, y);
-                        x += 1;
-                    }
-                }
-
-                SixelData::CarriageReturn => x = 0,
-                SixelData::NewLine => {
-                    x = 0;
-                    y += 6;
-                }
-
-                SixelData::DefineColorMapRGB { color_number, rgb } => {
-                    color_map.insert(*color_number, *rgb);
-                }
-
-                SixelData::DefineColorMapHSL {
-                    color_number,
-                    hue_angle,
-                    saturation,
-                    lightness,
-                } => {
-                    // Sixel's hue angles are: blue=0, red=120, green=240,
-                    // whereas Hsl has red=0, green=120, blue=240.
-                    // Looking at red, we need to rotate left by 120 to
-                    // go from sixel red to standard hsl red.
-                    // Negative values wrap around the circle.
-                    // https://github.com/wez/wezterm/issues/775
-                    let angle = (*hue_angle as f64) - 120.0;
-                    let angle = if angle < 0. { 360.0 + angle } else { angle };
-                    let c = csscolorparser::Color::from_hsla(
-                        angle,
-                        *saturation as f64 / 100.,
-                        *lightness as f64 / 100.,
-                        1.,
-                    );
-                    let [r, g, b, _] = c.to_rgba8();
-                    color_map.insert(*color_number, RgbColor::new_8bpc(r, g, b));
-                }
-
-                SixelData::SelectColorMapEntry(n) => {
-                    foreground_color = color_map.get(n).cloned().unwrap_or_else(|| {
-                        log::error!("sixel selected noexistent colormap entry {}", n);
-                        RgbColor::new_8bpc(255, 255, 255)
-                    });
-                }
-            }
-        }
+        let image = decode_sixel(&sixel, color_map);
 
         let data = image.into_vec();
         let image_data = ImageDataType::new_single_frame(width, height, data);
@@ -154,3 +69,150 @@ impl TerminalState {
         }
     }
 }
+
+/// Decode the sixel data into a bitmap, updating `color_map` with any
+/// color registers that are defined by the image.
+fn decode_sixel(sixel: &Sixel, color_map: &mut HashMap<u16, RgbColor>) -> RgbaImage {
+    let (width, height) = sixel.dimensions();
+
+    let mut image = if sixel.background_is_transparent {
+        RgbaImage::new(width, height)
+    } else {
+        let background_color = color_map
+            .get(&0)
+            .cloned()
+            .unwrap_or(RgbColor::new_8bpc(0, 0, 0));
+        let (red, green, blue) = background_color.to_tuple_rgb8();
+        RgbaImage::from_pixel(width, height, [red, green, blue, 0xffu8].into())
+    };
+
+    let mut x = 0;
+    let mut y = 0;
+    let mut foreground_color = RgbColor::new_8bpc(0, 0xff, 0);
+
+    let mut emit_sixel = |d: &u8, foreground_color: &RgbColor, x: u32, y: u32| {
+        if x >= width {
+            return;
+        }
+        let (red, green, blue) = foreground_color.to_tuple_rgb8();
+        for bitno in 0..6 {
+            if y + bitno >= height {
+                break;
+            }
+            let on = (d & (1 << bitno)) != 0;
+            if on {
+                image.get_pixel_mut(x, y + bitno).0 = [red, green, blue, 0xffu8];
+            }
+        }
+    };
+
+    for d in &sixel.data {
+        match d {
+            SixelData::Data(d) => {
+                emit_sixel(d, &foreground_color, x, y);
+                x += 1;
+            }
+
+            SixelData::Repeat { repeat_count, data } => {
+                for _ in 0..*repeat_count {
+                    emit_sixel(data, &foreground_color, x, y);
+                    x += 1;
+                }
+            }
+
+            SixelData::CarriageReturn => x = 0,
+            SixelData::NewLine => {
+                x = 0;
+                y += 6;
+            }
+
+            SixelData::DefineColorMapRGB { color_number, rgb } => {
+                color_map.insert(*color_number, *rgb);
+            }
+
+            SixelData::DefineColorMapHSL {
+                color_number,
+                hue_angle,
+                saturation,
+                lightness,
+            } => {
+                // Sixel's hue angles are: blue=0, red=120, green=240,
+                // whereas Hsl has red=0, green=120, blue=240.
+                // Looking at red, we need to rotate left by 120 to
+                // go from sixel red to standard hsl red.
+                // Negative values wrap around the circle.
+                // https://github.com/wez/wezterm/issues/775
+                let angle = (*hue_angle as f64) - 120.0;
+                let angle = if angle < 0. { 360.0 + angle } else { angle };
+                let c = csscolorparser::Color::from_hsla(
+                    angle,
+                    *saturation as f64 / 100.,
+                    *lightness as f64 / 100.,
+                    1.,
+                );
+                let [r, g, b, _] = c.to_rgba8();
+                color_map.insert(*color_number, RgbColor::new_8bpc(r, g, b));
+            }
+
+            SixelData::SelectColorMapEntry(n) => {
+                foreground_color = color_map.get(n).cloned().unwrap_or_else(|| {
+                    log::error!("sixel selected noexistent colormap entry {}", n);
+                    RgbColor::new_8bpc(255, 255, 255)
+                });
+            }
+        }
+    }
+
+    image
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use termwiz::escape::parser::Parser;
+    use termwiz::escape::Action;
+
+    fn parse_sixel(bytes: &[u8]) -> Box<Sixel> {
+        let mut p = Parser::new();
+        match p.parse_as_vec(bytes).into_iter().next() {
+            Some(Action::Sixel(sixel)) => sixel,
+            other => panic!("expected a sixel, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_small_image() {
+        // Define black and red color registers, then paint a column
+        // with all six pixels set, followed by a column with just
+        // the top pixel set.
+        let sixel = parse_sixel(b"\x1bPq#0;2;0;0;0#1;2;100;0;0#1~@\x1b\\");
+        let mut color_map = default_color_map();
+        let image = decode_sixel(&sixel, &mut color_map);
+
+        assert_eq!(image.dimensions(), (2, 6));
+
+        let red = [0xff, 0, 0, 0xff];
+        let black = [0, 0, 0, 0xff];
+        for y in 0..6 {
+            assert_eq!(image.get_pixel(0, y).0, red, "x=0 y={y}");
+        }
+        assert_eq!(image.get_pixel(1, 0).0, red);
+        for y in 1..6 {
+            assert_eq!(image.get_pixel(1, y).0, black, "x=1 y={y}");
+        }
+
+        // The color registers defined by the image are retained
+        assert_eq!(color_map.get(&1), Some(&RgbColor::new_8bpc(0xff, 0, 0)));
+    }
+
+    #[test]
+    fn decode_transparent_background() {
+        let sixel = parse_sixel(b"\x1bP0;1q#1;2;0;100;0#1@\x1b\\");
+        let mut color_map = default_color_map();
+        let image = decode_sixel(&sixel, &mut color_map);
+
+        assert_eq!(image.dimensions(), (1, 6));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0xff, 0, 0xff]);
+        assert_eq!(image.get_pixel(0, 1).0, [0, 0, 0, 0]);
+    }
+}
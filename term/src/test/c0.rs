@@ -1,6 +1,7 @@
 //! Testing C0 control characters
 
 use super::*;
+use k9::assert_equal as assert_eq;
 
 #[test]
 fn test_bs() {
@@ -13,6 +14,31 @@ fn test_bs() {
     // TODO: when we can set the left margin, we should test that here
 }
 
+#[test]
+fn test_bs_wraps_to_previous_line() {
+    let mut term = TestTerm::new(3, 4, 0);
+    term.print("ab\r\n");
+    let seqno = term.cursor_pos().seqno;
+    term.print("\x08");
+    term.assert_cursor_pos(
+        0,
+        1,
+        Some("no-op unless enabled in the config"),
+        Some(seqno),
+    );
+
+    term.set_config(Arc::new(TestTermConfig {
+        backspace_wraps_to_previous_line: true,
+        ..Default::default()
+    }));
+    term.print("\x08");
+    term.assert_cursor_pos(3, 0, Some("moves to the end of the prior line"), None);
+    term.print("\r");
+    let seqno = term.cursor_pos().seqno;
+    term.print("\x08");
+    term.assert_cursor_pos(0, 0, Some("doesn't wrap off the top"), Some(seqno));
+}
+
 #[test]
 fn test_lf() {
     let mut term = TestTerm::new(3, 10, 0);
@@ -48,3 +74,35 @@ fn test_tab() {
     term.print("\t");
     term.assert_cursor_pos(24, 0, None, None);
 }
+
+#[test]
+fn test_tmux_title_terminator() {
+    let mut term = TestTerm::new(3, 10, 0);
+    term.print("\x1bkone\x1b\\");
+    assert_eq!(term.get_title(), "one");
+
+    // By default, a control code abandons the title
+    term.print("\x1bktwo\nthree");
+    assert_eq!(term.get_title(), "one");
+    assert_visible_contents(&term, file!(), line!(), &["", "three", ""]);
+
+    term.set_config(Arc::new(TestTermConfig {
+        tmux_title_terminator: TmuxTitleTerminator::LineFeed,
+        ..Default::default()
+    }));
+    term.print("\r\x1bkfour\n");
+    assert_eq!(term.get_title(), "four");
+    // ST still works
+    term.print("\x1bkfive\x1b\\");
+    assert_eq!(term.get_title(), "five");
+    // but other control codes abandon the title
+    term.print("\x1bksix\r");
+    assert_eq!(term.get_title(), "five");
+
+    term.set_config(Arc::new(TestTermConfig {
+        tmux_title_terminator: TmuxTitleTerminator::AnyControl,
+        ..Default::default()
+    }));
+    term.print("\x1bkseven\r");
+    assert_eq!(term.get_title(), "seven");
+}
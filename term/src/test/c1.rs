@@ -1,6 +1,7 @@
 //! Testing C1 control sequences
 
 use super::*;
+use k9::assert_equal as assert_eq;
 
 #[test]
 fn test_ind() {
@@ -59,6 +60,22 @@ fn test_hts() {
     term.assert_cursor_pos(32, 1, None, None);
 }
 
+#[test]
+fn test_tab_stop_report() {
+    let mut term = TestTerm::new(3, 25, 0);
+    term.print("\x1b[2$w");
+    assert_eq!(term.read_response(), "\x1bP2$u1/9/17/25\x1b\\");
+
+    // Replace the default tab stops with some custom ones
+    term.print("\x1b[3g");
+    term.print("\x1b[5G\x1bH\x1b[12G\x1bH");
+    term.print("\x1b[2$w");
+    assert_eq!(term.read_response(), "\x1bP2$u5/12\x1b\\");
+
+    term.print("\x1b[3g\x1b[2$w");
+    assert_eq!(term.read_response(), "\x1bP2$u\x1b\\");
+}
+
 #[test]
 fn test_ri() {
     let mut term = TestTerm::new(4, 2, 0);
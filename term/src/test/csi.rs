@@ -341,6 +341,79 @@ fn test_dl() {
     assert_visible_contents(&term, file!(), line!(), &["1", "2", "3"]);
 }
 
+#[test]
+fn test_decic() {
+    let mut term = TestTerm::new(4, 5, 0);
+    term.print("abcde\r\nfghij\r\nklmno\r\npqrst");
+    term.set_scroll_region(1, 2);
+    term.cup(1, 1);
+    term.print("\x1b[2'}");
+    assert_visible_contents(
+        &term,
+        file!(),
+        line!(),
+        &["abcde", "f  gh", "k  lm", "pqrst"],
+    );
+
+    // Respects the right margin
+    term.set_mode("?69", true);
+    term.set_left_and_right_margins(1, 3);
+    term.cup(2, 1);
+    term.print("\x1b['}");
+    assert_visible_contents(
+        &term,
+        file!(),
+        line!(),
+        &["abcde", "f   h", "k   m", "pqrst"],
+    );
+
+    // No effect when the cursor is outside the margins
+    term.cup(4, 1);
+    term.print("\x1b['}");
+    assert_visible_contents(
+        &term,
+        file!(),
+        line!(),
+        &["abcde", "f   h", "k   m", "pqrst"],
+    );
+}
+
+#[test]
+fn test_decdc() {
+    let mut term = TestTerm::new(4, 5, 0);
+    term.print("abcde\r\nfghij\r\nklmno\r\npqrst");
+    term.set_scroll_region(1, 2);
+    term.cup(1, 1);
+    // The blank columns take the background color of the pen
+    term.print("\x1b[41m\x1b[2'~\x1b[0m");
+    assert_visible_contents(
+        &term,
+        file!(),
+        line!(),
+        &["abcde", "fij  ", "kno  ", "pqrst"],
+    );
+    let lines = term.screen().visible_lines();
+    let attrs = lines[1].get_cell(4).unwrap().attrs().clone();
+    k9::assert_equal!(
+        attrs.background(),
+        termwiz::color::ColorAttribute::PaletteIndex(1)
+    );
+    let attrs = lines[1].get_cell(2).unwrap().attrs().clone();
+    k9::assert_equal!(attrs.background(), termwiz::color::ColorAttribute::Default);
+
+    // Respects the right margin
+    term.set_mode("?69", true);
+    term.set_left_and_right_margins(0, 2);
+    term.cup(0, 1);
+    term.print("\x1b['~");
+    assert_visible_contents(
+        &term,
+        file!(),
+        line!(),
+        &["abcde", "ij   ", "no   ", "pqrst"],
+    );
+}
+
 #[test]
 fn test_cha() {
     let mut term = TestTerm::new(3, 4, 0);
@@ -401,3 +474,140 @@ fn test_ed_erase_scrollback() {
     term.print("b");
     assert_all_contents(&term, file!(), line!(), &["111", "222", "ab"]);
 }
+
+#[test]
+fn test_decrqm() {
+    let mut term = TestTerm::new(3, 3, 0);
+
+    // Bracketed paste is reset by default
+    term.print("\x1b[?2004$p");
+    k9::assert_equal!(term.read_response(), "\x1b[?2004;2$y");
+
+    term.set_mode("?2004", true);
+    term.print("\x1b[?2004$p");
+    k9::assert_equal!(term.read_response(), "\x1b[?2004;1$y");
+
+    // Auto wrap was enabled by TestTerm::new
+    term.print("\x1b[?7$p");
+    k9::assert_equal!(term.read_response(), "\x1b[?7;1$y");
+
+    term.set_mode("?1049", true);
+    term.print("\x1b[?1049$p");
+    k9::assert_equal!(term.read_response(), "\x1b[?1049;1$y");
+    term.set_mode("?1049", false);
+    term.print("\x1b[?1049$p");
+    k9::assert_equal!(term.read_response(), "\x1b[?1049;2$y");
+
+    // An unknown mode is reported as not recognized
+    term.print("\x1b[?31337$p");
+    k9::assert_equal!(term.read_response(), "\x1b[?31337;0$y");
+    term.print("\x1b[31337$p");
+    k9::assert_equal!(term.read_response(), "\x1b[31337;0$y");
+}
+
+#[test]
+fn test_decstr() {
+    let mut term = TestTerm::new(5, 5, 0);
+    term.print("hello");
+    term.cup(0, 4);
+    term.print("world");
+
+    term.set_scroll_region(1, 3);
+    term.set_mode("?6", true);
+    term.set_mode("?25", false);
+    term.print("\x1b[1m");
+    // Save the cursor, so that we can verify that it is forgotten
+    term.cup(2, 2);
+    term.print("\x1b7");
+
+    term.soft_reset();
+
+    // The screen contents are preserved
+    assert_visible_contents(&term, file!(), line!(), &["hello", "", "", "", "world"]);
+    k9::assert_equal!(term.pen(), CellAttributes::default());
+
+    term.print("\x1b[?6$p");
+    k9::assert_equal!(term.read_response(), "\x1b[?6;2$y");
+    term.print("\x1b[?25$p");
+    k9::assert_equal!(term.read_response(), "\x1b[?25;1$y");
+
+    // With the saved cursor forgotten, restoring it homes the cursor
+    term.print("\x1b8");
+    term.assert_cursor_pos(0, 0, Some("saved cursor was reset"), None);
+
+    // Origin mode is off, so this addresses the bottom row of the
+    // screen, and the margins now cover the whole screen, so a
+    // newline there scrolls everything up
+    term.cup(0, 4);
+    term.assert_cursor_pos(0, 4, Some("origin mode was reset"), None);
+    term.print("\n");
+    assert_visible_contents(&term, file!(), line!(), &["", "", "", "world", ""]);
+}
+
+#[test]
+fn test_report_cell_size_pixels() {
+    let mut term = TestTerm::new(3, 5, 0);
+
+    // TestTerm uses 8x16 pixel cells
+    term.print("\x1b[16t");
+    k9::assert_equal!(term.read_response(), "\x1b[6;16;8t");
+
+    term.resize(TerminalSize {
+        rows: 3,
+        cols: 5,
+        pixel_width: 50,
+        pixel_height: 60,
+        dpi: 0,
+    });
+    term.print("\x1b[16t");
+    k9::assert_equal!(term.read_response(), "\x1b[6;20;10t");
+}
+
+#[test]
+fn test_selective_erase() {
+    let mut term = TestTerm::new(3, 6, 0);
+    term.print("ab\x1b[1\"qcd\x1b[0\"qef");
+    term.cup(0, 1);
+    // DECSCA is not reset by SGR 0
+    term.print("\x1b[1\"q\x1b[0mgh\x1b[0\"qij");
+    term.cup(0, 2);
+    term.print("klm");
+
+    // DECSEL only clears the unprotected cells
+    term.cup(0, 0);
+    term.print("\x1b[?2K");
+    assert_visible_contents(&term, file!(), line!(), &["  cd  ", "ghij", "klm"]);
+
+    // DECSED likewise
+    term.print("\x1b[?2J");
+    assert_visible_contents(&term, file!(), line!(), &["  cd  ", "gh  ", "   "]);
+
+    // A regular EL ignores the protection
+    term.print("\x1b[2K");
+    assert_visible_contents(&term, file!(), line!(), &["      ", "gh  ", "   "]);
+}
+
+#[test]
+fn test_mode_flags() {
+    let mut term = TestTerm::new(3, 10, 0);
+    let initial = term.mode_flags();
+    assert!(initial.dec_auto_wrap);
+    assert!(!initial.bracketed_paste);
+    assert!(!initial.mouse_tracking);
+
+    term.set_mode("?2004", true);
+    term.set_mode("?1000", true);
+    term.set_mode("?7", false);
+    k9::assert_equal!(
+        term.mode_flags(),
+        TerminalModeFlags {
+            bracketed_paste: true,
+            mouse_tracking: true,
+            dec_auto_wrap: false,
+            ..initial
+        }
+    );
+
+    term.set_mode("?1049", true);
+    assert!(term.mode_flags().alt_screen);
+}
@@ -8,8 +8,11 @@ mod c1;
 mod csi;
 // mod selection; FIXME: port to render layer
 use crate::color::ColorPalette;
+use crate::config::{DeviceCapabilities, Osc52ClipboardPolicy, TmuxTitleTerminator};
 use k9::assert_equal as assert_eq;
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use termwiz::escape::csi::{Edit, EraseInDisplay, EraseInLine};
 use termwiz::escape::{OneBased, OperatingSystemCommand, CSI};
 use termwiz::surface::{CursorShape, CursorVisibility, SequenceNo, SEQ_ZERO};
@@ -38,19 +41,71 @@ impl Clipboard for LocalClip {
     }
 }
 
+/// Collects the bytes that the terminal writes back to the
+/// application, delivering them to the test upon flush.
+struct ResponseWriter {
+    buf: Vec<u8>,
+    sender: Sender<Vec<u8>>,
+}
+
+impl std::io::Write for ResponseWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.buf.is_empty() {
+            self.sender.send(std::mem::take(&mut self.buf)).ok();
+        }
+        Ok(())
+    }
+}
+
 struct TestTerm {
     term: Terminal,
+    responses: Receiver<Vec<u8>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct TestTermConfig {
     scrollback: usize,
+    backspace_wraps_to_previous_line: bool,
+    tmux_title_terminator: TmuxTitleTerminator,
+    attach_zero_width_graphemes: bool,
+    bell_disabled: bool,
+    device_capabilities: DeviceCapabilities,
+    osc52_clipboard_policy: Osc52ClipboardPolicy,
 }
 impl TerminalConfiguration for TestTermConfig {
     fn scrollback_size(&self) -> usize {
         self.scrollback
     }
 
+    fn backspace_wraps_to_previous_line(&self) -> bool {
+        self.backspace_wraps_to_previous_line
+    }
+
+    fn tmux_title_terminator(&self) -> TmuxTitleTerminator {
+        self.tmux_title_terminator
+    }
+
+    fn attach_zero_width_graphemes(&self) -> bool {
+        self.attach_zero_width_graphemes
+    }
+
+    fn bell_disabled(&self) -> bool {
+        self.bell_disabled
+    }
+
+    fn device_capabilities(&self) -> DeviceCapabilities {
+        self.device_capabilities
+    }
+
+    fn osc52_clipboard_policy(&self) -> Osc52ClipboardPolicy {
+        self.osc52_clipboard_policy
+    }
+
     fn color_palette(&self) -> ColorPalette {
         ColorPalette::default()
     }
@@ -63,6 +118,7 @@ impl TestTerm {
             .filter_level(log::LevelFilter::Trace)
             .try_init();
 
+        let (sender, responses) = channel();
         let mut term = Terminal::new(
             TerminalSize {
                 rows: height,
@@ -71,15 +127,21 @@ impl TestTerm {
                 pixel_height: height * 16,
                 dpi: 0,
             },
-            Arc::new(TestTermConfig { scrollback }),
+            Arc::new(TestTermConfig {
+                scrollback,
+                ..Default::default()
+            }),
             "WezTerm",
             "O_o",
-            Box::new(Vec::new()),
+            Box::new(ResponseWriter {
+                buf: vec![],
+                sender,
+            }),
         );
         let clip: Arc<dyn Clipboard> = Arc::new(LocalClip::new());
         term.set_clipboard(&clip);
 
-        let mut term = Self { term };
+        let mut term = Self { term, responses };
 
         term.set_auto_wrap(true);
 
@@ -90,6 +152,16 @@ impl TestTerm {
         self.term.advance_bytes(bytes);
     }
 
+    /// Returns the next response that the terminal sent back
+    /// to the application, waiting briefly for it to arrive.
+    fn read_response(&self) -> String {
+        let data = self
+            .responses
+            .recv_timeout(Duration::from_secs(5))
+            .expect("terminal to send a response");
+        String::from_utf8(data).expect("response to be utf8")
+    }
+
     fn set_mode(&mut self, mode: &str, enable: bool) {
         self.print(CSI);
         self.print(mode);
@@ -310,6 +382,66 @@ fn assert_all_contents(term: &Terminal, file: &str, line: u32, expect_lines: &[&
     assert_lines_equal(file, line, &screen.all_lines(), &expect, Compare::TEXT);
 }
 
+#[test]
+fn test_large_bracketed_paste() {
+    let mut term = TestTerm::new(3, 10, 0);
+    term.set_mode("?2004", true);
+
+    // Use a multi-byte character so that the chunk boundaries don't
+    // fall on character boundaries
+    let text = "\u{1f4a9}".repeat(PASTE_CHUNK_THRESHOLD);
+    term.send_paste(&text).unwrap();
+
+    let mut chunks = vec![];
+    let mut total = 0;
+    while total < text.len() + 12 {
+        let chunk = term.read_response();
+        total += chunk.len();
+        chunks.push(chunk);
+    }
+    assert!(chunks.len() > 1);
+
+    let last = chunks.len() - 1;
+    for (idx, chunk) in chunks.iter().enumerate() {
+        assert!(chunk.len() <= PASTE_CHUNK_SIZE + 6);
+        assert_eq!(chunk.starts_with("\x1b[200~"), idx == 0);
+        assert_eq!(chunk.ends_with("\x1b[201~"), idx == last);
+        let stripped = chunk.replace("\x1b[200~", "").replace("\x1b[201~", "");
+        assert_eq!(
+            chunk.len() - stripped.len(),
+            (idx == 0) as usize * 6 + (idx == last) as usize * 6
+        );
+    }
+
+    assert_eq!(chunks.concat(), format!("\x1b[200~{text}\x1b[201~"));
+}
+
+#[test]
+fn test_small_paste() {
+    let mut term = TestTerm::new(3, 10, 0);
+    term.set_mode("?2004", true);
+    term.send_paste("hello").unwrap();
+    assert_eq!(term.read_response(), "\x1b[200~hello\x1b[201~");
+}
+
+#[test]
+fn test_paste_end_marker_is_filtered() {
+    let mut term = TestTerm::new(3, 10, 0);
+    term.set_mode("?2004", true);
+    term.send_paste("a\x1b[201~b").unwrap();
+    assert_eq!(term.read_response(), "\x1b[200~ab\x1b[201~");
+
+    // Removing the inner marker must not leave behind another one
+    term.send_paste("a\x1b[2\x1b[201~01~b").unwrap();
+    assert_eq!(term.read_response(), "\x1b[200~ab\x1b[201~");
+
+    // The 8-bit form is filtered too, and filtering applies even
+    // when bracketed paste is not enabled
+    term.set_mode("?2004", false);
+    term.send_paste("a\u{9b}201~b").unwrap();
+    assert_eq!(term.read_response(), "ab");
+}
+
 #[test]
 fn test_semantic_1539() {
     use termwiz::escape::osc::FinalTermSemanticPrompt;
@@ -346,6 +478,153 @@ fn test_semantic_1539() {
     );
 }
 
+#[test]
+fn test_prompt_rows() {
+    use termwiz::escape::osc::FinalTermSemanticPrompt;
+    let mut term = TestTerm::new(3, 10, 10);
+
+    let start_prompt = format!(
+        "{}",
+        OperatingSystemCommand::FinalTermSemanticPrompt(
+            FinalTermSemanticPrompt::FreshLineAndStartPrompt {
+                aid: None,
+                cl: None
+            }
+        )
+    );
+    let end_prompt = format!(
+        "{}",
+        OperatingSystemCommand::FinalTermSemanticPrompt(
+            FinalTermSemanticPrompt::MarkEndOfPromptAndStartOfInputUntilNextMarker
+        )
+    );
+
+    term.print(format!("{start_prompt}> {end_prompt}ls\r\n"));
+    term.print("one\r\ntwo");
+    term.print(format!("{start_prompt}> {end_prompt}pwd\r\n"));
+    term.print("/tmp");
+    assert_eq!(term.get_prompt_rows(), vec![0, 3]);
+
+    // StartPrompt on its own marks the current line
+    term.print(format!(
+        "\r\n{}$ ",
+        OperatingSystemCommand::FinalTermSemanticPrompt(FinalTermSemanticPrompt::StartPrompt(
+            Default::default()
+        ))
+    ));
+    assert_eq!(term.get_prompt_rows(), vec![0, 3, 5]);
+
+    // Rows are not tracked for the alternate screen
+    term.set_mode("?1049", true);
+    assert_eq!(term.get_prompt_rows(), vec![]);
+    term.print(format!("{start_prompt}> "));
+    term.set_mode("?1049", false);
+    assert_eq!(term.get_prompt_rows(), vec![0, 3, 5]);
+
+    // Rows that fall out of the scrollback are dropped
+    term.print("\r\n".repeat(11));
+    assert_eq!(term.get_prompt_rows(), vec![5]);
+
+    // and so are all of them upon a full reset
+    term.print(format!("{start_prompt}> "));
+    assert_eq!(term.get_prompt_rows(), vec![5, 16]);
+    term.print("\x1bc");
+    assert_eq!(term.get_prompt_rows(), vec![]);
+}
+
+#[test]
+fn test_mark_rows() {
+    let mut term = TestTerm::new(3, 10, 10);
+    let alerts = Arc::new(Mutex::new(vec![]));
+    term.set_notification_handler(Box::new(AlertRecorder(Arc::clone(&alerts))));
+
+    term.print("one\x1b]1337;SetMark\x1b\\\r\ntwo\r\nthree");
+    term.print("\x1b]1337;SetMark\x1b\\");
+    // Marking the same row again doesn't duplicate it
+    term.print("\x1b]1337;SetMark\x1b\\");
+    assert_eq!(term.get_mark_rows(), vec![0, 2]);
+
+    // Rows are not tracked for the alternate screen
+    term.set_mode("?1049", true);
+    assert_eq!(term.get_mark_rows(), vec![]);
+    term.print("\x1b]1337;SetMark\x1b\\");
+    term.set_mode("?1049", false);
+    assert_eq!(term.get_mark_rows(), vec![0, 2]);
+
+    // Rows that fall out of the scrollback are dropped
+    term.print("\r\n".repeat(11));
+    assert_eq!(term.get_mark_rows(), vec![2]);
+
+    assert!(alerts.lock().unwrap().is_empty());
+    term.print("\x1b]1337;StealFocus\x1b\\");
+    assert_eq!(*alerts.lock().unwrap(), vec![Alert::StealFocus]);
+
+    // A full reset drops all of the marks
+    term.print("\x1b]1337;SetMark\x1b\\");
+    assert_eq!(term.get_mark_rows(), vec![2, 13]);
+    term.print("\x1bc");
+    assert_eq!(term.get_mark_rows(), vec![]);
+}
+
+#[test]
+fn test_error_prompt_rows() {
+    use termwiz::escape::osc::FinalTermSemanticPrompt;
+    let mut term = TestTerm::new(3, 10, 10);
+
+    let start_prompt = format!(
+        "{}",
+        OperatingSystemCommand::FinalTermSemanticPrompt(
+            FinalTermSemanticPrompt::FreshLineAndStartPrompt {
+                aid: None,
+                cl: None
+            }
+        )
+    );
+    let end_prompt = format!(
+        "{}",
+        OperatingSystemCommand::FinalTermSemanticPrompt(
+            FinalTermSemanticPrompt::MarkEndOfPromptAndStartOfInputUntilNextMarker
+        )
+    );
+    let status = |status| {
+        format!(
+            "{}",
+            OperatingSystemCommand::FinalTermSemanticPrompt(
+                FinalTermSemanticPrompt::CommandStatus { status, aid: None }
+            )
+        )
+    };
+
+    term.print(format!("{start_prompt}> {end_prompt}true\r\n{}", status(0)));
+    term.print(format!(
+        "{start_prompt}> {end_prompt}false\r\n{}",
+        status(1)
+    ));
+    term.print(format!(
+        "{start_prompt}> {end_prompt}ls\r\nfoo\r\n{}",
+        status(0)
+    ));
+    term.print(format!(
+        "{start_prompt}> {end_prompt}nope\r\n{}",
+        status(127)
+    ));
+    // No status has been reported for the current prompt yet
+    term.print(format!("{start_prompt}> {end_prompt}"));
+
+    assert_eq!(term.get_prompt_rows(), vec![0, 1, 2, 4, 5]);
+    assert_eq!(term.get_error_prompt_rows(), vec![1, 4]);
+
+    // The statuses survive a resize
+    term.resize(TerminalSize {
+        rows: 3,
+        cols: 12,
+        pixel_width: 12,
+        pixel_height: 3,
+        dpi: 0,
+    });
+    assert_eq!(term.get_error_prompt_rows(), vec![1, 4]);
+}
+
 #[test]
 fn test_semantic() {
     use termwiz::escape::osc::FinalTermSemanticPrompt;
@@ -1162,6 +1441,27 @@ fn test_emoji_with_modifier() {
     );
 }
 
+#[test]
+fn test_zero_width_grapheme() {
+    // The SGR sequence causes the combining acute accent to be
+    // processed separately from the "e" that precedes it
+    let text = "e\x1b[m\u{301}x";
+
+    let mut term = TestTerm::new(2, 5, 0);
+    term.print(text);
+    assert_visible_contents(&term, file!(), line!(), &["ex", ""]);
+
+    term.set_config(Arc::new(TestTermConfig {
+        attach_zero_width_graphemes: true,
+        ..Default::default()
+    }));
+    term.print("\r\n");
+    let seqno = term.current_seqno();
+    term.print(text);
+    assert_visible_contents(&term, file!(), line!(), &["ex", "e\u{301}x"]);
+    term.assert_cursor_pos(2, 1, None, Some(seqno));
+}
+
 #[test]
 fn test_1573() {
     let sequence = "\u{1112}\u{1161}\u{11ab}";
@@ -1265,3 +1565,291 @@ fn test_hyperlinks() {
         Compare::TEXT | Compare::ATTRS,
     );
 }
+
+#[test]
+fn test_focus_tracking() {
+    let mut term = TestTerm::new(3, 10, 0);
+    term.focus_changed(false);
+    term.focus_changed(true);
+
+    term.set_mode("?1004", true);
+    term.focus_changed(false);
+    assert_eq!(term.read_response(), "\x1b[O");
+    term.focus_changed(true);
+    assert_eq!(term.read_response(), "\x1b[I");
+
+    // No report if the focus state didn't change
+    term.focus_changed(true);
+    term.set_mode("?1004", false);
+    term.focus_changed(false);
+    assert!(term.responses.try_recv().is_err());
+}
+
+#[test]
+fn test_modified_function_key() {
+    let mut term = TestTerm::new(3, 10, 0);
+    let mods = KeyModifiers::CTRL | KeyModifiers::ALT;
+    term.key_down(KeyCode::Function(1), mods).unwrap();
+    assert_eq!(term.read_response(), "\x1b[1;7P");
+    term.key_down(KeyCode::Function(12), mods).unwrap();
+    assert_eq!(term.read_response(), "\x1b[24;7~");
+}
+
+struct AlertRecorder(Arc<Mutex<Vec<Alert>>>);
+
+impl AlertHandler for AlertRecorder {
+    fn alert(&mut self, alert: Alert) {
+        self.0.lock().unwrap().push(alert);
+    }
+}
+
+#[test]
+fn test_osc_reset_fires_palette_changed() {
+    let mut term = TestTerm::new(3, 10, 0);
+    let alerts = Arc::new(Mutex::new(vec![]));
+    term.set_notification_handler(Box::new(AlertRecorder(Arc::clone(&alerts))));
+    let default_palette = term.palette();
+
+    // Resetting colors that were never changed has no effect
+    term.print("\x1b]104\x1b\\");
+    assert!(alerts.lock().unwrap().is_empty());
+
+    term.print("\x1b]4;1;rgb:12/34/56\x1b\\\x1b]10;rgb:ab/cd/ef\x1b\\");
+    assert_ne!(term.palette(), default_palette);
+    alerts.lock().unwrap().clear();
+
+    term.print("\x1b]104;1\x1b\\");
+    assert_eq!(*alerts.lock().unwrap(), vec![Alert::PaletteChanged]);
+
+    term.print("\x1b]110\x1b\\");
+    assert_eq!(
+        *alerts.lock().unwrap(),
+        vec![Alert::PaletteChanged, Alert::PaletteChanged]
+    );
+    assert_eq!(term.palette(), default_palette);
+}
+
+#[test]
+fn test_bell_disabled() {
+    let mut term = TestTerm::new(3, 10, 0);
+    let alerts = Arc::new(Mutex::new(vec![]));
+    term.set_notification_handler(Box::new(AlertRecorder(Arc::clone(&alerts))));
+
+    term.print("\x07");
+    assert_eq!(*alerts.lock().unwrap(), vec![Alert::Bell]);
+    alerts.lock().unwrap().clear();
+
+    term.set_config(Arc::new(TestTermConfig {
+        bell_disabled: true,
+        ..Default::default()
+    }));
+    term.print("\x07");
+    assert!(alerts.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_device_attributes() {
+    let mut term = TestTerm::new(3, 10, 0);
+    term.print("\x1b[c");
+    assert_eq!(term.read_response(), "\x1b[?65;4;6;18;22c");
+    term.print("\x1b[>c");
+    assert_eq!(term.read_response(), "\x1b[>1;277;0c");
+
+    term.set_config(Arc::new(TestTermConfig {
+        device_capabilities: DeviceCapabilities {
+            sixel_graphics: false,
+            windowing_extensions: false,
+            firmware_version: 280,
+            ..DeviceCapabilities::default()
+        },
+        ..Default::default()
+    }));
+    term.print("\x1b[c");
+    assert_eq!(term.read_response(), "\x1b[?65;6;22c");
+    term.print("\x1b[>c");
+    assert_eq!(term.read_response(), "\x1b[>1;280;0c");
+}
+
+#[test]
+fn test_osc52_clipboard_policy() {
+    let mut term = TestTerm::new(3, 10, 0);
+    let clip = Arc::new(LocalClip::new());
+    let dyn_clip: Arc<dyn Clipboard> = clip.clone();
+    term.set_clipboard(&dyn_clip);
+
+    term.set_config(Arc::new(TestTermConfig {
+        osc52_clipboard_policy: Osc52ClipboardPolicy::Deny,
+        ..Default::default()
+    }));
+    term.print("\x1b]52;c;aGVsbG8=\x1b\\");
+    assert_eq!(*clip.clip.lock().unwrap(), None);
+
+    term.set_config(Arc::new(TestTermConfig {
+        osc52_clipboard_policy: Osc52ClipboardPolicy::Allow,
+        ..Default::default()
+    }));
+    term.print("\x1b]52;c;aGVsbG8=\x1b\\");
+    assert_eq!(*clip.clip.lock().unwrap(), Some("hello".to_string()));
+
+    // Clearing the clipboard is a write, too
+    term.set_config(Arc::new(TestTermConfig {
+        osc52_clipboard_policy: Osc52ClipboardPolicy::Deny,
+        ..Default::default()
+    }));
+    term.print("\x1b]52;c\x1b\\");
+    assert_eq!(*clip.clip.lock().unwrap(), Some("hello".to_string()));
+}
+
+#[test]
+fn test_synchronized_output() {
+    let mut term = TestTerm::new(3, 10, 0);
+    let alerts = Arc::new(Mutex::new(vec![]));
+    term.set_notification_handler(Box::new(AlertRecorder(Arc::clone(&alerts))));
+    assert!(!term.synchronized_output_enabled());
+
+    term.set_mode("?2026", true);
+    assert!(term.synchronized_output_enabled());
+    term.print("\x1b[?2026$p");
+    assert_eq!(term.read_response(), "\x1b[?2026;1$y");
+
+    // Setting it again doesn't raise another alert
+    term.set_mode("?2026", true);
+    term.set_mode("?2026", false);
+    assert!(!term.synchronized_output_enabled());
+    term.print("\x1b[?2026$p");
+    assert_eq!(term.read_response(), "\x1b[?2026;2$y");
+
+    // A soft reset ends the update
+    term.set_mode("?2026", true);
+    term.print("\x1b[!p");
+    assert!(!term.synchronized_output_enabled());
+
+    assert_eq!(
+        *alerts.lock().unwrap(),
+        vec![
+            Alert::SynchronizedOutputChanged(true),
+            Alert::SynchronizedOutputChanged(false),
+            Alert::SynchronizedOutputChanged(true),
+            Alert::SynchronizedOutputChanged(false),
+        ]
+    );
+}
+
+#[test]
+fn test_print_buffer_flush_threshold() {
+    use crate::terminalstate::performer::Performer;
+    use termwiz::escape::Action;
+
+    let mut term = TestTerm::new(1, 10_000, 0);
+    let threshold = term.get_config().print_buffer_flush_threshold();
+
+    fn line_text(state: &TerminalState) -> String {
+        state.screen().visible_lines()[0]
+            .as_str()
+            .trim_end()
+            .to_string()
+    }
+
+    let xs = "x".repeat(threshold - 1);
+    {
+        let mut performer = Performer::new(&mut term.term);
+        for c in xs.chars() {
+            performer.perform(Action::Print(c));
+        }
+        assert_eq!(line_text(&performer), "");
+
+        // Reaching the threshold flushes everything except for the last
+        // grapheme, which the combining mark that follows extends
+        performer.perform(Action::Print('e'));
+        assert_eq!(line_text(&performer), xs);
+
+        performer.perform(Action::Print('\u{301}'));
+    }
+
+    assert_eq!(line_text(&term), format!("{xs}e\u{301}"));
+}
+
+#[test]
+fn test_print_buffer_flush_threshold_single_grapheme() {
+    use crate::terminalstate::performer::Performer;
+    use termwiz::escape::Action;
+
+    let mut term = TestTerm::new(1, 10, 0);
+    let threshold = term.get_config().print_buffer_flush_threshold();
+
+    // A single grapheme that alone exceeds the threshold is flushed
+    // rather than being held back
+    let marks = "\u{301}".repeat(threshold / 2);
+    let mut performer = Performer::new(&mut term.term);
+    performer.perform(Action::Print('a'));
+    for c in marks.chars() {
+        performer.perform(Action::Print(c));
+    }
+    assert_eq!(
+        performer.screen().visible_lines()[0].as_str().trim_end(),
+        format!("a{marks}")
+    );
+}
+
+#[test]
+fn test_explicit_title() {
+    let mut term = TestTerm::new(3, 10, 0);
+    assert!(!term.has_explicit_title());
+
+    term.print("\x1b]2;window\x1b\\");
+    assert!(term.has_explicit_title());
+    assert_eq!(term.get_title(), "window");
+
+    term.print("\x1b]2;\x1b\\");
+    assert!(!term.has_explicit_title());
+
+    term.print("\x1b]1;icon\x1b\\");
+    assert!(term.has_explicit_title());
+    assert_eq!(term.get_title(), "icon");
+
+    term.print("\x1bc");
+    assert!(!term.has_explicit_title());
+
+    term.print("\x1b]0;both\x1b\\");
+    assert!(term.has_explicit_title());
+    assert_eq!(term.get_title(), "both");
+}
+
+#[test]
+fn test_unicode_version_negotiation() {
+    let mut term = TestTerm::new(3, 10, 0);
+
+    term.print("\x1b]1337;UnicodeVersion=8\x1b\\");
+    term.print("\x1b]1337;UnicodeVersion=?\x1b\\");
+    assert_eq!(term.read_response(), "\x1b]1337;UnicodeVersion=8\x1b\\");
+    assert_eq!(term.unicode_version().version, 8);
+
+    // The watch emoji was widened in unicode 9
+    term.print("\u{231a}x");
+    term.assert_cursor_pos(2, 0, None, Some(0));
+
+    term.print("\r\n\x1b]1337;UnicodeVersion=9\x1b\\");
+    let seqno = term.current_seqno();
+    term.print("\x1b]1337;UnicodeVersion=?\x1b\\");
+    assert_eq!(term.read_response(), "\x1b]1337;UnicodeVersion=9\x1b\\");
+
+    term.print("\u{231a}x");
+    term.assert_cursor_pos(3, 1, None, Some(seqno));
+}
+
+#[test]
+fn test_alt_screen_restores_cursor_shape() {
+    let mut term = TestTerm::new(3, 10, 0);
+
+    for mode in ["?1047", "?1049", "?47"] {
+        term.print("\x1b[5 q");
+        assert_eq!(term.cursor_pos().shape, CursorShape::BlinkingBar);
+
+        term.set_mode(mode, true);
+        term.print("\x1b[2 q");
+        assert_eq!(term.cursor_pos().shape, CursorShape::SteadyBlock);
+
+        term.set_mode(mode, false);
+        assert_eq!(term.cursor_pos().shape, CursorShape::BlinkingBar);
+    }
+}
@@ -276,6 +276,9 @@ impl CellAttributes {
     bitfield!(overline, set_overline, 12);
     bitfield!(semantic_type, set_semantic_type, SemanticType, 0b11, 13);
     bitfield!(vertical_align, set_vertical_align, VerticalAlign, 0b11, 15);
+    // Set by DECSCA; protected cells are skipped by the selective
+    // erase operations (DECSED, DECSEL).
+    bitfield!(protected, set_protected, 17);
 
     pub const fn blank() -> Self {
         Self {
@@ -508,6 +511,8 @@ impl CellAttributes {
         res.set_underline(Underline::None);
         res.set_overline(false);
         res.set_strikethrough(false);
+        // Erased cells are never protected
+        res.set_protected(false);
         res
     }
 
@@ -26,6 +26,14 @@ pub enum CSI {
 
     Mouse(MouseReport),
 
+    /// Sent by the terminal to the application when focus tracking
+    /// is enabled and the focus state of the terminal changes.
+    /// Only `CSI O` is parsed into this variant: `CSI I` is also a valid
+    /// `CHT` and is parsed as `Cursor::ForwardTabulation(1)` so that
+    /// applications can continue to use it to move the cursor.
+    /// Use `FocusReport::from_csi` to recognize either report.
+    Focus(FocusReport),
+
     Window(Box<Window>),
 
     Keyboard(Keyboard),
@@ -33,6 +41,9 @@ pub enum CSI {
     /// ECMA-48 SCP
     SelectCharacterPath(CharacterPath, i64),
 
+    /// DECSCA - Select Character Protection Attribute
+    SelectCharacterProtection(CharacterProtection),
+
     /// Unknown or unspecified; should be rare and is rather
     /// large, so it is boxed and kept outside of the enum
     /// body to help reduce space usage in the common cases.
@@ -87,6 +98,14 @@ pub enum CharacterPath {
     RightToLeftOrBottomToTop,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharacterProtection {
+    /// 0 or 2; characters can be erased by the selective erase functions
+    Unprotected,
+    /// 1; characters are preserved by the selective erase functions
+    Protected,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Unspecified {
     pub params: Vec<CsiParam>,
@@ -121,6 +140,7 @@ impl Display for CSI {
             CSI::Mode(mode) => mode.fmt(f)?,
             CSI::Unspecified(unspec) => unspec.fmt(f)?,
             CSI::Mouse(mouse) => mouse.fmt(f)?,
+            CSI::Focus(focus) => focus.fmt(f)?,
             CSI::Device(dev) => dev.fmt(f)?,
             CSI::Window(window) => window.fmt(f)?,
             CSI::Keyboard(Keyboard::SetKittyState { flags, mode }) => {
@@ -144,6 +164,8 @@ impl Display for CSI {
                     (a, n) => write!(f, "{};{} k", a, n)?,
                 }
             }
+            CSI::SelectCharacterProtection(CharacterProtection::Unprotected) => write!(f, "0\"q")?,
+            CSI::SelectCharacterProtection(CharacterProtection::Protected) => write!(f, "1\"q")?,
         };
         Ok(())
     }
@@ -359,6 +381,9 @@ pub enum Device {
     /// https://gitlab.gnome.org/GNOME/vte/-/issues/235
     RequestTerminalNameAndVersion,
     RequestTerminalParameters(i64),
+    /// DECRQPSR with `Ps=2`, requesting DECTABSR, the tab stop report
+    /// https://vt100.net/docs/vt510-rm/DECRQPSR.html
+    RequestTabStopReport,
     XtSmGraphics(XtSmGraphics),
 }
 
@@ -380,6 +405,7 @@ impl Display for Device {
             Device::RequestTerminalNameAndVersion => write!(f, ">q")?,
             Device::RequestTerminalParameters(n) => write!(f, "{};1;1;128;128;1;0x", n + 2)?,
             Device::StatusReport => write!(f, "5n")?,
+            Device::RequestTabStopReport => write!(f, "2$w")?,
             Device::XtSmGraphics(g) => {
                 write!(f, "?{};{}", g.item, g.action_or_status)?;
                 for v in &g.value {
@@ -561,6 +587,39 @@ impl Display for Window {
     }
 }
 
+/// Reports a change in the focus state of the terminal; see
+/// `DecPrivateModeCode::FocusTracking`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusReport {
+    /// `CSI I`
+    In,
+    /// `CSI O`
+    Out,
+}
+
+impl FocusReport {
+    /// Returns the focus report corresponding to the parsed `csi`, if any.
+    /// A focus-in report is encoded identically to `CHT` with its default
+    /// parameter, and so is parsed as `Cursor::ForwardTabulation(1)`;
+    /// this method recognizes that form as well as `CSI::Focus`.
+    pub fn from_csi(csi: &CSI) -> Option<Self> {
+        match csi {
+            CSI::Focus(focus) => Some(*focus),
+            CSI::Cursor(Cursor::ForwardTabulation(1)) => Some(Self::In),
+            _ => None,
+        }
+    }
+}
+
+impl Display for FocusReport {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match self {
+            Self::In => write!(f, "I"),
+            Self::Out => write!(f, "O"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MouseReport {
     SGR1006 {
@@ -1127,6 +1186,34 @@ pub enum Edit {
 
     /// REP - Repeat the preceding character n times
     Repeat(u32),
+
+    /// DECIC - Insert Column
+    /// Inserts n blank columns into the scrolling region, starting at the
+    /// column that contains the cursor.  Columns between the cursor and the
+    /// right margin move to the right; columns shifted past the right margin
+    /// are lost.
+    /// https://vt100.net/docs/vt510-rm/DECIC.html
+    InsertColumn(u32),
+
+    /// DECDC - Delete Column
+    /// Deletes n columns from the scrolling region, starting with the column
+    /// that contains the cursor.  Columns between the cursor and the right
+    /// margin move to the left, and blank columns are inserted at the right
+    /// margin.
+    /// https://vt100.net/docs/vt510-rm/DECDC.html
+    DeleteColumn(u32),
+
+    /// DECSED - Selective Erase in Display
+    /// Like ED, but only erases characters that have not been marked
+    /// as protected via DECSCA.
+    /// https://vt100.net/docs/vt510-rm/DECSED.html
+    SelectiveEraseInDisplay(EraseInDisplay),
+
+    /// DECSEL - Selective Erase in Line
+    /// Like EL, but only erases characters that have not been marked
+    /// as protected via DECSCA.
+    /// https://vt100.net/docs/vt510-rm/DECSEL.html
+    SelectiveEraseInLine(EraseInLine),
 }
 
 trait EncodeCSIParam {
@@ -1177,6 +1264,16 @@ impl Display for Edit {
             Edit::ScrollUp(n) => n.write_csi(f, "S")?,
             Edit::EraseInDisplay(n) => n.write_csi(f, "J")?,
             Edit::Repeat(n) => n.write_csi(f, "b")?,
+            Edit::InsertColumn(n) => n.write_csi(f, "'}")?,
+            Edit::DeleteColumn(n) => n.write_csi(f, "'~")?,
+            Edit::SelectiveEraseInDisplay(n) => {
+                write!(f, "?")?;
+                n.write_csi(f, "J")?
+            }
+            Edit::SelectiveEraseInLine(n) => {
+                write!(f, "?")?;
+                n.write_csi(f, "K")?
+            }
         }
         Ok(())
     }
@@ -1622,6 +1719,31 @@ impl CSI {
             orig_params: params,
         }
     }
+
+    /// Returns the canonical encoding of this sequence, including
+    /// the `CSI` introducer.
+    /// This is the same as the `Display` form, except that the
+    /// parameter bytes of a `CSI::Unspecified` sequence are emitted
+    /// as-is rather than being re-encoded as UTF-8.
+    /// Use `Parser::last_raw_csi()` if the exact bytes that were originally
+    /// received are needed instead.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            CSI::Unspecified(unspec) => {
+                let mut bytes = b"\x1b[".to_vec();
+                for p in &unspec.params {
+                    match p {
+                        CsiParam::Integer(v) => bytes.extend_from_slice(v.to_string().as_bytes()),
+                        CsiParam::P(p) => bytes.push(*p),
+                    }
+                }
+                let mut control = [0u8; 4];
+                bytes.extend_from_slice(unspec.control.encode_utf8(&mut control).as_bytes());
+                bytes
+            }
+            _ => self.to_string().into_bytes(),
+        }
+    }
 }
 
 /// A little helper to convert i64 -> u8 if safe
@@ -1729,7 +1851,14 @@ impl<'a> CSIParser<'a> {
         match (self.control, self.orig_params) {
             ('k', [.., CsiParam::P(b' ')]) => self.select_character_path(params),
             ('q', [.., CsiParam::P(b' ')]) => self.cursor_style(params),
+            ('q', [.., CsiParam::P(b'"')]) => self.select_character_protection(params),
             ('y', [.., CsiParam::P(b'*')]) => self.checksum_area(params),
+            ('}', [.., CsiParam::P(b'\'')]) => self
+                .column_edit(params)
+                .map(|n| CSI::Edit(Edit::InsertColumn(n))),
+            ('~', [.., CsiParam::P(b'\'')]) => self
+                .column_edit(params)
+                .map(|n| CSI::Edit(Edit::DeleteColumn(n))),
 
             ('c', [CsiParam::P(b'='), ..]) => self
                 .req_tertiary_device_attributes(params)
@@ -1747,10 +1876,17 @@ impl<'a> CSIParser<'a> {
                 .map(|dev| CSI::Device(Box::new(dev))),
 
             ('S', [CsiParam::P(b'?'), ..]) => XtSmGraphics::parse(params),
+            ('J', [CsiParam::P(b'?'), ..]) => ParseParams::parse_params(self.focus(params, 1, 0))
+                .map(|n| CSI::Edit(Edit::SelectiveEraseInDisplay(n))),
+            ('K', [CsiParam::P(b'?'), ..]) => ParseParams::parse_params(self.focus(params, 1, 0))
+                .map(|n| CSI::Edit(Edit::SelectiveEraseInLine(n))),
             ('p', [CsiParam::Integer(_), CsiParam::P(b'$')])
             | ('p', [CsiParam::P(b'?'), CsiParam::Integer(_), CsiParam::P(b'$')]) => {
                 self.decrqm(params)
             }
+            ('w', [CsiParam::Integer(2), CsiParam::P(b'$')]) => {
+                Ok(CSI::Device(Box::new(Device::RequestTabStopReport)))
+            }
             ('h', [CsiParam::P(b'?'), ..]) => self
                 .dec(self.focus(params, 1, 0))
                 .map(|mode| CSI::Mode(Mode::SetDecPrivateMode(mode))),
@@ -1844,6 +1980,8 @@ impl<'a> CSIParser<'a> {
                 'K' => parse!(Edit, EraseInLine, params),
                 'L' => parse!(Edit, InsertLine, params),
                 'M' => parse!(Edit, DeleteLine, params),
+                // `CSI I` is deliberately left to parse as CHT; see CSI::Focus
+                'O' if params.is_empty() => Ok(CSI::Focus(FocusReport::Out)),
                 'P' => parse!(Edit, DeleteCharacter, params),
                 'R' => parse!(Cursor, ActivePositionReport, line, col, params),
                 'S' => parse!(Edit, ScrollUp, params),
@@ -1948,6 +2086,28 @@ impl<'a> CSIParser<'a> {
         }
     }
 
+    /// Parse the count for DECIC/DECDC, which is followed by a `'`
+    /// intermediate
+    fn column_edit(&mut self, params: &'a [CsiParam]) -> Result<u32, ()> {
+        ParseParams::parse_params(&params[..params.len() - 1])
+    }
+
+    fn select_character_protection(&mut self, params: &'a [CsiParam]) -> Result<CSI, ()> {
+        let protection = match params {
+            [CsiParam::P(b'"')] | [CsiParam::Integer(0), CsiParam::P(b'"')] => {
+                CharacterProtection::Unprotected
+            }
+            [CsiParam::Integer(2), CsiParam::P(b'"')] => CharacterProtection::Unprotected,
+            [CsiParam::Integer(1), CsiParam::P(b'"')] => CharacterProtection::Protected,
+            _ => return Err(()),
+        };
+        Ok(self.advance_by(
+            params.len(),
+            params,
+            CSI::SelectCharacterProtection(protection),
+        ))
+    }
+
     fn checksum_area(&mut self, params: &'a [CsiParam]) -> Result<CSI, ()> {
         let params = Cracked::parse(&params[..params.len() - 1])?;
 
@@ -3086,6 +3246,116 @@ mod test {
         assert_eq!(res, vec![CSI::Device(Box::new(Device::SoftReset))],);
     }
 
+    #[test]
+    fn insert_delete_column() {
+        let res: Vec<_> = CSI::parse(&[CsiParam::P(b'\'')], false, '}').collect();
+        assert_eq!(encode(&res), "\x1b['}");
+        assert_eq!(res, vec![CSI::Edit(Edit::InsertColumn(1))]);
+
+        let res: Vec<_> =
+            CSI::parse(&[CsiParam::Integer(3), CsiParam::P(b'\'')], false, '~').collect();
+        assert_eq!(encode(&res), "\x1b[3'~");
+        assert_eq!(res, vec![CSI::Edit(Edit::DeleteColumn(3))]);
+    }
+
+    #[test]
+    fn select_character_protection() {
+        let res: Vec<_> =
+            CSI::parse(&[CsiParam::Integer(1), CsiParam::P(b'"')], false, 'q').collect();
+        assert_eq!(encode(&res), "\x1b[1\"q");
+        assert_eq!(
+            res,
+            vec![CSI::SelectCharacterProtection(
+                CharacterProtection::Protected
+            )]
+        );
+
+        let res: Vec<_> =
+            CSI::parse(&[CsiParam::Integer(2), CsiParam::P(b'"')], false, 'q').collect();
+        assert_eq!(encode(&res), "\x1b[0\"q");
+        assert_eq!(
+            res,
+            vec![CSI::SelectCharacterProtection(
+                CharacterProtection::Unprotected
+            )]
+        );
+    }
+
+    #[test]
+    fn selective_erase() {
+        let res: Vec<_> = CSI::parse(&[CsiParam::P(b'?')], false, 'J').collect();
+        assert_eq!(encode(&res), "\x1b[?J");
+        assert_eq!(
+            res,
+            vec![CSI::Edit(Edit::SelectiveEraseInDisplay(
+                EraseInDisplay::EraseToEndOfDisplay
+            ))]
+        );
+
+        let res: Vec<_> =
+            CSI::parse(&[CsiParam::P(b'?'), CsiParam::Integer(2)], false, 'K').collect();
+        assert_eq!(encode(&res), "\x1b[?2K");
+        assert_eq!(
+            res,
+            vec![CSI::Edit(Edit::SelectiveEraseInLine(
+                EraseInLine::EraseLine
+            ))]
+        );
+    }
+
+    #[test]
+    fn request_tab_stop_report() {
+        let res: Vec<_> =
+            CSI::parse(&[CsiParam::Integer(2), CsiParam::P(b'$')], false, 'w').collect();
+        assert_eq!(encode(&res), "\x1b[2$w");
+        assert_eq!(
+            res,
+            vec![CSI::Device(Box::new(Device::RequestTabStopReport))]
+        );
+    }
+
+    #[test]
+    fn kitty_keyboard_report() {
+        for flags in [
+            KittyKeyboardFlags::NONE,
+            KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES,
+            KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES
+                | KittyKeyboardFlags::REPORT_EVENT_TYPES
+                | KittyKeyboardFlags::REPORT_ASSOCIATED_TEXT,
+        ] {
+            let report = vec![CSI::Keyboard(Keyboard::ReportKittyState(flags))];
+            let encoded = encode(&report);
+            assert_eq!(encoded, format!("\x1b[?{}u", flags.bits()));
+
+            let actions = crate::escape::parser::Parser::new().parse_as_vec(encoded.as_bytes());
+            assert_eq!(
+                actions,
+                vec![crate::escape::Action::CSI(CSI::Keyboard(
+                    Keyboard::ReportKittyState(flags)
+                ))]
+            );
+        }
+    }
+
+    #[test]
+    fn focus_report() {
+        let res: Vec<_> = CSI::parse(&[], false, 'O').collect();
+        assert_eq!(encode(&res), "\x1b[O");
+        assert_eq!(res, vec![CSI::Focus(FocusReport::Out)]);
+        assert_eq!(FocusReport::from_csi(&res[0]), Some(FocusReport::Out));
+
+        // Focus-in is indistinguishable from CHT with its default
+        // parameter, which is how it is parsed
+        let res: Vec<_> = CSI::parse(&[], false, 'I').collect();
+        assert_eq!(encode(&res), "\x1b[I");
+        assert_eq!(res, vec![CSI::Cursor(Cursor::ForwardTabulation(1))]);
+        assert_eq!(FocusReport::from_csi(&res[0]), Some(FocusReport::In));
+        assert_eq!(encode(&vec![CSI::Focus(FocusReport::In)]), encode(&res));
+
+        let res: Vec<_> = CSI::parse(&[CsiParam::Integer(2)], false, 'I').collect();
+        assert_eq!(FocusReport::from_csi(&res[0]), None);
+    }
+
     #[test]
     fn device_attr() {
         let res: Vec<_> = CSI::parse(
@@ -3128,4 +3398,55 @@ mod test {
         );
         assert_eq!(encode(&res), "\x1b[?63;1;2;4;6;9;15;22c");
     }
+
+    fn parse_bytes(bytes: &[u8]) -> Vec<CSI> {
+        crate::escape::parser::Parser::new()
+            .parse_as_vec(bytes)
+            .into_iter()
+            .map(|action| match action {
+                crate::escape::Action::CSI(csi) => csi,
+                other => panic!("expected a CSI, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn to_bytes_round_trip() {
+        for &seq in &[
+            &b"\x1b[0m"[..],
+            b"\x1b[1;3;38;2;10;20;30m",
+            b"\x1b[4:3m",
+            b"\x1b[3;7H",
+            b"\x1b[5A",
+            b"\x1b[2J",
+            b"\x1b[3P",
+            b"\x1b[?1049h",
+            b"\x1b[?2026$p",
+            b"\x1b[<0;12;4M",
+            b"\x1b[>1;1u",
+            b"\x1b[?u",
+            b"\x1b[>c",
+            b"\x1b[18t",
+            b"\x1b[2 q",
+            b"\x1b[?1;2;3z",
+        ] {
+            let parsed = parse_bytes(seq);
+            assert!(!parsed.is_empty(), "{:?} didn't parse", seq);
+            for csi in &parsed {
+                let bytes = csi.to_bytes();
+                assert_eq!(bytes, csi.to_string().into_bytes());
+                assert_eq!(parse_bytes(&bytes), vec![csi.clone()]);
+            }
+        }
+
+        // A parameter byte outside of the ASCII range is emitted as-is
+        // by to_bytes, whereas Display encodes it as UTF-8
+        let csi = CSI::Unspecified(Box::new(Unspecified {
+            params: vec![CsiParam::P(0xb5), CsiParam::Integer(1)],
+            parameters_truncated: false,
+            control: 'x',
+        }));
+        assert_eq!(csi.to_bytes(), b"\x1b[\xb51x".to_vec());
+        assert_eq!(csi.to_string().into_bytes(), b"\x1b[\xc2\xb51x".to_vec());
+    }
 }
@@ -43,7 +43,7 @@ pub enum Action {
     Sixel(Box<Sixel>),
     /// A list of termcap, terminfo names for which the application
     /// wants information
-    XtGetTcap(Vec<String>),
+    XtGetTcap(Vec<TcapName>),
     KittyImage(Box<KittyImage>),
 }
 
@@ -83,6 +83,30 @@ fn action_size() {
     assert_eq!(std::mem::size_of::<Esc>(), 4);
 }
 
+/// A name requested via XTGETTCAP.
+/// The names are transmitted hex encoded; `valid` is true when
+/// the name was well-formed hex that decoded to valid UTF-8, in
+/// which case `name` holds the decoded name.  Otherwise `name`
+/// holds the undecoded data as it was received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcapName {
+    pub name: String,
+    pub valid: bool,
+}
+
+impl TcapName {
+    /// Decode a hex encoded name
+    pub fn decode(data: &[u8]) -> Self {
+        match hex::decode(data).map(String::from_utf8) {
+            Ok(Ok(name)) => Self { name, valid: true },
+            _ => Self {
+                name: String::from_utf8_lossy(data).to_string(),
+                valid: false,
+            },
+        }
+    }
+}
+
 /// Encode self as an escape sequence.  The escape sequence may potentially
 /// be clear text with no actual escape sequences.
 impl Display for Action {
@@ -102,8 +126,12 @@ impl Display for Action {
                     if i > 0 {
                         write!(f, ";")?;
                     }
-                    for &b in name.as_bytes() {
-                        write!(f, "{:x}", b)?;
+                    if name.valid {
+                        for &b in name.name.as_bytes() {
+                            write!(f, "{:x}", b)?;
+                        }
+                    } else {
+                        write!(f, "{}", name.name)?;
                     }
                 }
 
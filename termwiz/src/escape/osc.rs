@@ -38,6 +38,12 @@ pub enum OperatingSystemCommand {
     QuerySelection(Selection),
     SetSelection(Selection, String),
     SystemNotification(String),
+    /// ConEmu/Windows Terminal style `OSC 9 ; 4 ; state ; progress ST`
+    /// used to report progress in the taskbar
+    Progress {
+        state: ProgressState,
+        percent: Option<u8>,
+    },
     ITermProprietary(ITermProprietary),
     FinalTermSemanticPrompt(FinalTermSemanticPrompt),
     ChangeColorNumber(Vec<ChangeColorPair>),
@@ -50,6 +56,17 @@ pub enum OperatingSystemCommand {
     Unspecified(Vec<Vec<u8>>),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[repr(u8)]
+pub enum ProgressState {
+    /// Remove the progress indicator
+    None = 0,
+    Normal = 1,
+    Error = 2,
+    Indeterminate = 3,
+    Paused = 4,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
 #[repr(u8)]
 pub enum DynamicColorNumber {
@@ -163,6 +180,20 @@ impl OperatingSystemCommand {
         })
     }
 
+    fn parse_progress(osc: &[&[u8]]) -> Result<Self> {
+        let state: u8 = str::from_utf8(osc[2])?.parse()?;
+        let state = ProgressState::from_u8(state)
+            .ok_or_else(|| format!("invalid progress state {}", state))?;
+        let percent = match osc.get(3) {
+            Some(p) if !p.is_empty() => {
+                let p: u8 = str::from_utf8(p)?.parse()?;
+                Some(p.min(100))
+            }
+            _ => None,
+        };
+        Ok(OperatingSystemCommand::Progress { state, percent })
+    }
+
     fn parse_selection(osc: &[&[u8]]) -> Result<Self> {
         if osc.len() == 2 {
             Selection::try_parse(osc[1]).map(OperatingSystemCommand::ClearSelection)
@@ -170,7 +201,8 @@ impl OperatingSystemCommand {
             Selection::try_parse(osc[1]).map(OperatingSystemCommand::QuerySelection)
         } else if osc.len() == 3 {
             let sel = Selection::try_parse(osc[1])?;
-            let bytes = base64_decode(osc[2])?;
+            let bytes = base64_decode_lenient(osc[2])
+                .map_err(|err| format!("invalid base64 in OSC 52: {err}"))?;
             let s = String::from_utf8(bytes)?;
             Ok(OperatingSystemCommand::SetSelection(sel, s))
         } else {
@@ -311,6 +343,7 @@ impl OperatingSystemCommand {
             )),
             SetHyperlink => Ok(OperatingSystemCommand::SetHyperlink(Hyperlink::parse(osc)?)),
             ManipulateSelectionData => Self::parse_selection(osc),
+            SystemNotification if osc.len() >= 3 && osc[1] == b"4" => Self::parse_progress(osc),
             SystemNotification => single_string!(SystemNotification),
             SetCurrentWorkingDirectory => single_string!(CurrentWorkingDirectory),
             ITermProprietary => {
@@ -464,8 +497,11 @@ impl OperatingSystemCommandCode {
     }
 }
 
-impl Display for OperatingSystemCommand {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+impl OperatingSystemCommand {
+    /// Write the escape sequence for this command directly to a writer,
+    /// without first formatting it into an intermediate `String`.
+    /// The output is identical to that produced by `Display`.
+    pub fn write_to<W: std::io::Write>(&self, f: &mut W) -> std::io::Result<()> {
         write!(f, "\x1b]")?;
 
         macro_rules! single_string {
@@ -494,7 +530,7 @@ impl Display for OperatingSystemCommand {
             SetWindowTitleSun(title) => single_string!(SetWindowTitleSun, title),
             SetIconName(title) => single_string!(SetIconName, title),
             SetIconNameSun(title) => single_string!(SetIconNameSun, title),
-            SetHyperlink(Some(link)) => link.fmt(f)?,
+            SetHyperlink(Some(link)) => write!(f, "{}", link)?,
             SetHyperlink(None) => write!(f, "8;;")?,
             RxvtExtension(params) => write!(f, "777;{}", params.join(";"))?,
             Unspecified(v) => {
@@ -502,15 +538,21 @@ impl Display for OperatingSystemCommand {
                     if idx > 0 {
                         write!(f, ";")?;
                     }
-                    f.write_str(&String::from_utf8_lossy(item))?;
+                    f.write_all(String::from_utf8_lossy(item).as_bytes())?;
                 }
             }
             ClearSelection(s) => write!(f, "52;{}", s)?,
             QuerySelection(s) => write!(f, "52;{};?", s)?,
             SetSelection(s, val) => write!(f, "52;{};{}", s, base64_encode(val))?,
             SystemNotification(s) => write!(f, "9;{}", s)?,
-            ITermProprietary(i) => i.fmt(f)?,
-            FinalTermSemanticPrompt(i) => i.fmt(f)?,
+            Progress { state, percent } => {
+                write!(f, "9;4;{}", *state as u8)?;
+                if let Some(percent) = percent {
+                    write!(f, ";{}", percent)?;
+                }
+            }
+            ITermProprietary(i) => write!(f, "{}", i)?,
+            FinalTermSemanticPrompt(i) => write!(f, "{}", i)?,
             ResetColors(colors) => {
                 write!(f, "104")?;
                 for c in colors {
@@ -538,6 +580,44 @@ impl Display for OperatingSystemCommand {
         write!(f, "\x1b\\")?;
         Ok(())
     }
+
+    /// Write the escape sequences for each of `commands` to `w`, in order.
+    /// This is useful when emitting a large number of commands, such
+    /// as when setting the entire color palette.
+    pub fn write_batch_to<W: std::io::Write>(
+        commands: &[OperatingSystemCommand],
+        w: &mut W,
+    ) -> std::io::Result<()> {
+        for command in commands {
+            command.write_to(w)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adapts a `Formatter` so that it can be used as the target of
+/// `OperatingSystemCommand::write_to`.
+struct FormatterWriter<'a, 'b>(&'a mut Formatter<'b>);
+
+impl<'a, 'b> std::io::Write for FormatterWriter<'a, 'b> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let s = str::from_utf8(buf)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        self.0
+            .write_str(s)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Display for OperatingSystemCommand {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        self.write_to(&mut FormatterWriter(f)).map_err(|_| FmtError)
+    }
 }
 
 /// https://gitlab.freedesktop.org/Per_Bothner/specifications/blob/master/proposals/semantic-prompts.md
@@ -856,6 +936,9 @@ pub enum ITermUnicodeVersionOp {
     Set(u8),
     Push(Option<String>),
     Pop(Option<String>),
+    /// Request that the terminal report the active version
+    /// by responding with a `Set` sequence
+    Query,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1207,6 +1290,11 @@ impl ITermProprietary {
                         ITermUnicodeVersionOp::Pop(label.map(|s| s.to_string())),
                     ));
                 }
+                if p1 == "?" {
+                    return Ok(ITermProprietary::UnicodeVersion(
+                        ITermUnicodeVersionOp::Query,
+                    ));
+                }
 
                 if let Ok(n) = p1.parse::<u8>() {
                     return Ok(ITermProprietary::UnicodeVersion(
@@ -1241,6 +1329,25 @@ pub(crate) fn base64_decode<T: AsRef<[u8]>>(
     .decode(s)
 }
 
+/// Like `base64_decode`, but also accepts input that has ASCII
+/// whitespace, such as line breaks, embedded in it and/or that omits
+/// the trailing padding, as is produced by some clipboard scripts
+pub(crate) fn base64_decode_lenient(s: &[u8]) -> std::result::Result<Vec<u8>, base64::DecodeError> {
+    use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+    let s: Vec<u8> = s
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    GeneralPurpose::new(
+        &base64::alphabet::STANDARD,
+        GeneralPurposeConfig::new()
+            .with_decode_allow_trailing_bits(true)
+            .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+    )
+    .decode(s)
+}
+
 impl Display for ITermProprietary {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(f, "1337;")?;
@@ -1286,6 +1393,7 @@ impl Display for ITermProprietary {
                 write!(f, "UnicodeVersion=pop {}", label)?
             }
             UnicodeVersion(ITermUnicodeVersionOp::Pop(None)) => write!(f, "UnicodeVersion=pop")?,
+            UnicodeVersion(ITermUnicodeVersionOp::Query) => write!(f, "UnicodeVersion=?")?,
         }
         Ok(())
     }
@@ -1331,6 +1439,57 @@ mod test {
         );
     }
 
+    #[test]
+    fn set_selection() {
+        let hello = OperatingSystemCommand::SetSelection(Selection::CLIPBOARD, "hello".into());
+        assert_eq!(
+            parse(&["52", "c", "aGVsbG8="], "\x1b]52;c;aGVsbG8=\x1b\\"),
+            hello
+        );
+        // Missing padding
+        assert_eq!(
+            parse(&["52", "c", "aGVsbG8"], "\x1b]52;c;aGVsbG8=\x1b\\"),
+            hello
+        );
+        // Embedded whitespace
+        assert_eq!(
+            parse(&["52", "c", "aGVs\r\nbG8 ="], "\x1b]52;c;aGVsbG8=\x1b\\"),
+            hello
+        );
+        // Genuinely invalid input is not decoded
+        assert_eq!(
+            parse(&["52", "c", "aG*sbG8="], "\x1b]52;c;aG*sbG8=\x1b\\"),
+            OperatingSystemCommand::Unspecified(vec![
+                b"52".to_vec(),
+                b"c".to_vec(),
+                b"aG*sbG8=".to_vec()
+            ])
+        );
+    }
+
+    #[test]
+    fn progress() {
+        assert_eq!(
+            parse(&["9", "4", "1", "50"], "\x1b]9;4;1;50\x1b\\"),
+            OperatingSystemCommand::Progress {
+                state: ProgressState::Normal,
+                percent: Some(50),
+            }
+        );
+        assert_eq!(
+            parse(&["9", "4", "3"], "\x1b]9;4;3\x1b\\"),
+            OperatingSystemCommand::Progress {
+                state: ProgressState::Indeterminate,
+                percent: None,
+            }
+        );
+        // The plain notification form still parses
+        assert_eq!(
+            parse(&["9", "build done"], "\x1b]9;build done\x1b\\"),
+            OperatingSystemCommand::SystemNotification("build done".into())
+        );
+    }
+
     #[test]
     fn title() {
         assert_eq!(
@@ -1617,6 +1776,20 @@ mod test {
             parse(&["1337", "SetMark"], "\x1b]1337;SetMark\x1b\\"),
             OperatingSystemCommand::ITermProprietary(ITermProprietary::SetMark)
         );
+        assert_eq!(
+            parse(&["1337", "StealFocus"], "\x1b]1337;StealFocus\x1b\\"),
+            OperatingSystemCommand::ITermProprietary(ITermProprietary::StealFocus)
+        );
+
+        assert_eq!(
+            parse(
+                &["1337", "UnicodeVersion=?"],
+                "\x1b]1337;UnicodeVersion=?\x1b\\"
+            ),
+            OperatingSystemCommand::ITermProprietary(ITermProprietary::UnicodeVersion(
+                ITermUnicodeVersionOp::Query
+            ))
+        );
 
         assert_eq!(
             parse(
@@ -1869,4 +2042,22 @@ mod test {
             )))
         );
     }
+
+    #[test]
+    fn write_batch_to() {
+        let commands: Vec<OperatingSystemCommand> = (0..=255u8)
+            .map(|idx| {
+                OperatingSystemCommand::ChangeColorNumber(vec![ChangeColorPair {
+                    palette_index: idx,
+                    color: ColorOrQuery::Color(SrgbaTuple(0., idx as f32 / 255., 1., 1.)),
+                }])
+            })
+            .collect();
+
+        let mut bytes = vec![];
+        OperatingSystemCommand::write_batch_to(&commands, &mut bytes).unwrap();
+
+        let expected: String = commands.iter().map(encode).collect();
+        assert_eq!(String::from_utf8(bytes).unwrap(), expected);
+    }
 }
@@ -1,30 +1,33 @@
 #![allow(clippy::many_single_char_names)]
 use crate::escape::{
     Action, DeviceControlMode, EnterDeviceControlMode, Esc, OperatingSystemCommand,
-    ShortDeviceControl, CSI,
+    ShortDeviceControl, TcapName, CSI,
 };
 use crate::tmux_cc::Event;
+use bitflags::bitflags;
 use log::error;
 use num_traits::FromPrimitive;
 use std::borrow::BorrowMut;
 use std::cell::RefCell;
+use std::ops::Range;
 use vtparse::{CsiParam, VTActor, VTParser};
 
 mod sixel;
 use sixel::SixelBuilder;
 
+/// The longest CSI sequence whose raw bytes will be retained
+/// when `Parser::set_retain_raw_csi` is enabled
+const MAX_RAW_CSI: usize = 1024;
+
 #[derive(Default)]
 struct GetTcapBuilder {
     current: Vec<u8>,
-    names: Vec<String>,
+    names: Vec<TcapName>,
 }
 
 impl GetTcapBuilder {
     fn flush(&mut self) {
-        let decoded = hex::decode(&self.current)
-            .map(|s| String::from_utf8_lossy(&s).to_string())
-            .unwrap_or_else(|_| String::from_utf8_lossy(&self.current).to_string());
-        self.names.push(decoded);
+        self.names.push(TcapName::decode(&self.current));
         self.current.clear();
     }
 
@@ -36,7 +39,7 @@ impl GetTcapBuilder {
         }
     }
 
-    pub fn finish(mut self) -> Vec<String> {
+    pub fn finish(mut self) -> Vec<TcapName> {
         self.flush();
         self.names
     }
@@ -48,6 +51,61 @@ struct ParseState {
     dcs: Option<ShortDeviceControl>,
     get_tcap: Option<GetTcapBuilder>,
     tmux_state: Option<RefCell<crate::tmux_cc::Parser>>,
+    /// When Some, accumulates the bytes of the CSI sequence currently
+    /// being parsed so that they can be retained for CSI::Unspecified
+    raw_csi: Option<Vec<u8>>,
+    /// The raw bytes of the most recently dispatched CSI sequence,
+    /// if it was wholly unrecognized
+    last_raw_csi: Option<Vec<u8>>,
+}
+
+bitflags! {
+    /// Categories of escape sequences that are permitted to pass
+    /// through `Parser::parse_sanitized`.  Printable text, C0/C1
+    /// control codes and SGR sequences are always passed through.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct SanitizePolicy: u16 {
+        const NONE = 0;
+        /// OSC 52 clipboard and selection manipulation
+        const CLIPBOARD = 1<<1;
+        /// OSC sequences that change the window or icon title
+        const TITLE = 1<<2;
+        /// Any other OSC sequence
+        const OSC = 1<<3;
+        /// DCS sequences, including XTGETTCAP requests
+        const DCS = 1<<4;
+        /// Sixel and kitty image data
+        const IMAGES = 1<<5;
+        /// CSI sequences other than SGR
+        const CSI = 1<<6;
+        /// ESC sequences
+        const ESC = 1<<7;
+    }
+}
+
+impl SanitizePolicy {
+    /// Returns true if the policy allows `action` to pass through
+    pub fn permits(self, action: &Action) -> bool {
+        match action {
+            Action::Print(_) | Action::PrintString(_) | Action::Control(_) => true,
+            Action::CSI(CSI::Sgr(_)) => true,
+            Action::CSI(_) => self.contains(Self::CSI),
+            Action::Esc(_) => self.contains(Self::ESC),
+            Action::OperatingSystemCommand(osc) => match &**osc {
+                OperatingSystemCommand::ClearSelection(_)
+                | OperatingSystemCommand::QuerySelection(_)
+                | OperatingSystemCommand::SetSelection(..) => self.contains(Self::CLIPBOARD),
+                OperatingSystemCommand::SetIconNameAndWindowTitle(_)
+                | OperatingSystemCommand::SetWindowTitle(_)
+                | OperatingSystemCommand::SetWindowTitleSun(_)
+                | OperatingSystemCommand::SetIconName(_)
+                | OperatingSystemCommand::SetIconNameSun(_) => self.contains(Self::TITLE),
+                _ => self.contains(Self::OSC),
+            },
+            Action::DeviceControl(_) | Action::XtGetTcap(_) => self.contains(Self::DCS),
+            Action::Sixel(_) | Action::KittyImage(_) => self.contains(Self::IMAGES),
+        }
+    }
 }
 
 /// The `Parser` struct holds the state machine that is used to decode
@@ -59,6 +117,8 @@ struct ParseState {
 pub struct Parser {
     state_machine: VTParser,
     state: RefCell<ParseState>,
+    /// Printable text held back by `parse_coalesced`
+    coalesced: String,
 }
 
 impl Default for Parser {
@@ -72,9 +132,97 @@ impl Parser {
         Self {
             state_machine: VTParser::new(),
             state: RefCell::new(Default::default()),
+            coalesced: String::new(),
+        }
+    }
+
+    /// When enabled, the parser will retain the exact bytes of each
+    /// CSI sequence that it produces a `CSI::Unspecified` for, so that
+    /// they can be re-emitted verbatim; see `last_raw_csi`.
+    /// This is disabled by default to avoid the overhead of buffering.
+    pub fn set_retain_raw_csi(&mut self, retain: bool) {
+        let mut state = self.state.borrow_mut();
+        state.raw_csi = if retain { Some(vec![]) } else { None };
+        state.last_raw_csi = None;
+    }
+
+    /// Returns the exact bytes, including the introducer, of the most
+    /// recently dispatched CSI sequence if that sequence was wholly
+    /// unrecognized and produced a `CSI::Unspecified`.
+    /// This is only available when enabled via `set_retain_raw_csi`,
+    /// and is best paired with `parse_first` so that the bytes can
+    /// be associated with the action that was returned.
+    pub fn last_raw_csi(&self) -> Option<Vec<u8>> {
+        self.state.borrow().last_raw_csi.clone()
+    }
+
+    /// When enabled, the bytes 0x80-0x9f are treated as printable
+    /// characters with the same unicode codepoint value, rather than
+    /// as C1 control codes.  This can help when decoding streams that
+    /// mix legacy 8-bit encodings with escape sequences.
+    /// This is disabled by default.
+    pub fn set_treat_c1_as_printable(&mut self, enable: bool) {
+        self.state_machine.set_treat_c1_as_printable(enable);
+    }
+
+    /// Returns the name of the state that the parser is in, for use
+    /// when diagnosing output that appears to be stuck, such as when
+    /// an application left a DCS sequence incomplete.
+    /// Sixel, XTGETTCAP, short DCS and tmux control mode data that is
+    /// being accumulated is reported as such; otherwise the state of
+    /// the underlying `VTParser` is reported.
+    /// The names are not guaranteed to remain stable.
+    pub fn state_name(&self) -> &'static str {
+        let state = self.state.borrow();
+        if state.tmux_state.is_some() {
+            "tmux"
+        } else if state.sixel.is_some() {
+            "sixel"
+        } else if state.get_tcap.is_some() {
+            "xtgettcap"
+        } else if state.dcs.is_some() {
+            "short_dcs"
+        } else {
+            self.state_machine.state_name()
         }
     }
 
+    /// Feed a single byte to the state machine, recording it in the
+    /// raw CSI buffer if that has been enabled.
+    fn parse_byte<F: FnMut(Action)>(
+        state_machine: &mut VTParser,
+        byte: u8,
+        perform: &mut Performer<F>,
+    ) {
+        if let Some(raw) = perform.state.raw_csi.as_mut() {
+            match byte {
+                // ESC and CSI (re)start a sequence
+                0x1b => {
+                    raw.clear();
+                    raw.push(byte);
+                }
+                0x9b if state_machine.is_ground() => {
+                    raw.clear();
+                    raw.push(byte);
+                }
+                // CAN and SUB abort the sequence
+                0x18 | 0x1a => raw.clear(),
+                // Other C0 controls are executed immediately rather
+                // than being part of the sequence
+                0x00..=0x1f => {}
+                _ => match raw.as_slice() {
+                    [] => {}
+                    // Only CSI is retained; OSC, DCS and the other
+                    // ESC sequences may carry arbitrarily large payloads
+                    [0x1b] if byte != b'[' => raw.clear(),
+                    _ if raw.len() >= MAX_RAW_CSI => raw.clear(),
+                    _ => raw.push(byte),
+                },
+            }
+        }
+        state_machine.parse_byte(byte, perform);
+    }
+
     /// advance with tmux parser, bypass VTParse
     fn advance_tmux_bytes(&mut self, bytes: &[u8]) -> anyhow::Result<Vec<Event>> {
         let parser_state = self.state.borrow();
@@ -110,10 +258,35 @@ impl Parser {
                 callback: &mut callback,
                 state: &mut self.state.borrow_mut(),
             };
-            self.state_machine.parse(bytes, &mut perform);
+            if perform.state.raw_csi.is_some() {
+                for b in bytes {
+                    Self::parse_byte(&mut self.state_machine, *b, &mut perform);
+                }
+            } else {
+                self.state_machine.parse(bytes, &mut perform);
+            }
         }
     }
 
+    /// Parse `bytes` in the same way as `parse`, but only invoke the
+    /// callback for actions that are permitted by the `allow` policy;
+    /// everything else is silently dropped.
+    /// This is useful when displaying untrusted output in a context
+    /// where it should not be able to, for example, write to the
+    /// clipboard or change the window title.
+    pub fn parse_sanitized<F: FnMut(Action)>(
+        &mut self,
+        bytes: &[u8],
+        allow: SanitizePolicy,
+        mut callback: F,
+    ) {
+        self.parse(bytes, |action| {
+            if allow.permits(&action) {
+                callback(action);
+            }
+        });
+    }
+
     /// A specialized version of the parser that halts after recognizing the
     /// first action from the stream of bytes.  The return value is the action
     /// that was recognized and the length of the byte stream that was fed in
@@ -137,7 +310,7 @@ impl Parser {
                 state: &mut self.state.borrow_mut(),
             };
             for (idx, b) in bytes.iter().enumerate() {
-                self.state_machine.parse_byte(*b, &mut perform);
+                Self::parse_byte(&mut self.state_machine, *b, &mut perform);
                 if first.borrow().is_some() {
                     // if we recognized an action, record the iterator index
                     first_idx = Some(idx);
@@ -160,6 +333,61 @@ impl Parser {
         result
     }
 
+    /// Like `parse_as_vec`, but runs of printable characters are
+    /// combined into `Action::PrintString` rather than producing
+    /// an `Action::Print` for each individual character.
+    pub fn parse_as_vec_coalesced(&mut self, bytes: &[u8]) -> Vec<Action> {
+        let mut result = Vec::new();
+        self.parse(bytes, |action| action.append_to(&mut result));
+        result
+    }
+
+    /// Like `parse`, but printable characters are accumulated and
+    /// delivered to `callback` as a single `Action::PrintString`, even
+    /// when the text is split across multiple calls.
+    /// The pending text is delivered ahead of the next non-printing
+    /// action, or when `flush_coalesced` is called.
+    pub fn parse_coalesced<F: FnMut(Action)>(&mut self, bytes: &[u8], mut callback: F) {
+        let mut pending = std::mem::take(&mut self.coalesced);
+        self.parse(bytes, |action| match action {
+            Action::Print(c) => pending.push(c),
+            Action::PrintString(s) => pending.push_str(&s),
+            action => {
+                if !pending.is_empty() {
+                    callback(Action::PrintString(std::mem::take(&mut pending)));
+                }
+                callback(action);
+            }
+        });
+        self.coalesced = pending;
+    }
+
+    /// Returns any printable text that is being held back by
+    /// `parse_coalesced` as an `Action::PrintString`
+    pub fn flush_coalesced(&mut self) -> Option<Action> {
+        if self.coalesced.is_empty() {
+            None
+        } else {
+            Some(Action::PrintString(std::mem::take(&mut self.coalesced)))
+        }
+    }
+
+    /// Returns true if the parser is holding the bytes of a sequence
+    /// that has not yet been completed, such as a truncated CSI, a
+    /// partial UTF-8 character, or a DCS whose data is still being
+    /// accumulated.  Callers reassembling a stream can use this to
+    /// decide whether to wait for more bytes before acting on the
+    /// absence of output.
+    /// Text held back by `parse_coalesced` is not considered here;
+    /// use `flush_coalesced` to retrieve it.
+    pub fn has_pending(&self) -> bool {
+        if !self.state_machine.is_ground() {
+            return true;
+        }
+        let state = self.state.borrow();
+        state.sixel.is_some() || state.dcs.is_some() || state.get_tcap.is_some()
+    }
+
     /// Similar to `parse_first` but collects all actions from the first sequence,
     /// and guarantees the state machine is in the ground state at the end of this
     /// sequence.
@@ -167,7 +395,8 @@ impl Parser {
         let mut actions = Vec::new();
         let mut first_idx = None;
         for (idx, b) in bytes.iter().enumerate() {
-            self.state_machine.parse_byte(
+            Self::parse_byte(
+                &mut self.state_machine,
                 *b,
                 &mut Performer {
                     callback: &mut |action| actions.push(action),
@@ -182,6 +411,36 @@ impl Parser {
         }
         first_idx.map(|idx| (actions, idx + 1))
     }
+
+    /// Splits `bytes` into its complete sequences by repeatedly calling
+    /// `parse_first_as_vec`, returning the range of `bytes` occupied by
+    /// each sequence along with the actions that it produced.
+    /// Runs of printable text are returned as a single entry holding
+    /// the `Action::Print` for each character.
+    /// Trailing bytes that don't complete a sequence are retained by the
+    /// parser and will be considered by the next call.
+    pub fn split_into_sequences(&mut self, bytes: &[u8]) -> Vec<(Range<usize>, Vec<Action>)> {
+        fn is_text(actions: &[Action]) -> bool {
+            actions
+                .iter()
+                .all(|action| matches!(action, Action::Print(_)))
+        }
+
+        let mut sequences: Vec<(Range<usize>, Vec<Action>)> = vec![];
+        let mut offset = 0;
+        while let Some((mut actions, len)) = self.parse_first_as_vec(&bytes[offset..]) {
+            let range = offset..offset + len;
+            offset += len;
+            match sequences.last_mut() {
+                Some((prior_range, prior)) if is_text(prior) && is_text(&actions) => {
+                    prior_range.end = range.end;
+                    prior.append(&mut actions);
+                }
+                _ => sequences.push((range, actions)),
+            }
+        }
+        sequences
+    }
 }
 
 struct Performer<'a, F: FnMut(Action) + 'a> {
@@ -309,7 +568,23 @@ impl<'a, F: FnMut(Action)> VTActor for Performer<'a, F> {
     }
 
     fn csi_dispatch(&mut self, params: &[CsiParam], parameters_truncated: bool, control: u8) {
+        let raw = self
+            .state
+            .raw_csi
+            .as_mut()
+            .map(std::mem::take)
+            .filter(|raw| !raw.is_empty());
+        self.state.last_raw_csi = None;
         for action in CSI::parse(params, parameters_truncated, control as char) {
+            if let (CSI::Unspecified(unspec), Some(raw)) = (&action, &raw) {
+                // Only a sequence that was wholly unrecognized can be
+                // faithfully represented by its raw bytes; for eg: an
+                // unknown SGR code mixed in with known ones, the raw
+                // bytes would also re-apply the known codes.
+                if unspec.params == params {
+                    self.state.last_raw_csi = Some(raw.clone());
+                }
+            }
             (self.callback)(Action::CSI(action));
         }
     }
@@ -341,10 +616,10 @@ mod test {
     use crate::cell::{Intensity, Underline};
     use crate::color::ColorSpec;
     use crate::escape::csi::{
-        CharacterPath, DecPrivateMode, DecPrivateModeCode, Device, Mode, Sgr, Window, XtSmGraphics,
-        XtSmGraphicsItem, XtermKeyModifierResource,
+        CharacterPath, Cursor, DecPrivateMode, DecPrivateModeCode, Device, Mode, Sgr, Window,
+        XtSmGraphics, XtSmGraphicsItem, XtermKeyModifierResource,
     };
-    use crate::escape::{EscCode, OneBased};
+    use crate::escape::{ControlCode, EscCode, OneBased};
     use k9::assert_equal as assert_eq;
     use std::io::Write;
 
@@ -356,6 +631,118 @@ mod test {
         String::from_utf8(res).unwrap()
     }
 
+    #[test]
+    fn split_into_sequences() {
+        let data = "hé\x1b[1mbold\x1b[0m\r\n\x1b[2;3H\x1b[".as_bytes();
+        let mut p = Parser::new();
+
+        let sequences = p.split_into_sequences(data);
+        let ranges: Vec<&[u8]> = sequences
+            .iter()
+            .map(|(range, _)| &data[range.clone()])
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                "hé".as_bytes(),
+                b"\x1b[1m".as_slice(),
+                b"bold".as_slice(),
+                b"\x1b[0m".as_slice(),
+                b"\r".as_slice(),
+                b"\n".as_slice(),
+                b"\x1b[2;3H".as_slice(),
+            ]
+        );
+
+        let actions: Vec<Vec<Action>> = sequences.into_iter().map(|(_, a)| a).collect();
+        assert_eq!(
+            actions,
+            vec![
+                vec![Action::Print('h'), Action::Print('é')],
+                vec![Action::CSI(CSI::Sgr(Sgr::Intensity(Intensity::Bold)))],
+                "bold".chars().map(Action::Print).collect(),
+                vec![Action::CSI(CSI::Sgr(Sgr::Reset))],
+                vec![Action::Control(ControlCode::CarriageReturn)],
+                vec![Action::Control(ControlCode::LineFeed)],
+                vec![Action::CSI(CSI::Cursor(Cursor::Position {
+                    line: OneBased::new(2),
+                    col: OneBased::new(3),
+                }))],
+            ]
+        );
+
+        // The incomplete trailing CSI is completed by the next call
+        let sequences = p.split_into_sequences(b"4mx");
+        assert_eq!(
+            sequences,
+            vec![
+                (
+                    0..2,
+                    vec![Action::CSI(CSI::Sgr(Sgr::Underline(Underline::Single)))]
+                ),
+                (2..3, vec![Action::Print('x')]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_coalesced() {
+        let mut p = Parser::new();
+        let mut actions = vec![];
+
+        p.parse_coalesced(b"hel", |action| actions.push(action));
+        p.parse_coalesced("lo wörld".as_bytes(), |action| actions.push(action));
+        assert_eq!(actions, vec![]);
+        assert_eq!(
+            p.flush_coalesced(),
+            Some(Action::PrintString("hello wörld".to_string()))
+        );
+        assert_eq!(p.flush_coalesced(), None);
+
+        // A control code delivers the text that precedes it, and a
+        // multi-byte character split across feeds is kept whole
+        p.parse_coalesced(b"ab\r\xc3", |action| actions.push(action));
+        p.parse_coalesced(b"\xa9", |action| actions.push(action));
+        assert_eq!(
+            actions,
+            vec![
+                Action::PrintString("ab".to_string()),
+                Action::Control(ControlCode::CarriageReturn),
+            ]
+        );
+        assert_eq!(
+            p.flush_coalesced(),
+            Some(Action::PrintString("é".to_string()))
+        );
+    }
+
+    #[test]
+    fn has_pending() {
+        let mut p = Parser::new();
+        assert!(!p.has_pending());
+
+        assert_eq!(p.parse_as_vec(b"\x1b[1;3"), vec![]);
+        assert!(p.has_pending());
+        assert_eq!(
+            p.parse_as_vec(b"m"),
+            vec![
+                Action::CSI(CSI::Sgr(Sgr::Intensity(Intensity::Bold))),
+                Action::CSI(CSI::Sgr(Sgr::Italic(true))),
+            ]
+        );
+        assert!(!p.has_pending());
+
+        p.parse_as_vec(b"\x1bP+q544e");
+        assert!(p.has_pending());
+        p.parse_as_vec(b"\x1b\\");
+        assert!(!p.has_pending());
+
+        p.parse_as_vec(b"\xc3");
+        assert!(p.has_pending());
+        assert_eq!(p.parse_as_vec(b"\xa9"), vec![Action::Print('é')]);
+        assert!(!p.has_pending());
+    }
+
     // <https://github.com/markbt/streampager/issues/57>
     #[test]
     fn osc_bel_parse_first_as_vec() {
@@ -639,6 +1026,97 @@ mod test {
         );
     }
 
+    #[test]
+    fn unspecified_csi_raw_round_trip() {
+        // The leading zero would be lost if this were re-encoded
+        // via the Display impl
+        let input = b"\x1b[?1;02$y";
+
+        let mut p = Parser::new();
+        let actions = p.parse_as_vec(input);
+        match &actions[..] {
+            [Action::CSI(CSI::Unspecified(_))] => assert_eq!(p.last_raw_csi(), None),
+            _ => panic!("unexpected actions {:?}", actions),
+        }
+
+        p.set_retain_raw_csi(true);
+        let mut data = b"hello".to_vec();
+        data.extend_from_slice(input);
+        let (action, len) = p.parse_first(&data).unwrap();
+        assert_eq!(action, Action::Print('h'));
+        assert_eq!(p.last_raw_csi(), None);
+
+        // The sequence may be split across calls
+        let split = data.len() - 3;
+        p.parse_as_vec(&data[len..split]);
+        let actions = p.parse_as_vec(&data[split..]);
+        match &actions[..] {
+            [Action::CSI(CSI::Unspecified(_))] => {
+                assert_eq!(p.last_raw_csi().as_deref(), Some(&input[..]))
+            }
+            _ => panic!("unexpected actions {:?}", actions),
+        }
+
+        // A recognized sequence doesn't have its bytes retained
+        p.parse_as_vec(b"\x1b[1m");
+        assert_eq!(p.last_raw_csi(), None);
+    }
+
+    #[test]
+    fn unspecified_csi_raw_only_buffers_csi() {
+        let input = b"\x1b[?1;02$y";
+        let mut p = Parser::new();
+        p.set_retain_raw_csi(true);
+        let buffered = |p: &Parser| p.state.borrow().raw_csi.as_ref().map(Vec::len);
+
+        // The payload of an OSC is not buffered
+        p.parse_as_vec(b"\x1b]0;a rather long window title\x07");
+        assert_eq!(buffered(&p), Some(0));
+
+        let mut data = b"\x1b[1mtext\x1b]2;title\x1b\\".to_vec();
+        data.extend_from_slice(input);
+        let actions = p.parse_as_vec(&data);
+        match &actions[..] {
+            [.., Action::CSI(CSI::Unspecified(_))] => {
+                assert_eq!(p.last_raw_csi().as_deref(), Some(&input[..]))
+            }
+            _ => panic!("unexpected actions {:?}", actions),
+        }
+        // and the buffer is reset once the sequence is dispatched
+        assert_eq!(buffered(&p), Some(0));
+    }
+
+    #[test]
+    fn sanitized_drops_clipboard_write() {
+        let mut p = Parser::new();
+        let mut actions = vec![];
+        p.parse_sanitized(
+            b"\x1b[1mhi\x1b]52;c;aGVsbG8=\x07\x1b]2;title\x07!",
+            SanitizePolicy::TITLE,
+            |action| actions.push(action),
+        );
+        assert_eq!(
+            vec![
+                Action::CSI(CSI::Sgr(Sgr::Intensity(Intensity::Bold))),
+                Action::Print('h'),
+                Action::Print('i'),
+                Action::OperatingSystemCommand(Box::new(OperatingSystemCommand::SetWindowTitle(
+                    "title".to_owned()
+                ))),
+                Action::Print('!'),
+            ],
+            actions
+        );
+
+        let mut actions = vec![];
+        p.parse_sanitized(
+            b"\x1b]52;c;aGVsbG8=\x07ok",
+            SanitizePolicy::NONE,
+            |action| actions.push(action),
+        );
+        assert_eq!(vec![Action::Print('o'), Action::Print('k')], actions);
+    }
+
     #[test]
     fn basic_osc() {
         let mut p = Parser::new();
@@ -743,12 +1221,106 @@ mod test {
         actions
     }
 
+    #[test]
+    fn coalesced_print() {
+        let mut p = Parser::new();
+        assert_eq!(
+            p.parse_as_vec_coalesced(b"hello\r\nw\x1b[1mthere"),
+            vec![
+                Action::PrintString("hello".to_string()),
+                Action::Control(ControlCode::CarriageReturn),
+                Action::Control(ControlCode::LineFeed),
+                Action::Print('w'),
+                Action::CSI(CSI::Sgr(Sgr::Intensity(Intensity::Bold))),
+                Action::PrintString("there".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn c1_as_printable() {
+        let mut p = Parser::new();
+        assert_eq!(
+            p.parse_as_vec(b"a\x85b"),
+            vec![
+                Action::Print('a'),
+                Action::Control(ControlCode::NEL),
+                Action::Print('b'),
+            ]
+        );
+
+        p.set_treat_c1_as_printable(true);
+        assert_eq!(
+            p.parse_as_vec(b"a\x85b"),
+            vec![
+                Action::Print('a'),
+                Action::Print('\u{85}'),
+                Action::Print('b'),
+            ]
+        );
+    }
+
+    #[test]
+    fn state_name() {
+        let mut p = Parser::new();
+        assert_eq!(p.state_name(), "ground");
+
+        p.parse_as_vec(b"\x1b[1;");
+        assert_eq!(p.state_name(), "csi_param");
+        p.parse_as_vec(b"2H");
+        assert_eq!(p.state_name(), "ground");
+
+        p.parse_as_vec(b"\x1bP+q544e");
+        assert_eq!(p.state_name(), "xtgettcap");
+        p.parse_as_vec(b"\x1b\\");
+        assert_eq!(p.state_name(), "ground");
+
+        p.parse_as_vec(b"\x1bP1z");
+        assert_eq!(p.state_name(), "dcs_passthrough");
+        p.parse_as_vec(b"data\x1b\\");
+        assert_eq!(p.state_name(), "ground");
+    }
+
     #[test]
     fn xtgettcap() {
         assert_eq!(
             round_trip_parse("\x1bP+q544e\x1b\\"),
             vec![
-                Action::XtGetTcap(vec!["TN".to_string()]),
+                Action::XtGetTcap(vec![TcapName {
+                    name: "TN".to_string(),
+                    valid: true
+                }]),
+                Action::Esc(Esc::Code(EscCode::StringTerminator)),
+            ]
+        );
+    }
+
+    #[test]
+    fn xtgettcap_malformed_names() {
+        assert_eq!(
+            round_trip_parse("\x1bP+q544e;544;zz;ff\x1b\\"),
+            vec![
+                Action::XtGetTcap(vec![
+                    TcapName {
+                        name: "TN".to_string(),
+                        valid: true
+                    },
+                    // Odd length
+                    TcapName {
+                        name: "544".to_string(),
+                        valid: false
+                    },
+                    // Not hex
+                    TcapName {
+                        name: "zz".to_string(),
+                        valid: false
+                    },
+                    // Hex, but not UTF-8
+                    TcapName {
+                        name: "ff".to_string(),
+                        valid: false
+                    },
+                ]),
                 Action::Esc(Esc::Code(EscCode::StringTerminator)),
             ]
         );
@@ -693,11 +693,31 @@ impl TerminfoRenderer {
                     }
                 }
 
+                Change::ScrollRegion(region) => {
+                    if let Some(csr) = self.get_capability::<cap::ChangeScrollRegion>() {
+                        let (top, bottom) = match region {
+                            Some(region) if !region.is_empty() => {
+                                (region.start as u32, (region.end - 1) as u32)
+                            }
+                            _ => {
+                                let (_cols, rows) = out.get_size_in_cells()?;
+                                (0, rows.saturating_sub(1) as u32)
+                            }
+                        };
+                        csr.expand().top(top).bottom(bottom).to(out.by_ref())?;
+                    }
+                }
+
                 Change::Title(text) => {
                     let osc = OperatingSystemCommand::SetWindowTitle(text.to_string());
                     write!(out, "{}", osc)?;
                 }
 
+                Change::CurrentWorkingDirectory(cwd) => {
+                    let osc = OperatingSystemCommand::CurrentWorkingDirectory(cwd.to_string());
+                    write!(out, "{}", osc)?;
+                }
+
                 Change::LineAttribute(attr) => {
                     let esc = Esc::Code(match attr {
                         LineAttribute::DoubleHeightTopHalfLine => {
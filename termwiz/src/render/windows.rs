@@ -436,6 +436,14 @@ impl WindowsConsoleRenderer {
                 } => {
                     buffer.scroll(*first_row, *region_size, *scroll_count as isize, out)?;
                 }
+                Change::ScrollRegion(_) => {
+                    // The console API has no persistent scroll region;
+                    // ScrollRegionUp/Down explicitly scroll the rows that
+                    // they specify, so there is nothing to do here other
+                    // than to match the cursor position that a terminal
+                    // would have after setting the region.
+                    buffer.set_cursor(0, 0, out)?;
+                }
                 Change::Title(_text) => {
                     // Don't actually render this for now.
                     // The primary purpose of Change::Title at the time of
@@ -446,6 +454,9 @@ impl WindowsConsoleRenderer {
                     // renderer because we might be composing multiple widgets
                     // together, each with its own title.
                 }
+                Change::CurrentWorkingDirectory(_) => {
+                    // The console has no notion of this
+                }
                 Change::LineAttribute(_) => {
                     // Ignore line attributes
                 }
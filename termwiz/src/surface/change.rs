@@ -5,6 +5,7 @@ use crate::surface::{CursorShape, CursorVisibility, Position};
 use finl_unicode::grapheme_clusters::Graphemes;
 #[cfg(feature = "use_serde")]
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 use std::sync::Arc;
 
 #[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
@@ -96,10 +97,23 @@ pub enum Change {
         region_size: usize,
         scroll_count: usize,
     },
+    /// Restrict the scrolling that results from printing text to the
+    /// specified half-open range of rows, or restore scrolling of the
+    /// whole screen when `None`.  Subsequent line feeds at the bottom of
+    /// the region scroll only the rows within it.
+    ///
+    /// As with the equivalent terminal escape sequence, the cursor is
+    /// moved to the home position (top left) after the region is set.
+    ScrollRegion(Option<Range<usize>>),
     /// Change the title of the window in which the surface will be
     /// rendered.
     Title(String),
 
+    /// Record the current working directory, as would be reported by
+    /// an application via OSC 7.  The value is typically a `file://`
+    /// URL, but is stored as-is.
+    CurrentWorkingDirectory(String),
+
     /// Adjust the current line attributes, such as double height or width
     LineAttribute(LineAttribute),
 }
@@ -206,6 +220,7 @@ impl ChangeSequence {
             | Change::CursorVisibility(_)
             | Change::ClearToEndOfLine(_)
             | Change::Title(_)
+            | Change::CurrentWorkingDirectory(_)
             | Change::LineAttribute(_)
             | Change::ClearToEndOfScreen(_) => {}
             Change::Text(t) => {
@@ -232,7 +247,7 @@ impl ChangeSequence {
                 self.cursor_x += im.width;
                 self.render_y_max = self.render_y_max.max(self.cursor_y + im.height as isize);
             }
-            Change::ClearScreen(_) => {
+            Change::ClearScreen(_) | Change::ScrollRegion(_) => {
                 self.cursor_x = 0;
                 self.cursor_y = 0;
             }
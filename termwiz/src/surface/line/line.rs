@@ -31,6 +31,36 @@ pub enum DoubleClickRange {
     RangeWithWrap(Range<usize>),
 }
 
+/// Describes how a cell differs between two lines.
+/// See `Line::diff_cells_against`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellDelta {
+    /// The new grapheme and its width, if either changed
+    pub grapheme: Option<(String, usize)>,
+    /// The new attributes, if they changed
+    pub attrs: Option<CellAttributes>,
+}
+
+impl CellDelta {
+    fn compute(old: Option<&CellRef>, new: &CellRef) -> Option<Self> {
+        let grapheme_changed = match old {
+            Some(old) => old.str() != new.str() || old.width() != new.width(),
+            None => true,
+        };
+        let attrs_changed = match old {
+            Some(old) => old.attrs() != new.attrs(),
+            None => true,
+        };
+        if !grapheme_changed && !attrs_changed {
+            return None;
+        }
+        Some(Self {
+            grapheme: grapheme_changed.then(|| (new.str().to_string(), new.width())),
+            attrs: attrs_changed.then(|| new.attrs().clone()),
+        })
+    }
+}
+
 #[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 #[derive(Debug)]
 pub struct Line {
@@ -635,6 +665,78 @@ impl Line {
         }
     }
 
+    /// Split the line into two lines at the specified column, returning
+    /// the (left, right) halves and leaving self unmodified.
+    /// A wide grapheme that straddles `col` is placed wholly into the
+    /// left half along with its blank spacer cells, so the right half
+    /// begins at the first grapheme boundary at or after `col`.
+    /// The widths of the two halves sum to the width of this line;
+    /// `col` values beyond the end of the line yield an empty right half.
+    pub fn split_at_column(&self, col: usize, seqno: SequenceNo) -> (Self, Self) {
+        let mut left = vec![];
+        let mut right = vec![];
+        for cell in self.visible_cells() {
+            let target = if cell.cell_index() < col {
+                &mut left
+            } else {
+                &mut right
+            };
+            target.push(cell.as_cell());
+            for _ in 1..cell.width() {
+                target.push(Cell::blank_with_attrs(cell.attrs().clone()));
+            }
+        }
+
+        let make_line = |cells: Vec<Cell>| Self {
+            bits: self.bits,
+            cells: CellStorage::V(VecStorage::new(cells)),
+            seqno,
+            zones: vec![],
+            appdata: Mutex::new(None),
+        };
+        (make_line(left), make_line(right))
+    }
+
+    /// Compares the cells of this line with those of `other`, returning
+    /// the cell index and a `CellDelta` for each cell of `other` whose
+    /// grapheme and/or attributes differ from the cell of this line at
+    /// the same position.
+    /// Only the first cell of a wide grapheme is considered; a cell of
+    /// `other` that begins where this line has the spacer of a wide
+    /// grapheme is reported as changing both its grapheme and attributes.
+    /// Cells of this line beyond the end of `other` are compared with
+    /// blank cells.
+    pub fn diff_cells_against(&self, other: &Line) -> Vec<(usize, CellDelta)> {
+        let mine: Vec<CellRef> = self.visible_cells().collect();
+        let find = |idx: usize| {
+            mine.binary_search_by_key(&idx, |c| c.cell_index())
+                .ok()
+                .map(|i| &mine[i])
+        };
+
+        let mut deltas = vec![];
+        for cell in other.visible_cells() {
+            let idx = cell.cell_index();
+            if let Some(delta) = CellDelta::compute(find(idx), &cell) {
+                deltas.push((idx, delta));
+            }
+        }
+
+        let blank = Cell::blank();
+        for old in mine.iter().filter(|c| c.cell_index() >= other.len()) {
+            let idx = old.cell_index();
+            let new = CellRef::CellRef {
+                cell_index: idx,
+                cell: &blank,
+            };
+            if let Some(delta) = CellDelta::compute(Some(old), &new) {
+                deltas.push((idx, delta));
+            }
+        }
+
+        deltas
+    }
+
     pub fn compute_double_click_range<F: Fn(&str) -> bool>(
         &self,
         click_col: usize,
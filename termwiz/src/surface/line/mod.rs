@@ -7,4 +7,4 @@ mod test;
 mod vecstorage;
 
 pub use cellref::CellRef;
-pub use line::{DoubleClickRange, Line};
+pub use line::{CellDelta, DoubleClickRange, Line};
@@ -612,3 +612,128 @@ Line {
 "#
     );
 }
+
+#[test]
+fn split_at_column() {
+    let line: Line = "abcdef".into();
+    let (left, right) = line.split_at_column(2, SEQ_ZERO);
+    assert_eq!(left.as_str(), "ab");
+    assert_eq!(right.as_str(), "cdef");
+
+    let (left, right) = line.split_at_column(10, SEQ_ZERO);
+    assert_eq!(left.as_str(), "abcdef");
+    assert_eq!(right.len(), 0);
+}
+
+/// When the split column lands in the middle of a double-wide
+/// grapheme, the grapheme is moved wholly into the left half
+/// and the overall width is preserved.
+#[test]
+fn split_at_column_double_wide() {
+    let line: Line = "aグbc".into();
+    assert_eq!(line.len(), 5);
+
+    let (left, right) = line.split_at_column(2, SEQ_ZERO);
+    assert_eq!(left.len() + right.len(), line.len());
+    assert_eq!(left.len(), 3);
+    assert_eq!(left.columns_as_str(0..3), "aグ");
+    assert_eq!(right.as_str(), "bc");
+
+    // Splitting on the grapheme boundary leaves it intact on the right
+    let (left, right) = line.split_at_column(1, SEQ_ZERO);
+    assert_eq!(left.len() + right.len(), line.len());
+    assert_eq!(left.as_str(), "a");
+    assert_eq!(right.len(), 4);
+    assert_eq!(right.columns_as_str(0..4), "グbc");
+}
+
+#[test]
+fn diff_cells_against() {
+    let line: Line = "abcd".into();
+
+    // Grapheme only
+    let other: Line = "abXd".into();
+    assert_eq!(
+        line.diff_cells_against(&other),
+        vec![(
+            2,
+            CellDelta {
+                grapheme: Some(("X".to_string(), 1)),
+                attrs: None,
+            }
+        )]
+    );
+
+    // Attributes only
+    let mut other: Line = "abcd".into();
+    let mut bold = CellAttributes::default();
+    bold.set_intensity(crate::cell::Intensity::Bold);
+    other.set_cell(1, Cell::new('b', bold.clone()), SEQ_ZERO);
+    assert_eq!(
+        line.diff_cells_against(&other),
+        vec![(
+            1,
+            CellDelta {
+                grapheme: None,
+                attrs: Some(bold.clone()),
+            }
+        )]
+    );
+
+    // Both
+    let mut other: Line = "abcd".into();
+    other.set_cell(3, Cell::new('Y', bold.clone()), SEQ_ZERO);
+    assert_eq!(
+        line.diff_cells_against(&other),
+        vec![(
+            3,
+            CellDelta {
+                grapheme: Some(("Y".to_string(), 1)),
+                attrs: Some(bold),
+            }
+        )]
+    );
+
+    assert_eq!(line.diff_cells_against(&line), vec![]);
+}
+
+#[test]
+fn diff_cells_against_double_wide() {
+    let line: Line = "abcd".into();
+    let wide: Line = "aグd".into();
+    assert_eq!(wide.len(), 4);
+
+    // The wide grapheme replaces "b"; the "c" that is now covered by
+    // its spacer isn't reported separately
+    assert_eq!(
+        line.diff_cells_against(&wide),
+        vec![(
+            1,
+            CellDelta {
+                grapheme: Some(("グ".to_string(), 2)),
+                attrs: None,
+            }
+        )]
+    );
+
+    // Going the other way, the "c" begins where there was a spacer
+    assert_eq!(
+        wide.diff_cells_against(&line),
+        vec![
+            (
+                1,
+                CellDelta {
+                    grapheme: Some(("b".to_string(), 1)),
+                    attrs: None,
+                }
+            ),
+            (
+                2,
+                CellDelta {
+                    grapheme: Some(("c".to_string(), 1)),
+                    attrs: Some(CellAttributes::default()),
+                }
+            ),
+        ]
+    );
+}
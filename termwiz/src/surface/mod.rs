@@ -1,13 +1,24 @@
-use crate::cell::{Cell, CellAttributes};
+use crate::cell::{
+    grapheme_column_width, unicode_column_width, AttributeChange, Blink, Cell, CellAttributes,
+};
 use crate::color::ColorAttribute;
+use crate::escape::csi::{Cursor, Edit, EraseInDisplay, EraseInLine, Sgr};
+use crate::escape::parser::Parser;
+use crate::escape::{Action, ControlCode, OperatingSystemCommand, CSI};
 use crate::image::ImageCell;
 use crate::surface::line::CellRef;
 use finl_unicode::grapheme_clusters::Graphemes;
 use ordered_float::NotNan;
+use rangeset::RangeSet;
 #[cfg(feature = "use_serde")]
 use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::borrow::Cow;
 use std::cmp::min;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
 use wezterm_dynamic::{FromDynamic, ToDynamic};
 
 pub mod change;
@@ -72,11 +83,39 @@ impl CursorShape {
     }
 }
 
+/// Selects the box drawing characters used by `Surface::draw_box`
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoxStyle {
+    /// `┌─┐` with square corners
+    Single,
+    /// `╔═╗` with double lines
+    Double,
+    /// `╭─╮` with rounded corners
+    Rounded,
+}
+
+impl BoxStyle {
+    /// Returns the top left, top right, bottom left and bottom right
+    /// corners followed by the horizontal and vertical edges
+    fn glyphs(self) -> [char; 6] {
+        match self {
+            Self::Single => ['┌', '┐', '└', '┘', '─', '│'],
+            Self::Double => ['╔', '╗', '╚', '╝', '═', '║'],
+            Self::Rounded => ['╭', '╮', '╰', '╯', '─', '│'],
+        }
+    }
+}
+
 /// SequenceNo indicates a logical position within a stream of changes.
 /// The sequence is only meaningful within a given `Surface` instance.
 pub type SequenceNo = usize;
 pub const SEQ_ZERO: SequenceNo = 0;
 
+/// The default limit on the number of characters in a title set
+/// via `Surface::set_title`; see `Surface::set_max_title_len`
+pub const DEFAULT_MAX_TITLE_LEN: usize = 4096;
+
 /// The `Surface` type represents the contents of a terminal screen.
 /// It is not directly connected to a terminal device.
 /// It consists of a buffer and a log of changes.  You can accumulate
@@ -115,6 +154,19 @@ pub struct Surface {
     cursor_visibility: CursorVisibility,
     cursor_color: ColorAttribute,
     title: String,
+    /// Overrides DEFAULT_MAX_TITLE_LEN; see `set_max_title_len`
+    max_title_len: Option<usize>,
+    current_working_dir: Option<String>,
+    scroll_region: Option<Range<usize>>,
+    /// Invoked for each change as it is applied; see `set_change_observer`
+    change_observer: Option<Box<dyn FnMut(&Change) + Send + Sync>>,
+    /// Holds any partially received escape sequence between calls
+    /// to `apply_ansi`.  The Mutex is only present to keep the
+    /// Surface Sync; it is only ever accessed via get_mut.
+    ansi_parser: Option<Mutex<Parser>>,
+    /// While `apply_changes_tracked` is running, collects the rows
+    /// whose lines were moved by scrolling
+    moved_rows: Option<RangeSet<usize>>,
 }
 
 #[derive(Default)]
@@ -203,6 +255,22 @@ impl Surface {
         scr
     }
 
+    /// Create a surface from an existing set of lines.
+    /// The height is taken from the number of lines, and each
+    /// line is truncated or padded to the specified width.
+    /// The cursor is placed at the origin.
+    pub fn from_lines(lines: Vec<Line>, width: usize) -> Self {
+        let height = lines.len();
+        let mut scr = Surface {
+            width,
+            height,
+            lines,
+            ..Default::default()
+        };
+        scr.resize(width, height);
+        scr
+    }
+
     /// Returns the (width, height) of the surface
     pub fn dimensions(&self) -> (usize, usize) {
         (self.width, self.height)
@@ -224,6 +292,35 @@ impl Surface {
         &self.title
     }
 
+    /// Sets the title, truncating it to at most `max_title_len`
+    /// characters, and records the corresponding `Change::Title`.
+    /// Returns the sequence number of the change.
+    pub fn set_title(&mut self, title: impl Into<String>) -> SequenceNo {
+        let title = self.truncate_title(title.into());
+        self.add_change(Change::Title(title))
+    }
+
+    /// Changes the maximum number of characters that `set_title` and
+    /// `apply_ansi` will accept for the title; longer titles are
+    /// truncated.  The default is `DEFAULT_MAX_TITLE_LEN`.
+    pub fn set_max_title_len(&mut self, max_len: usize) {
+        self.max_title_len.replace(max_len);
+    }
+
+    fn truncate_title(&self, mut title: String) -> String {
+        let max_len = self.max_title_len.unwrap_or(DEFAULT_MAX_TITLE_LEN);
+        if let Some((idx, _)) = title.char_indices().nth(max_len) {
+            title.truncate(idx);
+        }
+        title
+    }
+
+    /// Returns the working directory that was most recently recorded
+    /// via `Change::CurrentWorkingDirectory`, if any
+    pub fn current_working_dir(&self) -> Option<&str> {
+        self.current_working_dir.as_deref()
+    }
+
     /// Resize the Surface to the specified width and height.
     /// If the width and/or height are smaller than previously, the rows and/or
     /// columns are truncated.  If the width and/or height are larger than
@@ -255,11 +352,132 @@ impl Surface {
         self.width = width;
         self.height = height;
 
+        // Keep the scroll region within the bounds of the new size,
+        // reverting to full screen scrolling if it no longer fits.
+        self.scroll_region = self
+            .scroll_region
+            .take()
+            .map(|region| region.start..region.end.min(height))
+            .filter(|region| !region.is_empty());
+
         // Ensure that the cursor position is well-defined
         self.xpos = compute_position_change(self.xpos, &Position::Relative(0), self.width);
         self.ypos = compute_position_change(self.ypos, &Position::Relative(0), self.height);
     }
 
+    /// Parse `bytes` as a stream of terminal output and apply the
+    /// equivalent changes to the surface.
+    /// Text, SGR attributes, cursor movement, erasing and window title
+    /// changes are supported; other sequences are ignored.
+    /// An escape sequence may be split across multiple calls.
+    /// Returns the sequence number of the surface after the changes.
+    pub fn apply_ansi(&mut self, bytes: &[u8]) -> SequenceNo {
+        let actions = self
+            .ansi_parser
+            .get_or_insert_with(|| Mutex::new(Parser::new()))
+            .get_mut()
+            .unwrap()
+            .parse_as_vec(bytes);
+
+        let mut text = String::new();
+        for action in actions {
+            match action {
+                Action::Print(c) => text.push(c),
+                Action::PrintString(s) => text.push_str(&s),
+                Action::Control(code @ (ControlCode::CarriageReturn | ControlCode::LineFeed)) => {
+                    text.push(code as u8 as char)
+                }
+                action => {
+                    if !text.is_empty() {
+                        self.add_change(std::mem::take(&mut text));
+                    }
+                    let changes = self.action_to_changes(action);
+                    self.add_changes(changes);
+                }
+            }
+        }
+        if !text.is_empty() {
+            self.add_change(text);
+        }
+
+        self.seqno
+    }
+
+    /// Translates a non-printing action into the equivalent changes,
+    /// given the current state of the surface.
+    fn action_to_changes(&self, action: Action) -> Vec<Change> {
+        let background = self.attributes.background();
+        match action {
+            Action::Control(ControlCode::Backspace) => vec![Change::CursorPosition {
+                x: Position::Relative(-1),
+                y: Position::Relative(0),
+            }],
+            Action::CSI(CSI::Sgr(sgr)) => {
+                let change = match sgr {
+                    Sgr::Reset => Change::AllAttributes(CellAttributes::default()),
+                    Sgr::Intensity(i) => AttributeChange::Intensity(i).into(),
+                    Sgr::Underline(u) => AttributeChange::Underline(u).into(),
+                    Sgr::Blink(b) => AttributeChange::Blink(b).into(),
+                    Sgr::Italic(b) => AttributeChange::Italic(b).into(),
+                    Sgr::Inverse(b) => AttributeChange::Reverse(b).into(),
+                    Sgr::Invisible(b) => AttributeChange::Invisible(b).into(),
+                    Sgr::StrikeThrough(b) => AttributeChange::StrikeThrough(b).into(),
+                    Sgr::Foreground(c) => AttributeChange::Foreground(c.into()).into(),
+                    Sgr::Background(c) => AttributeChange::Background(c.into()).into(),
+                    _ => return vec![],
+                };
+                vec![change]
+            }
+            Action::CSI(CSI::Cursor(cursor)) => {
+                let (x, y) = match cursor {
+                    Cursor::Position { line, col } => (
+                        Position::Absolute(col.as_zero_based() as usize),
+                        Position::Absolute(line.as_zero_based() as usize),
+                    ),
+                    Cursor::CharacterAbsolute(col) | Cursor::CharacterPositionAbsolute(col) => (
+                        Position::Absolute(col.as_zero_based() as usize),
+                        Position::Relative(0),
+                    ),
+                    Cursor::LinePositionAbsolute(line) => (
+                        Position::Relative(0),
+                        Position::Absolute(line.saturating_sub(1) as usize),
+                    ),
+                    Cursor::Up(n) => (Position::Relative(0), Position::Relative(-(n as isize))),
+                    Cursor::Down(n) => (Position::Relative(0), Position::Relative(n as isize)),
+                    Cursor::Left(n) => (Position::Relative(-(n as isize)), Position::Relative(0)),
+                    Cursor::Right(n) => (Position::Relative(n as isize), Position::Relative(0)),
+                    _ => return vec![],
+                };
+                vec![Change::CursorPosition { x, y }]
+            }
+            Action::CSI(CSI::Edit(Edit::EraseInDisplay(EraseInDisplay::EraseToEndOfDisplay))) => {
+                vec![Change::ClearToEndOfScreen(background)]
+            }
+            Action::CSI(CSI::Edit(Edit::EraseInDisplay(EraseInDisplay::EraseDisplay))) => {
+                // ClearScreen homes the cursor, but ED does not move it,
+                // so put it back where it was
+                vec![
+                    Change::ClearScreen(background),
+                    Change::CursorPosition {
+                        x: Position::Absolute(self.xpos),
+                        y: Position::Absolute(self.ypos),
+                    },
+                ]
+            }
+            Action::CSI(CSI::Edit(Edit::EraseInLine(EraseInLine::EraseToEndOfLine))) => {
+                vec![Change::ClearToEndOfLine(background)]
+            }
+            Action::OperatingSystemCommand(osc) => match *osc {
+                OperatingSystemCommand::SetWindowTitle(title)
+                | OperatingSystemCommand::SetIconNameAndWindowTitle(title) => {
+                    vec![Change::Title(self.truncate_title(title))]
+                }
+                _ => vec![],
+            },
+            _ => vec![],
+        }
+    }
+
     /// Efficiently apply a series of changes
     /// Returns the sequence number at the end of the change.
     pub fn add_changes(&mut self, mut changes: Vec<Change>) -> SequenceNo {
@@ -275,6 +493,34 @@ impl Surface {
         seq
     }
 
+    /// Apply a series of changes, returning the sequence number at
+    /// the end of the changes along with the set of row indices
+    /// that were modified by them.
+    /// This allows a renderer to repaint just those rows without
+    /// having to perform a separate diff pass.
+    pub fn apply_changes_tracked(&mut self, changes: Vec<Change>) -> (SequenceNo, RangeSet<usize>) {
+        let before = self.seqno;
+        let mut seq = self.seqno.saturating_sub(1);
+
+        // Applying each change individually stamps the lines it
+        // touches with a seqno newer than `before`.  Lines that
+        // are moved by scrolling keep their seqno, so those rows
+        // are collected separately.
+        self.moved_rows.replace(RangeSet::new());
+        for change in changes {
+            seq = self.add_change(change);
+        }
+
+        let mut rows = self.moved_rows.take().unwrap_or_default();
+        for (idx, line) in self.lines.iter().enumerate() {
+            if line.current_seqno() > before {
+                rows.add(idx);
+            }
+        }
+
+        (seq, rows)
+    }
+
     /// Apply a change and return the sequence number at the end of the change.
     pub fn add_change<C: Into<Change>>(&mut self, change: C) -> SequenceNo {
         let seq = self.seqno;
@@ -285,7 +531,86 @@ impl Surface {
         seq
     }
 
+    /// Like `add_change`, but if applying the change leaves the surface
+    /// looking exactly as it did before, such as moving the cursor to
+    /// where it already is, or printing over identical content, then
+    /// the change is discarded rather than recorded and `None` is
+    /// returned.
+    /// This takes a copy of the lines in order to compare them, so it
+    /// is best suited to smaller surfaces.
+    pub fn add_change_if_effective<C: Into<Change>>(&mut self, change: C) -> Option<SequenceNo> {
+        let lines = self.lines.clone();
+        let state = self.non_line_state();
+
+        // Hold off notifying the observer until we know whether the
+        // change is going to be kept
+        let observer = self.change_observer.take();
+        let seq = self.add_change(change);
+        self.change_observer = observer;
+
+        let effective = self.non_line_state() != state
+            || self
+                .lines
+                .iter()
+                .zip(lines.iter())
+                .any(|(line, before)| line != before && !lines_have_same_content(line, before));
+        if effective {
+            if let Some(observer) = self.change_observer.as_mut() {
+                observer(&self.changes[self.changes.len() - 1]);
+            }
+            Some(seq)
+        } else {
+            self.lines = lines;
+            self.seqno -= 1;
+            self.changes.pop();
+            None
+        }
+    }
+
+    /// Returns the state, other than the content of the lines, that
+    /// a change can affect
+    #[allow(clippy::type_complexity)]
+    fn non_line_state(
+        &self,
+    ) -> (
+        (usize, usize),
+        CellAttributes,
+        Option<CursorShape>,
+        CursorVisibility,
+        ColorAttribute,
+        String,
+        Option<String>,
+        Option<Range<usize>>,
+    ) {
+        (
+            (self.xpos, self.ypos),
+            self.attributes.clone(),
+            self.cursor_shape,
+            self.cursor_visibility,
+            self.cursor_color,
+            self.title.clone(),
+            self.current_working_dir.clone(),
+            self.scroll_region.clone(),
+        )
+    }
+
+    /// Install a closure that is called with each change as it is
+    /// applied to the surface, replacing any previously installed
+    /// observer.  This is intended for instrumentation and testing,
+    /// allowing the exact change stream to be logged or verified.
+    pub fn set_change_observer(&mut self, observer: Box<dyn FnMut(&Change) + Send + Sync>) {
+        self.change_observer.replace(observer);
+    }
+
+    /// Remove the observer installed by `set_change_observer`
+    pub fn clear_change_observer(&mut self) {
+        self.change_observer.take();
+    }
+
     fn apply_change(&mut self, change: &Change) {
+        if let Some(observer) = self.change_observer.as_mut() {
+            observer(change);
+        }
         match change {
             Change::AllAttributes(attr) => self.attributes = attr.clone(),
             Change::Text(text) => self.print_text(text),
@@ -299,6 +624,8 @@ impl Surface {
             Change::CursorVisibility(visibility) => self.cursor_visibility = *visibility,
             Change::Image(image) => self.add_image(image),
             Change::Title(text) => self.title = text.to_owned(),
+            Change::CurrentWorkingDirectory(cwd) => self.current_working_dir = Some(cwd.to_owned()),
+            Change::ScrollRegion(region) => self.set_scroll_region(region),
             Change::ScrollRegionUp {
                 first_row,
                 region_size,
@@ -384,9 +711,50 @@ impl Surface {
         self.lines[self.ypos].fill_range(self.xpos..self.width, &cleared, self.seqno);
     }
 
+    fn set_scroll_region(&mut self, region: &Option<Range<usize>>) {
+        self.scroll_region = region
+            .as_ref()
+            .map(|region| region.start..region.end.min(self.height))
+            .filter(|region| !region.is_empty());
+        self.xpos = 0;
+        self.ypos = 0;
+    }
+
+    /// Returns the half-open range of rows to which scrolling is
+    /// currently restricted, or `None` if the whole screen scrolls.
+    pub fn scroll_region(&self) -> Option<Range<usize>> {
+        self.scroll_region.clone()
+    }
+
+    /// Move the cursor down a line, scrolling the scroll region
+    /// (or the whole screen if there is no region) when the cursor
+    /// is on its last line.
+    fn new_line(&mut self) {
+        let new_y = self.ypos + 1;
+        match self.scroll_region.clone() {
+            Some(region) if new_y == region.end => {
+                self.scroll_region_up(region.start, region.len(), 1);
+            }
+            // Below the scroll region, the cursor stops at the
+            // bottom of the screen without scrolling anything
+            Some(_) if new_y >= self.height => {}
+            None if new_y >= self.height => self.scroll_screen_up(),
+            _ => self.ypos = new_y,
+        }
+    }
+
     fn scroll_screen_up(&mut self) {
         self.lines.remove(0);
         self.lines.push(Line::with_width(self.width, self.seqno));
+        self.note_rows_moved(0..self.height);
+    }
+
+    /// Records that the lines in `rows` moved to a different row, for
+    /// the benefit of `apply_changes_tracked`
+    fn note_rows_moved(&mut self, rows: Range<usize>) {
+        if let Some(moved) = self.moved_rows.as_mut() {
+            moved.add_range(rows);
+        }
     }
 
     fn scroll_region_up(&mut self, start: usize, size: usize, count: usize) {
@@ -397,6 +765,7 @@ impl Surface {
         // Rotate the remaining lines up the surface.
         if 0 < count && count < size {
             self.lines[start..start + size].rotate_left(count);
+            self.note_rows_moved(start..start + size);
         }
     }
 
@@ -408,6 +777,7 @@ impl Surface {
         // Rotate the remaining lines down the surface.
         if 0 < count && count < size {
             self.lines[start..start + size].rotate_right(count);
+            self.note_rows_moved(start..start + size);
         }
     }
 
@@ -425,12 +795,7 @@ impl Surface {
         for g in Graphemes::new(text) {
             if g == "\r\n" {
                 self.xpos = 0;
-                let new_y = self.ypos + 1;
-                if new_y >= self.height {
-                    self.scroll_screen_up();
-                } else {
-                    self.ypos = new_y;
-                }
+                self.new_line();
                 continue;
             }
 
@@ -440,22 +805,12 @@ impl Surface {
             }
 
             if g == "\n" {
-                let new_y = self.ypos + 1;
-                if new_y >= self.height {
-                    self.scroll_screen_up();
-                } else {
-                    self.ypos = new_y;
-                }
+                self.new_line();
                 continue;
             }
 
             if self.xpos >= self.width {
-                let new_y = self.ypos + 1;
-                if new_y >= self.height {
-                    self.scroll_screen_up();
-                } else {
-                    self.ypos = new_y;
-                }
+                self.new_line();
                 self.xpos = 0;
             }
 
@@ -481,6 +836,25 @@ impl Surface {
         self.ypos = compute_position_change(self.ypos, y, self.height);
     }
 
+    /// Computes a hash of the visible content of the surface; the
+    /// text and attributes of each cell along with the dimensions.
+    /// The change log, sequence numbers and cursor state are not
+    /// included, so two surfaces that look the same produce the same
+    /// hash regardless of how they came to be that way.
+    /// The hash is computed with a fixed key, so it is stable across
+    /// runs of the program.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = siphasher::sip::SipHasher::new();
+        self.width.hash(&mut hasher);
+        self.height.hash(&mut hasher);
+        for line in &self.lines {
+            for cell in line.visible_cells() {
+                cell.compute_shape_hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
     /// Returns the entire contents of the screen as a string.
     /// Only the character data is returned.  The end of each line is
     /// returned as a \n character.
@@ -512,6 +886,77 @@ impl Surface {
         self.lines.iter().map(|line| Cow::Borrowed(line)).collect()
     }
 
+    /// Returns an iterator over the visible cells of the screen in
+    /// row-major order, yielding `(x, y, cell)` for each one.
+    /// The continuation cells that follow a double-width grapheme
+    /// are skipped; the grapheme is yielded once at its starting column.
+    pub fn iter_cells(&self) -> impl Iterator<Item = (usize, usize, CellRef)> {
+        self.lines.iter().enumerate().flat_map(|(y, line)| {
+            line.visible_cells()
+                .map(move |cell| (cell.cell_index(), y, cell))
+        })
+    }
+
+    /// Returns the `(x, y)` coordinates of the cells that have a blink
+    /// attribute set, so that a renderer can limit its blink animation
+    /// to just those cells.
+    pub fn blinking_cells(&self) -> Vec<(usize, usize)> {
+        self.iter_cells()
+            .filter(|(_, _, cell)| cell.attrs().blink() != Blink::None)
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+
+    /// Returns the column after the last cell in row `y` that is
+    /// either non-blank or has non-default attributes, or 0 if the
+    /// row is empty.  A wide grapheme counts for its full width.
+    pub fn row_used_width(&self, y: usize) -> usize {
+        let default_attrs = CellAttributes::default();
+        self.lines[y]
+            .visible_cells()
+            .filter(|cell| is_cell_used(cell, &default_attrs))
+            .last()
+            .map(|cell| cell.cell_index() + cell.width())
+            .unwrap_or(0)
+    }
+
+    /// Returns the `(min_x, min_y, max_x, max_y)` bounding box, inclusive
+    /// of its edges, of the cells that are either non-blank or have
+    /// non-default attributes, or `None` if there are no such cells.
+    pub fn content_bounds(&self) -> Option<(usize, usize, usize, usize)> {
+        let default_attrs = CellAttributes::default();
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for (y, line) in self.lines.iter().enumerate() {
+            let min_x = match line
+                .visible_cells()
+                .find(|cell| is_cell_used(cell, &default_attrs))
+            {
+                Some(cell) => cell.cell_index(),
+                None => continue,
+            };
+            let max_x = self.row_used_width(y) - 1;
+            bounds = Some(match bounds {
+                None => (min_x, y, max_x, y),
+                Some((x1, y1, x2, _)) => (x1.min(min_x), y1, x2.max(max_x), y),
+            });
+        }
+        bounds
+    }
+
+    /// Associate application specific data with row `y`.
+    /// See `Line::set_appdata`; only a Weak reference is stored,
+    /// so the caller is responsible for keeping the data alive.
+    /// The data is retained by rows that survive a `resize`.
+    pub fn set_line_appdata<T: Any + Send + Sync>(&self, y: usize, appdata: Arc<T>) {
+        self.lines[y].set_appdata(appdata);
+    }
+
+    /// Retrieve the application specific data previously associated
+    /// with row `y` via `set_line_appdata`, if it is still alive.
+    pub fn get_line_appdata(&self, y: usize) -> Option<Arc<dyn Any + Send + Sync>> {
+        self.lines[y].get_appdata()
+    }
+
     /// Returns a stream of changes suitable to update the screen
     /// to match the model.  The input `seq` argument should be 0
     /// on the first call, or in any situation where the screen
@@ -543,6 +988,126 @@ impl Surface {
         }
     }
 
+    /// Like `get_changes`, but if the incremental change stream would
+    /// consist of more than `max_changes` entries then a full repaint
+    /// is returned instead.  This gives the caller explicit control over
+    /// the size of the update, rather than relying solely on the
+    /// estimated cost of a full repaint.
+    pub fn get_changes_capped(
+        &self,
+        seq: SequenceNo,
+        max_changes: usize,
+    ) -> (SequenceNo, Cow<[Change]>) {
+        match self.get_changes(seq) {
+            (seq, Cow::Borrowed(changes)) if changes.len() > max_changes => {
+                (seq, Cow::Owned(self.repaint_all()))
+            }
+            result => result,
+        }
+    }
+
+    /// Like `get_changes`, but partitions the changes by the row that
+    /// they affect, so that a renderer can update just those rows.
+    /// Batches are keyed by `Option<usize>` rather than by a plain row
+    /// index so that changes which don't target a single row can be
+    /// returned in stream order alongside the others, instead of being
+    /// dropped or reordered.  Those changes are returned in batches
+    /// keyed by `None`: changes to the cursor, title and scroll region,
+    /// changes that affect several rows such as clearing or scrolling
+    /// the screen, and changes that precede the first change that
+    /// positions the cursor, as the row that they target cannot be
+    /// determined.
+    /// The stream is split at each of those changes, and the row batches
+    /// between two such splits are returned in ascending row order, so
+    /// applying the batches in the order that they are returned has the
+    /// same effect as applying the original changes.  A row may therefore
+    /// appear in more than one batch.
+    /// Text that continues onto subsequent rows is split at the row
+    /// boundaries.  The changes in each batch retain their relative order.
+    pub fn get_changes_by_row(
+        &self,
+        seq: SequenceNo,
+    ) -> (SequenceNo, Vec<(Option<usize>, Vec<Change>)>) {
+        let (seq, changes) = self.get_changes(seq);
+        let mut batches = vec![];
+        let mut rows: BTreeMap<usize, Vec<Change>> = BTreeMap::new();
+        let mut global = vec![];
+        let mut push = |row: Option<usize>, change: Change| match row {
+            Some(row) => {
+                if !global.is_empty() {
+                    batches.push((None, std::mem::take(&mut global)));
+                }
+                rows.entry(row).or_default().push(change);
+            }
+            None => {
+                batches.extend(
+                    std::mem::take(&mut rows)
+                        .into_iter()
+                        .map(|(row, changes)| (Some(row), changes)),
+                );
+                global.push(change);
+            }
+        };
+        let mut tracker = RowTracker {
+            x: None,
+            y: None,
+            width: self.width,
+            height: self.height,
+            scroll_region: self.scroll_region.clone(),
+        };
+
+        for change in changes.iter() {
+            let row = match change {
+                Change::Text(text) => {
+                    if let Some(runs) = tracker.split_text(text) {
+                        for (row, text) in runs {
+                            push(Some(row), Change::Text(text));
+                        }
+                        continue;
+                    }
+                    None
+                }
+                Change::Attribute(_)
+                | Change::AllAttributes(_)
+                | Change::ClearToEndOfLine(_)
+                | Change::LineAttribute(_) => tracker.y,
+                Change::ClearScreen(_) => {
+                    tracker.home();
+                    None
+                }
+                Change::ScrollRegion(region) => {
+                    tracker.scroll_region = region.clone();
+                    tracker.home();
+                    None
+                }
+                Change::CursorPosition { x, y } => {
+                    tracker.x = tracker.position(tracker.x, x, tracker.width);
+                    tracker.y = tracker.position(tracker.y, y, tracker.height);
+                    None
+                }
+                Change::Image(image) => {
+                    tracker.x = tracker.x.map(|x| x + image.width);
+                    None
+                }
+                Change::ClearToEndOfScreen(_)
+                | Change::ScrollRegionUp { .. }
+                | Change::ScrollRegionDown { .. }
+                | Change::CursorColor(_)
+                | Change::CursorShape(_)
+                | Change::CursorVisibility(_)
+                | Change::Title(_)
+                | Change::CurrentWorkingDirectory(_) => None,
+            };
+            push(row, change.clone());
+        }
+
+        if !global.is_empty() {
+            batches.push((None, global));
+        }
+        batches.extend(rows.into_iter().map(|(row, changes)| (Some(row), changes)));
+        (seq, batches)
+    }
+
     pub fn has_changes(&self, seq: SequenceNo) -> bool {
         self.seqno != seq
     }
@@ -583,6 +1148,16 @@ impl Surface {
             result.push(Change::Title(self.title.to_owned()));
         }
 
+        if let Some(cwd) = &self.current_working_dir {
+            result.push(Change::CurrentWorkingDirectory(cwd.to_owned()));
+        }
+
+        if self.scroll_region.is_some() {
+            // Setting the region homes the cursor, which is also where
+            // the ClearScreen above left it.
+            result.push(Change::ScrollRegion(self.scroll_region.clone()));
+        }
+
         let mut attr = CellAttributes::default();
 
         let crlf = Change::CursorPosition {
@@ -781,12 +1356,48 @@ impl Surface {
         diff_state.changes
     }
 
+    /// Computes the change stream required to make `self` display the
+    /// provided lines of text using the default attributes.
+    /// Lines that are longer than the width of the surface are truncated,
+    /// while shorter lines, and any rows for which no text is provided,
+    /// are padded with blanks.
+    pub fn diff_against_text(&self, lines: &[String]) -> Vec<Change> {
+        let mut diff_state = DiffState::default();
+        let attrs = CellAttributes::default();
+        for (row_num, line) in self.lines.iter().enumerate() {
+            let text = lines.get(row_num).map(String::as_str).unwrap_or("");
+            let mut other_line = Line::from_text(text, &attrs, SEQ_ZERO, None);
+            other_line.resize(self.width, SEQ_ZERO);
+            diff_line(
+                &mut diff_state,
+                line,
+                row_num,
+                &other_line,
+                0,
+                self.width,
+                0,
+            );
+        }
+        diff_state.changes
+    }
+
     /// Computes the change stream required to make `self` have the same
     /// screen contents as `other`.
     pub fn diff_screens(&self, other: &Surface) -> Vec<Change> {
         self.diff_region(0, 0, self.width, self.height, other, 0, 0)
     }
 
+    /// Updates `self` so that it has the same screen contents as `other`,
+    /// which is expected to have the same dimensions, by applying the
+    /// changes computed by `diff_screens`.
+    /// Returns the set of row indices that were modified, so that a
+    /// double-buffered renderer can repaint just those rows.
+    pub fn sync_from(&mut self, other: &Surface) -> RangeSet<usize> {
+        let changes = self.diff_screens(other);
+        let (_seq, rows) = self.apply_changes_tracked(changes);
+        rows
+    }
+
     /// Draw the contents of `other` into self at the specified coordinates.
     /// The required updates are recorded as Change entries as well as stored
     /// in the screen line/cell data.
@@ -804,6 +1415,224 @@ impl Surface {
         seq
     }
 
+    /// Applies `changes` and then records the changes needed to move
+    /// the cursor back to where it was and to restore the attributes
+    /// that were in effect beforehand, so that the change log agrees
+    /// with the state of the surface.
+    /// A cursor that is past the last column, waiting to wrap, is
+    /// restored to the last column.
+    fn add_changes_preserving_cursor(&mut self, mut changes: Vec<Change>) -> SequenceNo {
+        changes.push(Change::CursorPosition {
+            x: Position::Absolute(self.xpos),
+            y: Position::Absolute(self.ypos),
+        });
+        changes.push(Change::AllAttributes(self.attributes.clone()));
+        self.add_changes(changes)
+    }
+
+    /// Replaces row `y` with `line`, which is first resized to the width
+    /// of the surface.  Only the changes needed to turn the previous
+    /// content of the row into `line` are recorded.
+    /// Saves the cursor position and attributes that were in effect prior
+    /// to calling `set_line` and restores them afterwards.
+    /// If `y` is outside the surface, nothing is changed and the current
+    /// sequence number is returned.
+    pub fn set_line(&mut self, y: usize, mut line: Line) -> SequenceNo {
+        if y >= self.height {
+            return self.seqno;
+        }
+        line.resize(self.width, self.seqno);
+        let changes = self.diff_against_numbered_line(y, &line);
+        if changes.is_empty() {
+            return self.seqno;
+        }
+
+        let seq = self.add_changes_preserving_cursor(changes);
+        line.update_last_change_seqno(seq);
+        self.lines[y] = line;
+        seq
+    }
+
+    /// Word-wraps `text` to fit within the box of the specified `width`
+    /// with its top left corner at `x`, `y`, and draws it there using
+    /// `attrs`.  Newlines in `text` start a new line, and words that are
+    /// wider than the box are broken between graphemes.  A grapheme that
+    /// is wider than the box itself, such as a double-width character in
+    /// a box that is only one column wide, is omitted.
+    /// Tabs are drawn as spaces, and other control characters are removed
+    /// so that they cannot move the cursor outside of the box.
+    /// Lines beyond `height` rows are clipped, as is the box itself if it
+    /// extends beyond the edges of the surface.
+    /// Returns the number of rows that were drawn.
+    /// The cursor position and attributes that were in effect prior to
+    /// calling `draw_text_wrapped` are restored afterwards.
+    pub fn draw_text_wrapped(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        text: &str,
+        attrs: &CellAttributes,
+    ) -> usize {
+        let width = width.min(self.width.saturating_sub(x));
+        let height = height.min(self.height.saturating_sub(y));
+        if width == 0 || height == 0 {
+            return 0;
+        }
+
+        let lines = wrap_text(text, width);
+        let rows = lines.len().min(height);
+        let mut changes = vec![Change::AllAttributes(attrs.clone())];
+        for (row, line) in lines.into_iter().take(rows).enumerate() {
+            changes.push(Change::CursorPosition {
+                x: Position::Absolute(x),
+                y: Position::Absolute(y + row),
+            });
+            changes.push(Change::Text(line));
+        }
+        self.add_changes_preserving_cursor(changes);
+        rows
+    }
+
+    /// Fills `width` columns of row `y`, starting at column `x`, by
+    /// repeating the graphemes of `pattern` using `attrs`.
+    /// The span is clamped to the bounds of the surface.  If a wide
+    /// grapheme would not fit in the columns that remain at the end of
+    /// the span, those columns are filled with spaces instead.
+    /// The cursor position and attributes that were in effect prior to
+    /// calling `fill_row_pattern` are restored afterwards.
+    /// Returns the sequence number of the last change; if there is
+    /// nothing to fill, nothing is changed and the current sequence
+    /// number is returned.
+    pub fn fill_row_pattern(
+        &mut self,
+        y: usize,
+        x: usize,
+        width: usize,
+        pattern: &str,
+        attrs: &CellAttributes,
+    ) -> SequenceNo {
+        let width = width.min(self.width.saturating_sub(x));
+        let graphemes: Vec<(&str, usize)> = Graphemes::new(pattern)
+            .map(|g| (g, unicode_column_width(g, None).max(1)))
+            .collect();
+        if y >= self.height || width == 0 || graphemes.is_empty() {
+            return self.seqno;
+        }
+
+        let mut text = String::new();
+        let mut used = 0;
+        for (g, g_width) in graphemes.iter().cycle() {
+            if used + g_width > width {
+                break;
+            }
+            text.push_str(g);
+            used += g_width;
+        }
+        for _ in used..width {
+            text.push(' ');
+        }
+
+        self.add_changes_preserving_cursor(vec![
+            Change::CursorPosition {
+                x: Position::Absolute(x),
+                y: Position::Absolute(y),
+            },
+            Change::AllAttributes(attrs.clone()),
+            Change::Text(text),
+        ])
+    }
+
+    /// Clears the entire surface to blank cells with a background of
+    /// `color`, moving the cursor to the top left corner and resetting
+    /// the current attributes to that background.
+    /// This records a single `Change::ClearScreen` rather than a change
+    /// for each cleared line, so it is the cheapest way to blank the
+    /// surface.
+    /// Returns the sequence number of the clear.
+    pub fn clear_to_color(&mut self, color: ColorAttribute) -> SequenceNo {
+        self.add_change(Change::ClearScreen(color))
+    }
+
+    /// Draws the outline of a box of `width` x `height` cells, with its
+    /// top left corner at `x`, `y`, using the box drawing characters of
+    /// `style` and `attrs`.  The cells inside the box are left untouched.
+    /// The parts of the box that fall outside of the surface are clipped.
+    /// The cursor position and attributes that were in effect prior to
+    /// calling `draw_box` are restored afterwards.
+    /// Returns the sequence number of the last change; if the box is
+    /// smaller than 2x2 cells or not visible, nothing is changed and the
+    /// current sequence number is returned.
+    pub fn draw_box(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        style: BoxStyle,
+        attrs: &CellAttributes,
+    ) -> SequenceNo {
+        if width < 2 || height < 2 || x >= self.width || y >= self.height {
+            return self.seqno;
+        }
+        let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] = style.glyphs();
+        let right = x.saturating_add(width - 1);
+        let bottom = y.saturating_add(height - 1);
+        let visible = self.width - x;
+
+        let edge = |left: char, right: char| -> String {
+            std::iter::once(left)
+                .chain(std::iter::repeat(horizontal).take(width - 2))
+                .chain(std::iter::once(right))
+                .take(visible)
+                .collect()
+        };
+
+        let mut changes = vec![Change::AllAttributes(attrs.clone())];
+        let mut text_at = |x: usize, y: usize, text: String| {
+            changes.push(Change::CursorPosition {
+                x: Position::Absolute(x),
+                y: Position::Absolute(y),
+            });
+            changes.push(Change::Text(text));
+        };
+
+        text_at(x, y, edge(top_left, top_right));
+        for row in y + 1..bottom.min(self.height) {
+            text_at(x, row, vertical.to_string());
+            if right < self.width {
+                text_at(right, row, vertical.to_string());
+            }
+        }
+        if bottom < self.height {
+            text_at(x, bottom, edge(bottom_left, bottom_right));
+        }
+
+        self.add_changes_preserving_cursor(changes)
+    }
+
+    /// Returns a new standalone `Surface` holding a copy of the cells
+    /// in the region of `self` with its top left corner at `x`, `y`.
+    /// The region is clamped to the bounds of `self`, so the returned
+    /// surface may be smaller than the requested `width` and `height`.
+    /// Cell attributes are preserved while the cursor position and
+    /// pen attributes of the new surface are left at their defaults.
+    pub fn extract_region(&self, x: usize, y: usize, width: usize, height: usize) -> Surface {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        let width = width.min(self.width - x);
+        let height = height.min(self.height - y);
+
+        let mut region = Surface::new(width, height);
+        let changes = region.diff_region(0, 0, width, height, self, x, y);
+        region.add_changes(changes);
+        region.xpos = 0;
+        region.ypos = 0;
+        region.attributes = CellAttributes::default();
+        region
+    }
+
     /// Copy the contents of the specified region to the same sized
     /// region elsewhere in the screen display.
     /// The regions may overlap.
@@ -878,6 +1707,92 @@ fn diff_line(
     }
 }
 
+/// Tracks the cursor through a change stream on behalf of
+/// `Surface::get_changes_by_row`.  The coordinates are `None`
+/// until the stream positions the cursor.
+struct RowTracker {
+    x: Option<usize>,
+    y: Option<usize>,
+    width: usize,
+    height: usize,
+    scroll_region: Option<Range<usize>>,
+}
+
+impl RowTracker {
+    fn home(&mut self) {
+        self.x = Some(0);
+        self.y = Some(0);
+    }
+
+    fn position(&self, current: Option<usize>, pos: &Position, limit: usize) -> Option<usize> {
+        match (current, pos) {
+            (Some(current), _) => Some(compute_position_change(current, pos, limit)),
+            (None, Position::Relative(_)) => None,
+            (None, _) => Some(compute_position_change(0, pos, limit)),
+        }
+    }
+
+    /// Splits `text` into the runs that are printed on each row,
+    /// mirroring `Surface::print_text`, and advances the cursor.
+    /// Returns `None` if the row(s) can't be determined, or if
+    /// printing the text scrolls the surface.
+    fn split_text(&mut self, text: &str) -> Option<Vec<(usize, String)>> {
+        let (mut x, mut y) = match (self.x, self.y) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return None,
+        };
+        let mut scrolled = false;
+        let mut runs = vec![];
+        let mut run = String::new();
+
+        let mut new_line = |y: &mut usize, run: &mut String, runs: &mut Vec<(usize, String)>| {
+            if !run.is_empty() {
+                runs.push((*y, std::mem::take(run)));
+            }
+            let new_y = *y + 1;
+            match &self.scroll_region {
+                Some(region) if new_y == region.end => scrolled = true,
+                Some(_) if new_y >= self.height => {}
+                None if new_y >= self.height => scrolled = true,
+                _ => *y = new_y,
+            }
+        };
+
+        for g in Graphemes::new(text) {
+            match g {
+                "\r\n" => {
+                    x = 0;
+                    new_line(&mut y, &mut run, &mut runs);
+                }
+                "\n" => new_line(&mut y, &mut run, &mut runs),
+                "\r" => {
+                    x = 0;
+                    run.push_str(g);
+                }
+                _ => {
+                    if x >= self.width {
+                        new_line(&mut y, &mut run, &mut runs);
+                        x = 0;
+                    }
+                    run.push_str(g);
+                    x += grapheme_column_width(g, None).max(1);
+                }
+            }
+        }
+        if !run.is_empty() {
+            runs.push((y, run));
+        }
+
+        self.x = Some(x);
+        self.y = Some(y);
+        if scrolled {
+            None
+        } else {
+            Some(runs)
+        }
+    }
+}
+
 /// Applies a Position update to either the x or y position.
 /// The value is clamped to be in the range: 0..limit
 fn compute_position_change(current: usize, pos: &Position, limit: usize) -> usize {
@@ -898,6 +1813,86 @@ fn compute_position_change(current: usize, pos: &Position, limit: usize) -> usiz
     }
 }
 
+/// Word-wraps `text` into lines that are no more than `width` columns
+/// wide.  Runs of spaces between words are collapsed at line breaks,
+/// and words that are wider than `width` are broken between graphemes.
+/// A grapheme that is wider than `width` is dropped.
+/// Tabs are treated as spaces, and other control characters, which
+/// would move the cursor when printed, are removed.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.split('\n') {
+        let paragraph: String = paragraph
+            .chars()
+            .filter_map(|c| match c {
+                '\t' => Some(' '),
+                c if c.is_control() => None,
+                c => Some(c),
+            })
+            .collect();
+        let mut line = String::new();
+        let mut line_width = 0;
+        for word in paragraph.split(' ').filter(|word| !word.is_empty()) {
+            let word_width = unicode_column_width(word, None);
+            if line_width > 0 && line_width + 1 + word_width <= width {
+                line.push(' ');
+                line.push_str(word);
+                line_width += 1 + word_width;
+                continue;
+            }
+            if line_width > 0 {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if word_width <= width {
+                line.push_str(word);
+                line_width = word_width;
+                continue;
+            }
+            for g in Graphemes::new(word) {
+                let g_width = grapheme_column_width(g, None);
+                if g_width > width {
+                    continue;
+                }
+                if line_width + g_width > width {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                }
+                line.push_str(g);
+                line_width += g_width;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Returns true if the two lines have the same content and
+/// attributes, disregarding when they were last changed
+fn lines_have_same_content(a: &Line, b: &Line) -> bool {
+    // Trailing blanks may have been pruned from one of the lines,
+    // which doesn't change how it looks
+    fn content(line: &Line) -> Vec<Cell> {
+        let mut cells: Vec<Cell> = line.visible_cells().map(|cell| cell.as_cell()).collect();
+        let blank = Cell::blank();
+        while cells.last() == Some(&blank) {
+            cells.pop();
+        }
+        cells
+    }
+
+    a.is_double_width() == b.is_double_width()
+        && a.is_double_height_top() == b.is_double_height_top()
+        && a.is_double_height_bottom() == b.is_double_height_bottom()
+        && content(a) == content(b)
+}
+
+/// Returns true if the cell is either non-blank or has
+/// non-default attributes
+fn is_cell_used(cell: &CellRef, default_attrs: &CellAttributes) -> bool {
+    cell.str() != " " || cell.attrs() != default_attrs
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -954,11 +1949,438 @@ mod test {
     }
 
     #[test]
-    fn newline() {
+    fn apply_changes_tracked() {
         let mut s = Surface::new(4, 4);
-        s.add_change("bloo\rwat\n hey\r\nho");
-        assert_eq!(
-            s.screen_chars_to_string(),
+        s.add_change("a");
+
+        let (seq, rows) = s.apply_changes_tracked(vec![
+            Change::CursorPosition {
+                x: Position::Absolute(0),
+                y: Position::Absolute(1),
+            },
+            Change::Text("b".to_string()),
+            Change::CursorPosition {
+                x: Position::Absolute(2),
+                y: Position::Absolute(3),
+            },
+            Change::Text("c".to_string()),
+        ]);
+
+        assert_eq!(seq, s.current_seqno() - 1);
+        assert_eq!(rows.iter_values().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "a\x20\x20\x20\n\
+             b\x20\x20\x20\n\
+             \x20\x20\x20\x20\n\
+             \x20\x20c\x20\n"
+        );
+
+        // Scrolling moves every row, but doesn't alter the lines
+        // themselves
+        let seqnos = |s: &Surface| {
+            s.screen_lines()
+                .iter()
+                .map(|line| line.current_seqno())
+                .collect::<Vec<_>>()
+        };
+        let before = seqnos(&s);
+        let (_seq, rows) = s.apply_changes_tracked(vec![Change::Text("\r\n".to_string())]);
+        assert_eq!(rows.iter_values().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        assert_eq!(&seqnos(&s)[..3], &before[1..]);
+
+        // and rows are only collected while tracking
+        s.add_change("\r\n");
+        let (_seq, rows) = s.apply_changes_tracked(vec![]);
+        assert!(rows.is_empty());
+    }
+
+    #[test]
+    fn draw_text_wrapped() {
+        let mut s = Surface::new(12, 5);
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(11),
+            y: Position::Absolute(4),
+        });
+        let rows = s.draw_text_wrapped(
+            1,
+            0,
+            9,
+            5,
+            "the quick brown fox\njumped  over",
+            &CellAttributes::default(),
+        );
+        assert_eq!(rows, 4);
+        let expected: String = [
+            " the quick  ",
+            " brown fox  ",
+            " jumped     ",
+            " over       ",
+            "            ",
+        ]
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect();
+        assert_eq!(s.screen_chars_to_string(), expected);
+        // The cursor is left where it was
+        assert_eq!(s.cursor_position(), (11, 4));
+
+        // and the change log agrees
+        let mut replay = Surface::new(12, 5);
+        replay.add_changes(s.changes.clone());
+        assert_eq!(replay.screen_chars_to_string(), expected);
+        assert_eq!(replay.cursor_position(), (11, 4));
+    }
+
+    #[test]
+    fn fill_row_pattern() {
+        let mut s = Surface::new(8, 3);
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(7),
+            y: Position::Absolute(2),
+        });
+        let attrs = CellAttributes::default();
+
+        s.fill_row_pattern(0, 1, 5, "-=", &attrs);
+        // The span is clamped to the width of the surface
+        s.fill_row_pattern(1, 3, 100, "ab", &attrs);
+        assert_eq!(s.screen_chars_to_string(), " -=-=-  \n   ababa\n        \n");
+        // The cursor is left where it was
+        assert_eq!(s.cursor_position(), (7, 2));
+
+        // and the change log agrees
+        let mut replay = Surface::new(8, 3);
+        replay.add_changes(s.changes.clone());
+        assert_eq!(replay.cursor_position(), (7, 2));
+
+        // Nothing happens for out of bounds rows or an empty pattern
+        let seq = s.current_seqno();
+        assert_eq!(s.fill_row_pattern(3, 0, 8, "x", &attrs), seq);
+        assert_eq!(s.fill_row_pattern(2, 0, 8, "", &attrs), seq);
+        assert_eq!(s.fill_row_pattern(2, 8, 8, "x", &attrs), seq);
+    }
+
+    #[test]
+    fn draw_box() {
+        let mut s = Surface::new(6, 4);
+        s.add_change("abcdef\r\nabcdef");
+        let attrs = CellAttributes::default();
+
+        s.draw_box(0, 0, 4, 3, BoxStyle::Single, &attrs);
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "┌──┐ef\n│bc│ef\n└──┘  \n      \n"
+        );
+        // The cursor is moved back to where it was, although a pending
+        // wrap can't be expressed as a position and is clamped to the
+        // last column
+        assert_eq!(s.cursor_position(), (5, 1));
+        let mut replay = Surface::new(6, 4);
+        replay.add_changes(s.changes.clone());
+        assert_eq!(replay.cursor_position(), (5, 1));
+
+        s.draw_box(2, 1, 4, 3, BoxStyle::Double, &attrs);
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "┌──┐ef\n│b╔══╗\n└─║┘ ║\n  ╚══╝\n"
+        );
+
+        s.draw_box(0, 0, 2, 2, BoxStyle::Rounded, &attrs);
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "╭╮─┐ef\n╰╯╔══╗\n└─║┘ ║\n  ╚══╝\n"
+        );
+
+        // Nothing happens for boxes that are too small or out of bounds
+        let seq = s.current_seqno();
+        assert_eq!(s.draw_box(0, 0, 1, 3, BoxStyle::Single, &attrs), seq);
+        assert_eq!(s.draw_box(6, 0, 3, 3, BoxStyle::Single, &attrs), seq);
+        assert_eq!(s.draw_box(0, 4, 3, 3, BoxStyle::Single, &attrs), seq);
+    }
+
+    #[test]
+    fn draw_box_clipped() {
+        let mut s = Surface::new(5, 3);
+        s.draw_box(2, 1, 6, 4, BoxStyle::Single, &CellAttributes::default());
+        assert_eq!(s.screen_chars_to_string(), "     \n  ┌──\n  │  \n");
+
+        // The far edges are beyond the surface rather than overflowing
+        let mut s = Surface::new(5, 3);
+        let attrs = CellAttributes::default();
+        s.draw_box(2, 1, usize::MAX, usize::MAX, BoxStyle::Single, &attrs);
+        assert_eq!(s.screen_chars_to_string(), "     \n  ┌──\n  │  \n");
+    }
+
+    #[test]
+    fn clear_to_color() {
+        let mut s = Surface::new(4, 2);
+        s.add_change("hello");
+        s.add_change(Change::Attribute(AttributeChange::Intensity(
+            Intensity::Bold,
+        )));
+        let seq = s.current_seqno();
+
+        let color: ColorAttribute = AnsiColor::Navy.into();
+        assert_eq!(s.clear_to_color(color), seq);
+        assert_eq!(s.current_seqno(), seq + 1);
+
+        let (_seq, changes) = s.get_changes(seq);
+        assert_eq!(changes.into_owned(), vec![Change::ClearScreen(color)]);
+
+        assert_eq!(s.cursor_position(), (0, 0));
+        assert_eq!(s.screen_chars_to_string(), "    \n    \n");
+        let blank = CellAttributes::default().set_background(color).clone();
+        for line in s.screen_lines() {
+            for cell in line.visible_cells() {
+                assert_eq!(cell.attrs(), &blank);
+            }
+        }
+    }
+
+    #[test]
+    fn fill_row_pattern_wide() {
+        let mut s = Surface::new(8, 2);
+        let attrs = CellAttributes::default();
+
+        // Three wide graphemes fit in 7 columns; the last column is
+        // too narrow for another and is left blank
+        s.fill_row_pattern(0, 0, 7, "\u{1F468}", &attrs);
+        s.fill_row_pattern(1, 0, 8, "\u{1F468}.", &attrs);
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "\u{1F468}\u{1F468}\u{1F468}  \n\
+             \u{1F468}.\u{1F468}.\u{1F468}\n"
+        );
+    }
+
+    #[test]
+    fn draw_text_wrapped_clips_and_splits() {
+        let mut s = Surface::new(4, 2);
+        // The long word is broken, and the wide graphemes are kept whole
+        // rather than being split across lines
+        let rows = s.draw_text_wrapped(
+            0,
+            0,
+            3,
+            5,
+            "abcd \u{1F468}\u{1F468} e",
+            &CellAttributes::default(),
+        );
+        assert_eq!(rows, 2);
+        assert_eq!(s.screen_chars_to_string(), "abc \nd   \n");
+
+        assert_eq!(
+            wrap_text("abcd \u{1F468}\u{1F468} e", 3),
+            vec![
+                "abc".to_string(),
+                "d".to_string(),
+                "\u{1F468}".to_string(),
+                "\u{1F468}".to_string(),
+                "e".to_string(),
+            ]
+        );
+
+        // A grapheme that can't fit in the box at all is omitted
+        assert_eq!(
+            wrap_text("a\u{1F468}b", 1),
+            vec!["a".to_string(), "b".to_string()]
+        );
+    }
+
+    #[test]
+    fn draw_text_wrapped_control_characters() {
+        let mut s = Surface::new(6, 2);
+        let rows = s.draw_text_wrapped(1, 0, 5, 2, "a\tb\r\nc\x1b[2Jd", &CellAttributes::default());
+        assert_eq!(rows, 2);
+        // The escape sequence is not interpreted, and the carriage
+        // return doesn't move the text to the left of the box
+        assert_eq!(s.screen_chars_to_string(), " a b  \n c[2Jd\n");
+    }
+
+    #[test]
+    fn sync_from() {
+        let mut rendered = Surface::new(4, 3);
+        rendered.add_change("abcdefghijkl");
+
+        let mut current = Surface::new(4, 3);
+        current.add_change("abcdXfghijkY");
+
+        let rows = rendered.sync_from(&current);
+        assert_eq!(rows.iter_values().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(
+            rendered.screen_chars_to_string(),
+            current.screen_chars_to_string()
+        );
+
+        // Nothing changes when the surfaces already match
+        let rows = rendered.sync_from(&current);
+        assert!(rows.is_empty());
+    }
+
+    #[test]
+    fn get_changes_capped() {
+        let mut s = Surface::new(80, 24);
+        s.add_change("hello");
+        let (seq, _) = s.get_changes(0);
+
+        s.add_change("a");
+        let (_, changes) = s.get_changes_capped(seq, 3);
+        assert_eq!(changes.as_ref(), &[Change::Text("a".to_string())]);
+
+        for _ in 0..4 {
+            s.add_change("b");
+        }
+        // The incremental diff is cheaper than a full paint...
+        let (_, changes) = s.get_changes(seq);
+        assert_eq!(changes.len(), 5);
+        // ...but exceeds the cap, so we get a full repaint
+        let (capped_seq, changes) = s.get_changes_capped(seq, 3);
+        assert_eq!(capped_seq, s.current_seqno());
+        assert_eq!(changes.into_owned(), s.repaint_all());
+    }
+
+    #[test]
+    fn get_changes_by_row() {
+        let mut s = Surface::new(4, 3);
+        s.add_change("hello");
+        let (seq, _) = s.get_changes(0);
+
+        s.add_change("?");
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(0),
+            y: Position::Absolute(1),
+        });
+        s.add_change(Change::Attribute(AttributeChange::Italic(true)));
+        s.add_change("hello");
+        s.add_change(Change::CursorVisibility(CursorVisibility::Hidden));
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(2),
+            y: Position::Relative(-2),
+        });
+        s.add_change(Change::ClearToEndOfLine(Default::default()));
+
+        let (by_row_seq, batches) = s.get_changes_by_row(seq);
+        assert_eq!(by_row_seq, s.current_seqno());
+        assert_eq!(
+            batches,
+            vec![
+                (
+                    None,
+                    vec![
+                        // The row is unknown until the cursor is positioned
+                        Change::Text("?".to_string()),
+                        Change::CursorPosition {
+                            x: Position::Absolute(0),
+                            y: Position::Absolute(1),
+                        },
+                    ]
+                ),
+                (
+                    Some(1),
+                    vec![
+                        Change::Attribute(AttributeChange::Italic(true)),
+                        Change::Text("hell".to_string()),
+                    ]
+                ),
+                (Some(2), vec![Change::Text("o".to_string())]),
+                (
+                    None,
+                    vec![
+                        Change::CursorVisibility(CursorVisibility::Hidden),
+                        Change::CursorPosition {
+                            x: Position::Absolute(2),
+                            y: Position::Relative(-2),
+                        },
+                    ]
+                ),
+                (Some(0), vec![Change::ClearToEndOfLine(Default::default())]),
+            ]
+        );
+    }
+
+    #[test]
+    fn get_changes_by_row_scrolling() {
+        let mut s = Surface::new(4, 2);
+        s.add_change("hello");
+        let (seq, _) = s.get_changes(0);
+
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(0),
+            y: Position::Absolute(1),
+        });
+        // Printing this scrolls the surface, affecting both rows
+        s.add_change("ab\r\ncd");
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(0),
+            y: Position::Absolute(0),
+        });
+        s.add_change("x\r\ny");
+
+        let (_, batches) = s.get_changes_by_row(seq);
+        assert_eq!(
+            batches,
+            vec![
+                (
+                    None,
+                    vec![
+                        Change::CursorPosition {
+                            x: Position::Absolute(0),
+                            y: Position::Absolute(1),
+                        },
+                        Change::Text("ab\r\ncd".to_string()),
+                        Change::CursorPosition {
+                            x: Position::Absolute(0),
+                            y: Position::Absolute(0),
+                        },
+                    ]
+                ),
+                (Some(0), vec![Change::Text("x".to_string())]),
+                (Some(1), vec![Change::Text("y".to_string())]),
+            ]
+        );
+    }
+
+    #[test]
+    fn change_observer() {
+        let observed = Arc::new(std::sync::Mutex::new(vec![]));
+        let mut s = Surface::new(4, 2);
+        {
+            let observed = Arc::clone(&observed);
+            s.set_change_observer(Box::new(move |change: &Change| {
+                observed.lock().unwrap().push(change.clone());
+            }));
+        }
+
+        s.add_change("a");
+        s.add_changes(vec![
+            Change::CursorPosition {
+                x: Position::Absolute(0),
+                y: Position::Absolute(1),
+            },
+            Change::Text("b".to_string()),
+        ]);
+        s.clear_change_observer();
+        s.add_change("c");
+
+        assert_eq!(
+            *observed.lock().unwrap(),
+            vec![
+                Change::Text("a".to_string()),
+                Change::CursorPosition {
+                    x: Position::Absolute(0),
+                    y: Position::Absolute(1),
+                },
+                Change::Text("b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn newline() {
+        let mut s = Surface::new(4, 4);
+        s.add_change("bloo\rwat\n hey\r\nho");
+        assert_eq!(
+            s.screen_chars_to_string(),
             "wato\n\
              \x20\x20\x20\x20\n\
              hey \n\
@@ -1453,6 +2875,173 @@ mod test {
         }
     }
 
+    #[test]
+    fn scroll_region() {
+        let mut s = Surface::new(2, 4);
+        s.add_change("a\r\nb\r\nc\r\nd");
+        assert_eq!(s.scroll_region(), None);
+
+        s.add_change(Change::ScrollRegion(Some(1..3)));
+        assert_eq!(s.scroll_region(), Some(1..3));
+        assert_eq!((s.xpos, s.ypos), (0, 0));
+
+        // Move into the region and scroll it; the first and last
+        // lines must remain untouched
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(0),
+            y: Position::Absolute(2),
+        });
+        s.add_change("\r\ne\r\nf");
+        assert_eq!(s.ypos, 2);
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "a \n\
+             e \n\
+             f \n\
+             d \n"
+        );
+
+        // Text wrapping at the bottom of the region also scrolls it
+        s.add_change("gh");
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "a \n\
+             fg\n\
+             h \n\
+             d \n"
+        );
+
+        // Below the region, the cursor sticks at the bottom of the screen
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(0),
+            y: Position::Absolute(3),
+        });
+        s.add_change("\r\ni");
+        assert_eq!(s.ypos, 3);
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "a \n\
+             fg\n\
+             h \n\
+             i \n"
+        );
+
+        s.add_change(Change::ScrollRegion(None));
+        assert_eq!(s.scroll_region(), None);
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(0),
+            y: Position::Absolute(3),
+        });
+        s.add_change("\r\nj");
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "fg\n\
+             h \n\
+             i \n\
+             j \n"
+        );
+    }
+
+    #[test]
+    fn scroll_region_resize() {
+        let mut s = Surface::new(2, 4);
+        s.add_change(Change::ScrollRegion(Some(1..10)));
+        assert_eq!(s.scroll_region(), Some(1..4));
+
+        s.resize(2, 3);
+        assert_eq!(s.scroll_region(), Some(1..3));
+
+        s.resize(2, 1);
+        assert_eq!(s.scroll_region(), None);
+    }
+
+    #[test]
+    fn diff_against_text() {
+        let mut s = Surface::new(4, 3);
+        s.add_change("w00t");
+        s.add_change("foo");
+        s.add_change("baar");
+        s.add_change("baz");
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "foob\n\
+             aarb\n\
+             az  \n"
+        );
+
+        let changes = s.diff_against_text(&["hello".to_string(), "ab".to_string()]);
+        s.add_changes(changes);
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "hell\n\
+             ab  \n\
+             \x20\x20\x20\x20\n"
+        );
+
+        // There is nothing left to change
+        assert_eq!(
+            s.diff_against_text(&["hell".to_string(), "ab".to_string()]),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn set_line() {
+        let mut s = Surface::new(4, 3);
+        s.add_change("aaaabbbbcccc");
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(2),
+            y: Position::Absolute(0),
+        });
+        let seq = s.current_seqno();
+
+        let line = Line::from_text("bXb", &CellAttributes::default(), SEQ_ZERO, None);
+        s.set_line(1, line);
+        assert_eq!(
+            s.screen_chars_to_string(),
+            "aaaa\n\
+             bXb \n\
+             cccc\n"
+        );
+        assert_eq!(s.cursor_position(), (2, 0));
+
+        // Only the modified row is marked as changed
+        let changed: Vec<bool> = s
+            .screen_lines()
+            .iter()
+            .map(|line| line.changed_since(seq))
+            .collect();
+        assert_eq!(changed, vec![false, true, false]);
+
+        // and the change log only touches that row before moving the
+        // cursor back to where it was
+        let (_, changes) = s.get_changes(seq);
+        let (changes, restore) = changes.split_at(changes.len() - 2);
+        assert!(!changes.is_empty());
+        assert_eq!(
+            restore[0],
+            Change::CursorPosition {
+                x: Position::Absolute(2),
+                y: Position::Absolute(0),
+            }
+        );
+        for change in changes.iter() {
+            match change {
+                Change::CursorPosition { y, .. } => assert_eq!(*y, Position::Absolute(1)),
+                Change::Text(_) | Change::AllAttributes(_) | Change::Attribute(_) => {}
+                change => panic!("unexpected change {:?}", change),
+            }
+        }
+
+        // Replacing a row with identical content, or one that is out
+        // of range, changes nothing
+        let seq = s.current_seqno();
+        let line = Line::from_text("bXb", &CellAttributes::default(), SEQ_ZERO, None);
+        assert_eq!(s.set_line(1, line.clone()), seq);
+        assert_eq!(s.set_line(3, line), seq);
+        assert_eq!(s.current_seqno(), seq);
+    }
+
     #[test]
     fn draw_screens() {
         let mut s = Surface::new(4, 4);
@@ -1566,6 +3155,233 @@ mod test {
         );
     }
 
+    #[test]
+    fn iter_cells() {
+        let mut s = Surface::new(4, 2);
+        s.add_change("a🤷b\r\ncd");
+
+        let cells: Vec<(usize, usize, String)> = s
+            .iter_cells()
+            .map(|(x, y, cell)| (x, y, cell.str().to_string()))
+            .collect();
+        assert_eq!(cells.len(), 7);
+        assert_eq!(
+            cells
+                .iter()
+                .filter(|(_, _, text)| text == "🤷")
+                .collect::<Vec<_>>(),
+            vec![&(1, 0, "🤷".to_string())]
+        );
+        assert_eq!(cells[2], (3, 0, "b".to_string()));
+        assert_eq!(cells[3], (0, 1, "c".to_string()));
+        assert_eq!(cells[6], (3, 1, " ".to_string()));
+    }
+
+    #[test]
+    fn from_lines() {
+        let attrs = CellAttributes::default();
+        let s = Surface::from_lines(
+            vec![
+                Line::from_text("hello", &attrs, SEQ_ZERO, None),
+                Line::from_text("hi", &attrs, SEQ_ZERO, None),
+            ],
+            4,
+        );
+        assert_eq!(s.dimensions(), (4, 2));
+        assert_eq!(s.cursor_position(), (0, 0));
+        assert_eq!(s.screen_chars_to_string(), "hell\nhi  \n");
+    }
+
+    #[test]
+    fn add_change_if_effective() {
+        let mut s = Surface::new(4, 2);
+        s.add_change("ab");
+        let (seq, _) = s.get_changes(0);
+
+        // Moving the cursor to where it already is, or clearing a
+        // region that is already clear, does not alter the surface
+        assert_eq!(
+            s.add_change_if_effective(Change::CursorPosition {
+                x: Position::Absolute(2),
+                y: Position::Absolute(0),
+            }),
+            None
+        );
+        assert_eq!(
+            s.add_change_if_effective(Change::ClearToEndOfLine(Default::default())),
+            None
+        );
+        let (noop_seq, changes) = s.get_changes(seq);
+        assert_eq!(noop_seq, seq);
+        assert!(changes.is_empty());
+
+        // Moving the cursor somewhere new is effective
+        assert!(s
+            .add_change_if_effective(Change::CursorPosition {
+                x: Position::Absolute(0),
+                y: Position::Absolute(0),
+            })
+            .is_some());
+
+        // Printing different text is effective
+        assert!(s.add_change_if_effective("xb").is_some());
+        assert_eq!(s.screen_chars_to_string(), "xb  \n    \n");
+        let (_, changes) = s.get_changes(seq);
+        assert_eq!(changes.len(), 2);
+    }
+
+    #[test]
+    fn add_change_if_effective_observer() {
+        let observed = Arc::new(std::sync::Mutex::new(vec![]));
+        let mut s = Surface::new(4, 2);
+        {
+            let observed = Arc::clone(&observed);
+            s.set_change_observer(Box::new(move |change: &Change| {
+                observed.lock().unwrap().push(change.clone());
+            }));
+        }
+
+        // A change that is rolled back is never reported
+        assert_eq!(
+            s.add_change_if_effective(Change::CursorPosition {
+                x: Position::Absolute(0),
+                y: Position::Absolute(0),
+            }),
+            None
+        );
+        assert!(observed.lock().unwrap().is_empty());
+
+        // A change that is kept is reported exactly once
+        assert!(s.add_change_if_effective("a").is_some());
+        assert_eq!(
+            *observed.lock().unwrap(),
+            vec![Change::Text("a".to_string())]
+        );
+    }
+
+    #[test]
+    fn current_working_dir() {
+        let mut s = Surface::new(4, 2);
+        assert_eq!(s.current_working_dir(), None);
+        s.add_change(Change::CurrentWorkingDirectory(
+            "file://host/tmp".to_string(),
+        ));
+        assert_eq!(s.current_working_dir(), Some("file://host/tmp"));
+
+        // A full repaint re-emits the cwd, so it is carried over to
+        // a surface that the repaint is applied to
+        let (_seq, changes) = s.get_changes(0);
+        assert!(changes.contains(&Change::CurrentWorkingDirectory(
+            "file://host/tmp".to_string()
+        )));
+        let mut copy = Surface::new(4, 2);
+        copy.add_changes(changes.into_owned());
+        assert_eq!(copy.current_working_dir(), Some("file://host/tmp"));
+    }
+
+    #[test]
+    fn blinking_cells() {
+        let mut s = Surface::new(4, 2);
+        s.add_change("a");
+        s.add_change(Change::Attribute(AttributeChange::Blink(Blink::Slow)));
+        s.add_change("b");
+        s.add_change(Change::Attribute(AttributeChange::Blink(Blink::None)));
+        s.add_change("cd\r\nef");
+        s.add_change(Change::Attribute(AttributeChange::Blink(Blink::Rapid)));
+        s.add_change("g");
+
+        assert_eq!(s.blinking_cells(), vec![(1, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn content_bounds() {
+        let mut s = Surface::new(6, 4);
+        assert_eq!(s.content_bounds(), None);
+
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(2),
+            y: Position::Absolute(1),
+        });
+        s.add_change("a");
+        assert_eq!(s.content_bounds(), Some((2, 1, 2, 1)));
+
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(4),
+            y: Position::Absolute(0),
+        });
+        s.add_change("🤷");
+        s.add_change(Change::CursorPosition {
+            x: Position::Absolute(1),
+            y: Position::Absolute(2),
+        });
+        s.add_change("b");
+        assert_eq!(s.content_bounds(), Some((1, 0, 5, 2)));
+    }
+
+    #[test]
+    fn row_used_width() {
+        let mut s = Surface::new(4, 4);
+        s.add_change("abcd\r\na🤷\r\n\x20");
+        s.add_change(Change::Attribute(AttributeChange::Background(
+            AnsiColor::Maroon.into(),
+        )));
+        s.add_change(" ");
+
+        assert_eq!(s.row_used_width(0), 4);
+        assert_eq!(s.row_used_width(1), 3);
+        // A blank with a non-default background counts as used
+        assert_eq!(s.row_used_width(2), 2);
+        assert_eq!(s.row_used_width(3), 0);
+    }
+
+    #[test]
+    fn line_appdata_survives_resize() {
+        let mut s = Surface::new(4, 3);
+        let first = Arc::new(1usize);
+        let last = Arc::new(3usize);
+        s.set_line_appdata(0, Arc::clone(&first));
+        s.set_line_appdata(2, Arc::clone(&last));
+
+        let appdata = |s: &Surface, y| {
+            s.get_line_appdata(y)
+                .and_then(|data| data.downcast_ref::<usize>().copied())
+        };
+        assert_eq!(appdata(&s, 0), Some(1));
+        assert_eq!(appdata(&s, 1), None);
+
+        // Changing the width keeps every row
+        s.resize(2, 3);
+        assert_eq!(appdata(&s, 0), Some(1));
+        assert_eq!(appdata(&s, 2), Some(3));
+
+        // Truncating the height drops the data along with the row,
+        // and the newly added row has none
+        s.resize(2, 2);
+        s.resize(2, 3);
+        assert_eq!(appdata(&s, 0), Some(1));
+        assert_eq!(appdata(&s, 2), None);
+
+        // Only a weak reference is held
+        drop(first);
+        assert_eq!(appdata(&s, 0), None);
+    }
+
+    #[test]
+    fn extract_region() {
+        let mut s = Surface::new(4, 3);
+        s.add_change("abcd\r\nefgh\r\nijkl");
+
+        let region = s.extract_region(1, 1, 2, 2);
+        assert_eq!(region.dimensions(), (2, 2));
+        assert_eq!(region.cursor_position(), (0, 0));
+        assert_eq!(region.screen_chars_to_string(), "fg\njk\n");
+
+        // The region is clamped to the bounds of the source
+        let region = s.extract_region(3, 2, 5, 5);
+        assert_eq!(region.dimensions(), (1, 1));
+        assert_eq!(region.screen_chars_to_string(), "l\n");
+    }
+
     #[test]
     fn double_width() {
         let mut s = Surface::new(4, 1);
@@ -1766,4 +3582,96 @@ mod test {
             ),]]
         );
     }
+
+    #[test]
+    fn content_hash() {
+        let mut a = Surface::new(4, 2);
+        a.add_change("hello");
+
+        // Same content, different history
+        let mut b = Surface::new(4, 2);
+        b.add_change("xyz");
+        b.add_change(Change::ClearScreen(Default::default()));
+        b.add_change("hel");
+        b.add_change("lo");
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        b.add_change(Change::CursorPosition {
+            x: Position::Absolute(0),
+            y: Position::Absolute(1),
+        });
+        b.add_change("O");
+        assert_ne!(a.content_hash(), b.content_hash());
+
+        let mut c = Surface::new(4, 2);
+        c.add_change("hello");
+        c.add_change(Change::CursorPosition {
+            x: Position::Absolute(0),
+            y: Position::Absolute(0),
+        });
+        c.add_change(Change::Attribute(AttributeChange::Intensity(
+            Intensity::Bold,
+        )));
+        c.add_change("h");
+        assert_eq!(a.screen_chars_to_string(), c.screen_chars_to_string());
+        assert_ne!(a.content_hash(), c.content_hash());
+    }
+
+    #[test]
+    fn apply_ansi() {
+        let mut s = Surface::new(5, 3);
+        s.apply_ansi(b"\x1b[31mred\x1b[0m!");
+
+        let red = CellAttributes::default()
+            .set_foreground(AnsiColor::Maroon)
+            .clone();
+        assert_eq!(
+            s.screen_cells()[0],
+            [
+                Cell::new('r', red.clone()),
+                Cell::new('e', red.clone()),
+                Cell::new('d', red.clone()),
+                Cell::new('!', CellAttributes::default()),
+                Cell::default(),
+            ]
+        );
+
+        // A sequence split across calls is reassembled
+        s.apply_ansi(b"\x1b[2;");
+        s.apply_ansi(b"2Hx\x1b[1");
+        s.apply_ansi(b"mb\r\ny");
+        assert_eq!(s.screen_chars_to_string(), "red! \n xb  \ny    \n");
+        assert_eq!(s.cursor_position(), (1, 2));
+
+        // Erasing to the end of the line prunes the trailing blanks
+        s.apply_ansi(b"\x1b[1;3H\x1b[K");
+        assert_eq!(s.screen_chars_to_string(), "re\n xb  \ny    \n");
+        assert_eq!(s.cursor_position(), (2, 0));
+    }
+
+    #[test]
+    fn set_title() {
+        let mut s = Surface::new(4, 1);
+        s.add_change("x");
+        let (seq, _) = s.get_changes(0);
+        s.set_title("hello");
+        assert_eq!(s.title(), "hello");
+
+        s.set_max_title_len(3);
+        s.set_title("h\u{e9}llo");
+        assert_eq!(s.title(), "h\u{e9}l");
+
+        let (_seq, changes) = s.get_changes(seq);
+        assert_eq!(
+            changes.as_ref(),
+            &[
+                Change::Title("hello".to_string()),
+                Change::Title("h\u{e9}l".to_string())
+            ]
+        );
+
+        // Titles from escape sequences are also limited
+        s.apply_ansi(b"\x1b]2;abcdef\x1b\\");
+        assert_eq!(s.title(), "abc");
+    }
 }
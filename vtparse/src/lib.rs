@@ -346,6 +346,8 @@ pub struct VTParser {
 
     utf8_parser: Utf8Parser,
     utf8_return_state: State,
+
+    treat_c1_as_printable: bool,
 }
 
 /// Represents a parameter to a CSI-based escaped sequence.
@@ -429,15 +431,58 @@ impl VTParser {
 
             utf8_parser: Utf8Parser::new(),
             apc_data: vec![],
+
+            treat_c1_as_printable: false,
         }
     }
 
+    /// When enabled, C1 control codes (0x80-0x9f) that are encountered
+    /// in the ground state, whether as raw 8-bit bytes or encoded as
+    /// UTF-8, are passed to `VTActor::print` as the corresponding
+    /// unicode codepoint rather than being interpreted as controls.
+    /// This is useful when processing legacy data that uses those
+    /// bytes for printable characters.
+    /// This is disabled by default.
+    pub fn set_treat_c1_as_printable(&mut self, enable: bool) {
+        self.treat_c1_as_printable = enable;
+    }
+
+    fn is_printable_c1(&self, state: State, c: u32) -> bool {
+        self.treat_c1_as_printable && state == State::Ground && (0x80..=0x9f).contains(&c)
+    }
+
     /// Returns if the state machine is in the ground state,
     /// i.e. there is no pending state held by the state machine.
     pub fn is_ground(&self) -> bool {
         self.state == State::Ground
     }
 
+    /// Returns the name of the current state of the state machine.
+    /// This is intended for diagnostic purposes, such as figuring out
+    /// why output appears to have stopped, and the names are not
+    /// guaranteed to remain stable.
+    pub fn state_name(&self) -> &'static str {
+        match self.state {
+            State::Ground => "ground",
+            State::Escape => "escape",
+            State::EscapeIntermediate => "escape_intermediate",
+            State::CsiEntry => "csi_entry",
+            State::CsiParam => "csi_param",
+            State::CsiIntermediate => "csi_intermediate",
+            State::CsiIgnore => "csi_ignore",
+            State::DcsEntry => "dcs_entry",
+            State::DcsParam => "dcs_param",
+            State::DcsIntermediate => "dcs_intermediate",
+            State::DcsPassthrough => "dcs_passthrough",
+            State::DcsIgnore => "dcs_ignore",
+            State::OscString => "osc_string",
+            State::SosPmString => "sos_pm_string",
+            State::ApcString => "apc_string",
+            State::Anywhere => "anywhere",
+            State::Utf8Sequence => "utf8_sequence",
+        }
+    }
+
     fn as_integer_params(&self) -> [i64; MAX_PARAMS] {
         let mut res = [0i64; MAX_PARAMS];
         let mut i = 0;
@@ -637,7 +682,7 @@ impl VTParser {
             // value would cause a state transition, then we process
             // that state transition rather than performing the default
             // string accumulation.
-            if c as u32 <= 0xff {
+            if c as u32 <= 0xff && !self.is_printable_c1(self.utf8_return_state, c as u32) {
                 let byte = ((c as u32) & 0xff) as u8;
 
                 let (action, state) = lookup(self.utf8_return_state, byte);
@@ -675,6 +720,11 @@ impl VTParser {
             return;
         }
 
+        if self.is_printable_c1(self.state, byte as u32) {
+            actor.print(byte as char);
+            return;
+        }
+
         let (action, state) = lookup(self.state, byte);
 
         if state != self.state {
@@ -742,6 +792,30 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_c1_as_printable() {
+        let mut parser = VTParser::new();
+        parser.set_treat_c1_as_printable(true);
+        let mut actor = CollectingVTActor::default();
+        parser.parse(b"a\x85\x9b1m\xc2\x9b\x1b[1m", &mut actor);
+        assert_eq!(
+            actor.into_vec(),
+            vec![
+                VTAction::Print('a'),
+                VTAction::Print('\u{85}'),
+                VTAction::Print('\u{9b}'),
+                VTAction::Print('1'),
+                VTAction::Print('m'),
+                VTAction::Print('\u{9b}'),
+                VTAction::CsiDispatch {
+                    params: vec![CsiParam::Integer(1)],
+                    parameters_truncated: false,
+                    byte: b'm',
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_print() {
         assert_eq!(
@@ -1115,6 +1115,19 @@ impl Client {
         }
     }
 
+    /// A client that isn't connected to anything; every request fails
+    #[cfg(test)]
+    pub(crate) fn new_disconnected() -> Self {
+        let (sender, _receiver) = unbounded();
+        Self {
+            sender,
+            local_domain_id: None,
+            is_reconnectable: false,
+            is_local: false,
+            client_id: ClientId::new(),
+        }
+    }
+
     pub async fn verify_version_compat(
         &self,
         ui: &ConnectionUI,
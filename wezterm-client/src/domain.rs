@@ -6,7 +6,7 @@ use codec::{ListPanesResponse, SpawnV2, SplitPane};
 use config::keyassignment::SpawnTabDomain;
 use config::{SshDomain, TlsDomainClient, UnixDomain};
 use mux::connui::{ConnectionUI, ConnectionUIParams};
-use mux::domain::{alloc_domain_id, Domain, DomainId, DomainState, SplitSource};
+use mux::domain::{alloc_domain_id, ConnectionStatus, Domain, DomainId, DomainState, SplitSource};
 use mux::pane::{Pane, PaneId};
 use mux::tab::{SplitRequest, Tab, TabId};
 use mux::window::WindowId;
@@ -15,6 +15,7 @@ use portable_pty::CommandBuilder;
 use promise::spawn::spawn_into_new_thread;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use wezterm_term::TerminalSize;
 
 pub struct ClientInner {
@@ -229,6 +230,25 @@ impl ClientDomainConfig {
             ClientDomainConfig::Ssh(ssh) => ssh.connect_automatically,
         }
     }
+
+    /// Returns the number of retries and the initial delay between
+    /// them to use when connecting automatically at startup
+    pub fn connect_automatically_retries(&self) -> (usize, Duration) {
+        match self {
+            ClientDomainConfig::Unix(unix) => (
+                unix.connect_automatically_retries,
+                unix.connect_automatically_retry_delay,
+            ),
+            ClientDomainConfig::Tls(tls) => (
+                tls.connect_automatically_retries,
+                tls.connect_automatically_retry_delay,
+            ),
+            ClientDomainConfig::Ssh(ssh) => (
+                ssh.connect_automatically_retries,
+                ssh.connect_automatically_retry_delay,
+            ),
+        }
+    }
 }
 
 impl ClientInner {
@@ -262,6 +282,8 @@ pub struct ClientDomain {
     label: String,
     inner: Mutex<Option<Arc<ClientInner>>>,
     local_domain_id: DomainId,
+    /// The outcome of the most recent call to `attach`
+    status: Mutex<ConnectionStatus>,
 }
 
 async fn update_remote_workspace(
@@ -412,6 +434,7 @@ impl ClientDomain {
             label,
             inner: Mutex::new(None),
             local_domain_id,
+            status: Mutex::new(ConnectionStatus::Disconnected),
         }
     }
 
@@ -419,14 +442,28 @@ impl ClientDomain {
         self.inner.lock().unwrap().as_ref().map(Arc::clone)
     }
 
-    pub fn connect_automatically(&self) -> bool {
-        self.config.connect_automatically()
+    fn set_connection_status(&self, status: ConnectionStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+
+    pub fn connect_automatically_retries(&self) -> (usize, Duration) {
+        self.config.connect_automatically_retries()
     }
 
     pub fn perform_detach(&self) {
         log::info!("detached domain {}", self.local_domain_id);
-        self.inner.lock().unwrap().take();
+        let inner = self.inner.lock().unwrap().take();
+        self.set_connection_status(ConnectionStatus::Disconnected);
         let mux = Mux::get();
+        if let Some(inner) = inner {
+            // Remember where each pane was scrolled to, so that
+            // the viewport can be restored when we reattach
+            for (&remote_pane_id, &local_pane_id) in
+                inner.remote_to_local_pane.lock().unwrap().iter()
+            {
+                mux.stash_pane_viewport(self.local_domain_id, remote_pane_id, local_pane_id);
+            }
+        }
         mux.domain_was_detached(self.local_domain_id);
     }
 
@@ -543,6 +580,7 @@ impl ClientDomain {
             .keys()
             .copied()
             .collect();
+        let mut remote_panes_present = HashSet::new();
 
         for (tabroot, tab_title) in panes.tabs.into_iter().zip(panes.tab_titles.iter()) {
             let root_size = match tabroot.root_size() {
@@ -587,6 +625,7 @@ impl ClientDomain {
                 tab.sync_with_pane_tree(root_size, tabroot, |entry| {
                     workspace.replace(entry.workspace.clone());
                     remote_panes_to_forget.remove(&entry.pane_id);
+                    remote_panes_present.insert(entry.pane_id);
                     if let Some(pane_id) = inner.remote_to_local_pane_id(entry.pane_id) {
                         match mux.get_pane(pane_id) {
                             Some(pane) => pane,
@@ -621,6 +660,11 @@ impl ClientDomain {
                             pane.pane_id()
                         );
                         mux.add_pane(&pane).expect("failed to add pane to mux");
+                        mux.restore_pane_viewport(
+                            inner.local_domain_id,
+                            entry.pane_id,
+                            pane.pane_id(),
+                        );
                         pane
                     }
                 });
@@ -676,6 +720,10 @@ impl ClientDomain {
             }
         }
 
+        // Viewports stashed for panes that went away while we were
+        // detached can never be restored
+        mux.prune_detached_viewports(inner.local_domain_id, &remote_panes_present);
+
         for (remote_window_id, window_title) in panes.window_titles {
             if let Some(local_window_id) = inner.remote_to_local_window(remote_window_id) {
                 let mut window = mux
@@ -941,16 +989,33 @@ impl Domain for ClientDomain {
             return Ok(());
         }
 
-        let domain_id = self.local_domain_id;
-        let config = self.config.clone();
-
-        let activity = mux::activity::Activity::new();
         let ui = ConnectionUI::with_params(ConnectionUIParams {
             window_id,
             ..Default::default()
         });
         ui.title("wezterm: Connecting...");
 
+        self.attach_with_ui(window_id, &ui).await?;
+        ui.close();
+        Ok(())
+    }
+
+    async fn attach_with_ui(
+        &self,
+        window_id: Option<WindowId>,
+        ui: &ConnectionUI,
+    ) -> anyhow::Result<()> {
+        if self.state() == DomainState::Attached {
+            // Already attached
+            return Ok(());
+        }
+
+        let domain_id = self.local_domain_id;
+        let config = self.config.clone();
+        self.set_connection_status(ConnectionStatus::Connecting);
+
+        let activity = mux::activity::Activity::new();
+
         ui.async_run_and_log_error({
             let ui = ui.clone();
             async move {
@@ -987,12 +1052,13 @@ impl Domain for ClientDomain {
         .await
         .map_err(|e| {
             ui.output_str(&format!("Error during attach: {:#}\n", e));
+            self.set_connection_status(ConnectionStatus::Failed(format!("{:#}", e)));
             e
         })?;
 
+        self.set_connection_status(ConnectionStatus::Connected);
         ui.output_str("Attached!\n");
         drop(activity);
-        ui.close();
         Ok(())
     }
 
@@ -1012,4 +1078,165 @@ impl Domain for ClientDomain {
             DomainState::Detached
         }
     }
+
+    fn connect_automatically(&self) -> bool {
+        self.config.connect_automatically()
+    }
+
+    fn connection_status(&self) -> ConnectionStatus {
+        match (self.state(), self.status.lock().unwrap().clone()) {
+            (DomainState::Attached, _) => ConnectionStatus::Connected,
+            (DomainState::Detached, ConnectionStatus::Connected) => ConnectionStatus::Disconnected,
+            (DomainState::Detached, status) => status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mux::renderable::StableCursorPosition;
+    use mux::tab::{PaneEntry, PaneNode};
+
+    /// The mux and the executor are process-wide, so tests that
+    /// install them must not run concurrently
+    static MUX_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Builds a pane list with each of the remote panes in a tab of its own
+    fn pane_list(remote_pane_ids: &[PaneId]) -> ListPanesResponse {
+        let tabs: Vec<PaneNode> = remote_pane_ids
+            .iter()
+            .map(|&pane_id| {
+                PaneNode::Leaf(PaneEntry {
+                    window_id: 0,
+                    tab_id: pane_id,
+                    pane_id,
+                    title: String::new(),
+                    size: TerminalSize::default(),
+                    working_dir: None,
+                    is_active_pane: true,
+                    is_zoomed_pane: false,
+                    workspace: mux::DEFAULT_WORKSPACE.to_string(),
+                    cursor_pos: StableCursorPosition::default(),
+                    physical_top: 0,
+                    top_row: 0,
+                    left_col: 0,
+                    tty_name: None,
+                })
+            })
+            .collect();
+        ListPanesResponse {
+            tab_titles: vec![String::new(); tabs.len()],
+            tabs,
+            window_titles: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn pane_viewport_survives_detach() {
+        let _guard = MUX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        config::use_test_configuration();
+        // Queues up the requests that panes make of the server; they
+        // are never run
+        let _executor = promise::spawn::SimpleExecutor::new();
+        let mux = Arc::new(Mux::new(None));
+        Mux::set_mux(&mux);
+
+        let domain = Arc::new(ClientDomain::new(ClientDomainConfig::Unix(
+            UnixDomain::default(),
+        )));
+        mux.add_domain(&(Arc::clone(&domain) as Arc<dyn Domain>));
+
+        let attach = |remote_pane_ids: &[PaneId]| {
+            let inner = Arc::new(ClientInner::new(
+                domain.local_domain_id,
+                Client::new_disconnected(),
+                None,
+                false,
+            ));
+            *domain.inner.lock().unwrap() = Some(Arc::clone(&inner));
+            ClientDomain::process_pane_list(Arc::clone(&inner), pane_list(remote_pane_ids), None)
+                .unwrap();
+            inner
+        };
+        let local = |inner: &Arc<ClientInner>, remote_pane_id| {
+            inner.remote_to_local_pane_id(remote_pane_id).unwrap()
+        };
+
+        // Remote pane 7 is scrolled back, 8 is at the bottom and 9 is
+        // scrolled back but will be closed while we are detached
+        let inner = attach(&[7, 8, 9]);
+        let old_pane_id = local(&inner, 7);
+        mux.set_pane_viewport(old_pane_id, Some(-20));
+        mux.set_pane_viewport(local(&inner, 9), Some(-5));
+        domain.perform_detach();
+        assert_eq!(mux.get_pane_viewport(old_pane_id), None);
+
+        // On reattach, the remote panes are assigned new local ids
+        let inner = attach(&[7, 8]);
+        let new_pane_id = local(&inner, 7);
+        assert_ne!(new_pane_id, old_pane_id);
+        assert_eq!(mux.get_pane_viewport(new_pane_id), Some(-20));
+        assert_eq!(mux.get_pane_viewport(local(&inner, 8)), None);
+
+        // The viewport of pane 9 was dropped when it was found to be
+        // missing, so it is not restored should that id show up again
+        mux.set_pane_viewport(new_pane_id, None);
+        domain.perform_detach();
+        let inner = attach(&[7, 9]);
+        assert_eq!(mux.get_pane_viewport(local(&inner, 7)), None);
+        assert_eq!(mux.get_pane_viewport(local(&inner, 9)), None);
+
+        Mux::shutdown();
+    }
+
+    #[test]
+    fn connection_status_transitions() {
+        let _guard = MUX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        config::use_test_configuration();
+        let _executor = promise::spawn::SimpleExecutor::new();
+        let mux = Arc::new(Mux::new(None));
+        Mux::set_mux(&mux);
+
+        // Nothing is listening on this socket, and we must not try
+        // to start a server for it
+        let domain = ClientDomain::new(ClientDomainConfig::Unix(UnixDomain {
+            socket_path: Some(
+                std::env::temp_dir()
+                    .join(format!("wezterm-client-test-{}.sock", std::process::id())),
+            ),
+            no_serve_automatically: true,
+            ..UnixDomain::default()
+        }));
+        assert_eq!(domain.connection_status(), ConnectionStatus::Disconnected);
+
+        // The connection is made on another thread, so the first poll
+        // leaves the attach pending
+        let ui = ConnectionUI::new_headless();
+        let mut attach = Box::pin(domain.attach_with_ui(None, &ui));
+        assert!(smol::block_on(smol::future::poll_once(&mut attach)).is_none());
+        assert_eq!(domain.connection_status(), ConnectionStatus::Connecting);
+
+        let err = smol::block_on(attach).unwrap_err();
+        assert_eq!(
+            domain.connection_status(),
+            ConnectionStatus::Failed(format!("{:#}", err))
+        );
+        assert_eq!(domain.state(), DomainState::Detached);
+
+        // Stand in for a successful attach
+        *domain.inner.lock().unwrap() = Some(Arc::new(ClientInner::new(
+            domain.local_domain_id,
+            Client::new_disconnected(),
+            None,
+            false,
+        )));
+        assert_eq!(domain.connection_status(), ConnectionStatus::Connected);
+
+        domain.perform_detach();
+        assert_eq!(domain.connection_status(), ConnectionStatus::Disconnected);
+
+        ui.close();
+        Mux::shutdown();
+    }
 }
@@ -261,4 +261,8 @@ pub struct ShowKeysCommand {
     /// In lua mode, show only the named key table
     #[arg(long)]
     pub key_table: Option<String>,
+    /// Output the key assignments as a JSON array, with the table
+    /// name, key, modifiers and a description of each assignment
+    #[arg(long, conflicts_with = "lua")]
+    pub json: bool,
 }
@@ -775,6 +775,20 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &["Edit"],
             icon: Some("cod_clear_all"),
         },
+        ClearScreenAndScrollback { send_redraw } => CommandDef {
+            brief: "Clear the scrollback and viewport".into(),
+            doc: if *send_redraw {
+                "Removes all content from the screen and scrollback, \
+                 then sends CTRL-L so that the shell redraws its prompt"
+                    .into()
+            } else {
+                "Removes all content from the screen and scrollback".into()
+            },
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &[],
+            icon: Some("cod_clear_all"),
+        },
         Search(Pattern::CurrentSelectionOrEmptyString) => CommandDef {
             brief: "Search pane output".into(),
             doc: "Enters the search mode UI for the current pane".into(),
@@ -799,6 +813,24 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &["Help"],
             icon: Some("cod_debug"),
         },
+        DumpPane { scrollback, format } => CommandDef {
+            brief: format!(
+                "Dump the {} of the pane as {}",
+                if *scrollback { "scrollback" } else { "viewport" },
+                match format {
+                    DumpFormat::Text => "text",
+                    DumpFormat::Ansi => "ANSI",
+                }
+            )
+            .into(),
+            doc: "Writes the content of the current pane to a file \
+                  in the runtime directory"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &[],
+            icon: None,
+        },
         InputSelector(_) => CommandDef {
             brief: "Prompt the user to choose from a list".into(),
             doc: "Activates the selector overlay and wait for input".into(),
@@ -815,6 +847,14 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &[],
             icon: None,
         },
+        PromptInputSelector(_) => CommandDef {
+            brief: "Prompt the user to choose a line of text from a list".into(),
+            doc: "Activates the filterable prompt selector overlay and wait for input".into(),
+            keys: vec![],
+            args: &[ArgType::ActiveWindow],
+            menubar: &[],
+            icon: None,
+        },
         QuickSelect => CommandDef {
             brief: "Enter QuickSelect mode".into(),
             doc: "Activates the quick selection UI for the current pane".into(),
@@ -1228,6 +1268,26 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
                 icon: None,
             }
         }
+        ActivateTabMostRecentlyUsed(n) => {
+            let (direction, amount) = if *n < 0 {
+                ("least", -n)
+            } else {
+                ("most", *n)
+            };
+            let ordinal = english_ordinal(amount);
+            CommandDef {
+                brief: format!("Activate the {ordinal} {direction} recently used tab").into(),
+                doc: format!(
+                    "Activates the {ordinal} {direction} recently used tab, \
+                     other than the current tab. Wraps around to the other end"
+                )
+                .into(),
+                keys: vec![],
+                args: &[ArgType::ActiveWindow],
+                menubar: &[],
+                icon: None,
+            }
+        }
         ActivateTabRelativeNoWrap(-1) => CommandDef {
             brief: "Activate the tab to the left (no wrapping)".into(),
             doc: "Activates the tab to the left. Stopping at the left-most tab".into(),
@@ -1403,6 +1463,31 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
                 icon: Some("oct_terminal"),
             }
         }
+        ScrollToPromptFiltered { delta, only_errors } => {
+            let (direction, amount) = if *delta < 0 {
+                ("up", -delta)
+            } else {
+                ("down", *delta)
+            };
+            let ordinal = english_ordinal(amount);
+            let kind = if *only_errors {
+                "failed command prompt"
+            } else {
+                "semantic prompt zone"
+            };
+            CommandDef {
+                brief: format!("Scroll {direction} {amount} {kind}(s)").into(),
+                doc: format!(
+                    "Scrolls the viewport {direction} to the \
+                             {ordinal} {kind} in that direction"
+                )
+                .into(),
+                keys: vec![],
+                args: &[ArgType::ActivePane],
+                menubar: &[],
+                icon: Some("oct_terminal"),
+            }
+        }
         ScrollByCurrentEventWheelDelta => CommandDef {
             brief: "Scrolls based on the mouse wheel position \
                 in the current mouse event"
@@ -1591,6 +1676,56 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &["Window"],
             icon: Some("md_fullscreen"),
         },
+        ActivateTabByTitle { substring, .. } => CommandDef {
+            brief: format!("Activate the tab whose title contains `{substring}`").into(),
+            doc: "Activates the first tab whose title matches; \
+                  has no effect if no tab matches"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActiveWindow],
+            menubar: &[],
+            icon: None,
+        },
+        SetTabTitle(Some(title)) => CommandDef {
+            brief: format!("Set the tab title to `{title}`").into(),
+            doc: "Sets the title of the current tab, overriding the title \
+                  set by the application"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActiveTab],
+            menubar: &[],
+            icon: None,
+        },
+        SetTabTitle(None) => CommandDef {
+            brief: "Clear the tab title".into(),
+            doc: "Removes the title set for the current tab, restoring \
+                  the title set by the application"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActiveTab],
+            menubar: &[],
+            icon: None,
+        },
+        SetWindowTitle(Some(title)) => CommandDef {
+            brief: format!("Set the window title to `{title}`").into(),
+            doc: "Sets the title of the current window, overriding the title \
+                  set by the application"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActiveWindow],
+            menubar: &[],
+            icon: None,
+        },
+        SetWindowTitle(None) => CommandDef {
+            brief: "Clear the window title".into(),
+            doc: "Removes the title set for the current window, restoring \
+                  the title set by the application"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActiveWindow],
+            menubar: &[],
+            icon: None,
+        },
         ActivateLastTab => CommandDef {
             brief: "Activate the last active tab".into(),
             doc: "If there was no prior active tab, has no effect.".into(),
@@ -1729,6 +1864,22 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &[],
             icon: Some("md_keyboard_variant"),
         },
+        SendComposedKey { key, mods } => CommandDef {
+            brief: format!(
+                "Sends {key:?} with {mods:?} to the active pane, \
+                           as though you typed it"
+            )
+            .into(),
+            doc: format!(
+                "Sends {key:?} with {mods:?} to the active pane, \
+                         encoded for its active keyboard protocol"
+            )
+            .into(),
+            keys: vec![],
+            args: &[],
+            menubar: &[],
+            icon: Some("md_keyboard_variant"),
+        },
         Nop => CommandDef {
             brief: "Does nothing".into(),
             doc: "Has no effect".into(),
@@ -1973,6 +2124,17 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
                 RotationDirection::CounterClockwise => "md_rotate_left",
             }),
         },
+        RotateTabs(direction) => CommandDef {
+            brief: format!("Rotate tabs {direction:?}").into(),
+            doc: format!("Rotate tabs {direction:?}").into(),
+            keys: vec![],
+            args: &[ArgType::ActiveWindow],
+            menubar: &["Window", "Rotate Tab"],
+            icon: Some(match direction {
+                RotationDirection::Clockwise => "md_rotate_right",
+                RotationDirection::CounterClockwise => "md_rotate_left",
+            }),
+        },
         SplitPane(split) => {
             let direction = split.direction;
             CommandDef {
@@ -2092,6 +2254,8 @@ fn compute_default_actions() -> Vec<KeyAssignment> {
         }),
         RotatePanes(RotationDirection::Clockwise),
         RotatePanes(RotationDirection::CounterClockwise),
+        RotateTabs(RotationDirection::Clockwise),
+        RotateTabs(RotationDirection::CounterClockwise),
         ActivateTab(0),
         ActivateTab(1),
         ActivateTab(2),
@@ -14,7 +14,8 @@ use std::cell::RefCell;
 use std::collections::{BTreeMap, HashSet};
 use std::rc::Rc;
 use std::sync::Arc;
-use wezterm_term::{Alert, ClipboardSelection};
+use wezterm_term::config::Osc52ClipboardPolicy;
+use wezterm_term::{Alert, ClipboardSelection, TerminalConfiguration};
 use wezterm_toast_notification::*;
 
 pub struct GuiFrontEnd {
@@ -89,8 +90,13 @@ impl GuiFrontEnd {
                 MuxNotification::TabTitleChanged { .. } => {}
                 MuxNotification::WindowTitleChanged { .. } => {}
                 MuxNotification::TabResized(_) => {}
+                MuxNotification::TabRemoved(tab_id) => {
+                    crate::overlay::copy::forget_saved_pattern(tab_id);
+                }
                 MuxNotification::TabAddedToWindow { .. } => {}
-                MuxNotification::PaneRemoved(_) => {}
+                MuxNotification::PaneRemoved(pane_id) => {
+                    crate::termwindow::pane_title::forget_pane_title(pane_id);
+                }
                 MuxNotification::WindowInvalidated(_) => {}
                 MuxNotification::PaneOutput(_) => {}
                 MuxNotification::PaneAdded(_) => {}
@@ -147,7 +153,9 @@ impl GuiFrontEnd {
                         | Alert::WindowTitleChanged(_)
                         | Alert::TabTitleChanged(_)
                         | Alert::IconTitleChanged(_)
-                        | Alert::SetUserVar { .. },
+                        | Alert::SetUserVar { .. }
+                        | Alert::StealFocus
+                        | Alert::SynchronizedOutputChanged(_),
                 } => {}
                 MuxNotification::Empty => {
                     if config::configuration().quit_when_all_windows_are_closed {
@@ -184,16 +192,59 @@ impl GuiFrontEnd {
                             selection,
                             clipboard
                         );
-                        if let Some(window) = fe.known_windows.borrow().keys().next() {
-                            window.set_clipboard(
-                                match selection {
-                                    ClipboardSelection::Clipboard => Clipboard::Clipboard,
-                                    ClipboardSelection::PrimarySelection => {
-                                        Clipboard::PrimarySelection
+                        let selection = match selection {
+                            ClipboardSelection::Clipboard => Clipboard::Clipboard,
+                            ClipboardSelection::PrimarySelection => Clipboard::PrimarySelection,
+                        };
+                        let clipboard = match clipboard {
+                            Some(text) => {
+                                match crate::termwindow::clipboard::filter_osc52_clipboard_write(
+                                    pane_id, text,
+                                ) {
+                                    Some(text) => Some(text),
+                                    None => return,
+                                }
+                            }
+                            None => None,
+                        };
+
+                        if let Some(text) = clipboard.as_ref() {
+                            // Panes may carry their own configuration, so ask
+                            // the pane rather than the global config
+                            let policy = Mux::get()
+                                .get_pane(pane_id)
+                                .and_then(|pane| pane.get_config())
+                                .map(|config| config.osc52_clipboard_policy())
+                                .unwrap_or_else(|| {
+                                    config::TermConfig::new().osc52_clipboard_policy()
+                                });
+                            if policy == Osc52ClipboardPolicy::Prompt {
+                                let gui_win = Mux::get().resolve_pane_id(pane_id).and_then(
+                                    |(_, window_id, _)| fe.gui_window_for_mux_window(window_id),
+                                );
+                                match gui_win {
+                                    Some(gui_win) => {
+                                        let text = text.clone();
+                                        gui_win.window.notify(TermWindowNotif::Apply(Box::new(
+                                            move |term_window| {
+                                                term_window.confirm_osc52_clipboard_write(
+                                                    pane_id, selection, text,
+                                                );
+                                            },
+                                        )));
                                     }
-                                },
-                                clipboard.unwrap_or_else(String::new),
-                            );
+                                    None => log::error!(
+                                        "Cannot confirm clipboard write as pane {} \
+                                         has no window",
+                                        pane_id
+                                    ),
+                                }
+                                return;
+                            }
+                        }
+
+                        if let Some(window) = fe.known_windows.borrow().keys().next() {
+                            window.set_clipboard(selection, clipboard.unwrap_or_else(String::new));
                         } else {
                             log::error!("Cannot assign clipboard as there are no windows");
                         };
@@ -256,6 +307,7 @@ impl GuiFrontEnd {
                             pane_id,
                             workspace,
                             None, // optional position
+                            None, // optional initial input
                         )
                         .await
                     {
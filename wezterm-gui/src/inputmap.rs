@@ -1,15 +1,30 @@
 use crate::commands::CommandDef;
 use config::keyassignment::{
-    ClipboardCopyDestination, ClipboardPasteSource, KeyAssignment, KeyTableEntry, KeyTables,
-    MouseEventTrigger, SelectionMode,
+    ClipboardCopyDestination, ClipboardPasteSource, KeyAssignment, KeyTable, KeyTableEntry,
+    KeyTables, MouseEventTrigger, SelectionMode,
 };
 use config::{ConfigHandle, MouseEventAltScreen, MouseEventTriggerMods};
+use serde::Serialize;
 use std::collections::{BTreeMap, HashMap};
 use std::time::Duration;
 use wezterm_dynamic::{ToDynamic, Value};
 use wezterm_term::input::MouseButton;
 use window::{KeyCode, Modifiers, PhysKeyCode, UIKeyCapRendering};
 
+/// A flattened description of a single key binding, suitable for
+/// rendering a keybinding reference or for serializing to JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyBindingInfo {
+    /// The name of the key table, or "default" for the default table
+    pub table: String,
+    /// The key, using the same names that are used in the config
+    pub key: String,
+    /// The modifiers, using the same syntax that is used in the config
+    pub mods: String,
+    /// A human readable description of the assigned action
+    pub label: String,
+}
+
 pub struct InputMap {
     pub keys: KeyTables,
     pub mouse: HashMap<(MouseEventTrigger, MouseEventTriggerMods), KeyAssignment>,
@@ -375,12 +390,16 @@ impl InputMap {
             mouse.insert((code, mods), v);
         }
 
-        keys.by_name
-            .entry("copy_mode".to_string())
-            .or_insert_with(crate::overlay::copy::copy_key_table);
-        keys.by_name
-            .entry("search_mode".to_string())
-            .or_insert_with(crate::overlay::copy::search_key_table);
+        for (name, defaults) in [
+            ("copy_mode", crate::overlay::copy::copy_key_table()),
+            ("search_mode", crate::overlay::copy::search_key_table()),
+        ] {
+            let table = match keys.by_name.remove(name) {
+                Some(table) if config.disable_default_key_bindings => table,
+                table => merge_default_key_table(table.unwrap_or_default(), defaults),
+            };
+            keys.by_name.insert(name.to_string(), table);
+        }
 
         Self {
             keys,
@@ -463,6 +482,24 @@ impl InputMap {
         self.mouse.get(&(event, mods)).cloned()
     }
 
+    /// Returns the key bindings from the default table followed by
+    /// those of each of the named tables, ordered by table name
+    /// and then by key.
+    pub fn export_tables(&self) -> Vec<KeyBindingInfo> {
+        let mut result = vec![];
+        export_key_table("default", &self.keys.default, &mut result);
+
+        let mut table_names = self.keys.by_name.keys().collect::<Vec<_>>();
+        table_names.sort();
+        for name in table_names {
+            if let Some(table) = self.keys.by_name.get(name) {
+                export_key_table(name, table, &mut result);
+            }
+        }
+
+        result
+    }
+
     pub fn dump_config(&self, key_table: Option<&str>) {
         println!("local wezterm = require 'wezterm'");
         println!("local act = wezterm.action");
@@ -566,6 +603,23 @@ impl InputMap {
     }
 }
 
+/// Adds the entries from `defaults` to the user supplied `table`,
+/// without replacing any that the user has assigned, and then removes
+/// any entries that were assigned to `DisableDefaultAssignment`.
+fn merge_default_key_table(mut table: KeyTable, defaults: KeyTable) -> KeyTable {
+    for ((code, mods), entry) in defaults {
+        // As in InputMap::new, the user supplied keys have been
+        // normalized, so check for the normalized form of the default
+        let (disable_code, disable_mods) = code.normalize_shift(mods);
+        if table.contains_key(&(disable_code, disable_mods)) {
+            continue;
+        }
+        table.entry((code, mods)).or_insert(entry);
+    }
+    table.retain(|_, v| v.action != KeyAssignment::DisableDefaultAssignment);
+    table
+}
+
 fn section_header(title: &str) {
     let dash = "-".repeat(title.len());
     println!("{title}");
@@ -800,6 +854,27 @@ fn show_key_table(table: &config::keyassignment::KeyTable) {
     }
 }
 
+fn export_key_table(
+    name: &str,
+    table: &config::keyassignment::KeyTable,
+    result: &mut Vec<KeyBindingInfo>,
+) {
+    let ordered = table.iter().collect::<BTreeMap<_, _>>();
+    for ((key, mods), entry) in ordered {
+        let action = &entry.action;
+        let label = match crate::commands::derive_command_from_key_assignment(action) {
+            Some(cmd) => cmd.brief.to_string(),
+            None => format!("{action:?}"),
+        };
+        result.push(KeyBindingInfo {
+            table: name.to_string(),
+            key: lua_key_code(key),
+            mods: format!("{mods:?}").replace(" ", ""),
+            label,
+        });
+    }
+}
+
 fn show_key_table_as_lua(table: &config::keyassignment::KeyTable, indent: usize) {
     let ordered = table.iter().collect::<BTreeMap<_, _>>();
 
@@ -809,3 +884,62 @@ fn show_key_table_as_lua(table: &config::keyassignment::KeyTable, indent: usize)
         println!("{pad}{},", lua_key(key, *mods, action));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use config::keyassignment::CopyModeAssignment;
+
+    #[test]
+    fn export_default_tables() {
+        let map = InputMap::default_input_map();
+        let bindings = map.export_tables();
+        assert!(!bindings.is_empty());
+
+        // Labels are taken from the command palette descriptions
+        assert!(bindings
+            .iter()
+            .any(|info| info.table == "default" && info.label == "Copy to clipboard"));
+
+        // Every binding has a label, and the named tables follow the default
+        // table, so the bindings are grouped by table
+        assert!(bindings.iter().all(|info| !info.label.is_empty()));
+        let first_named = bindings
+            .iter()
+            .position(|info| info.table != "default")
+            .unwrap_or(bindings.len());
+        assert!(bindings[first_named..]
+            .iter()
+            .all(|info| info.table != "default"));
+        assert!(bindings.iter().any(|info| info.table == "copy_mode"));
+
+        let json = serde_json::to_string(&bindings).unwrap();
+        assert!(json.contains("\"label\":\"Copy to clipboard\""));
+    }
+
+    #[test]
+    fn disable_default_copy_mode_assignment() {
+        let mut user = KeyTable::default();
+        user.insert(
+            (KeyCode::Char('y'), Modifiers::NONE),
+            KeyTableEntry {
+                action: KeyAssignment::DisableDefaultAssignment,
+            },
+        );
+        user.insert(
+            (KeyCode::Char('x'), Modifiers::NONE),
+            KeyTableEntry {
+                action: KeyAssignment::CopyMode(CopyModeAssignment::Close),
+            },
+        );
+
+        let defaults = crate::overlay::copy::copy_key_table();
+        assert!(defaults.contains_key(&(KeyCode::Char('y'), Modifiers::NONE)));
+
+        let table = merge_default_key_table(user, defaults);
+        assert!(!table.contains_key(&(KeyCode::Char('y'), Modifiers::NONE)));
+        // The user's own assignments and the remaining defaults are kept
+        assert!(table.contains_key(&(KeyCode::Char('x'), Modifiers::NONE)));
+        assert!(table.contains_key(&(KeyCode::Char('q'), Modifiers::NONE)));
+    }
+}
@@ -10,7 +10,8 @@ use clap::{Parser, ValueHint};
 use config::keyassignment::{SpawnCommand, SpawnTabDomain};
 use config::{ConfigHandle, SerialDomain, SshDomain, SshMultiplexing};
 use mux::activity::Activity;
-use mux::domain::{Domain, LocalDomain};
+use mux::connui::ConnectionUI;
+use mux::domain::{attach_with_retries, Domain, LocalDomain};
 use mux::Mux;
 use mux_lua::MuxDomain;
 use portable_pty::cmdbuilder::CommandBuilder;
@@ -22,6 +23,7 @@ use std::ffi::OsString;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 use termwiz::cell::{CellAttributes, UnicodeVersion};
 use termwiz::surface::{Line, SEQ_ZERO};
 use unicode_normalization::UnicodeNormalization;
@@ -331,13 +333,52 @@ async fn spawn_tab_in_domain_if_mux_is_empty(
     Ok(())
 }
 
+/// Attaches `domain`, retrying if that fails, and shows a notification
+/// if it still couldn't be attached once the retries are exhausted
+async fn attach_with_retries_in_background(
+    domain: Arc<dyn Domain>,
+    retries: usize,
+    delay: Duration,
+) {
+    let ui = ConnectionUI::new();
+    ui.title("wezterm: Connecting...");
+    match attach_with_retries(&*domain, None, retries, delay, &ui).await {
+        Ok(()) => ui.close(),
+        Err(err) => {
+            let message = format!(
+                "Failed to connect to domain {}: {:#}",
+                domain.domain_name(),
+                err
+            );
+            log::error!("{}", message);
+            transient_toast_notification(
+                "Wezterm Connection Error",
+                &message,
+                Duration::from_secs(10),
+            );
+        }
+    }
+}
+
 async fn connect_to_auto_connect_domains() -> anyhow::Result<()> {
     let mux = Mux::get();
     let domains = mux.iter_domains();
-    for dom in domains {
-        if let Some(dom) = dom.downcast_ref::<ClientDomain>() {
+    for domain in domains {
+        if let Some(dom) = domain.downcast_ref::<ClientDomain>() {
             if dom.connect_automatically() {
-                dom.attach(None).await?;
+                let (retries, delay) = dom.connect_automatically_retries();
+                if retries == 0 {
+                    dom.attach(None).await?;
+                } else {
+                    // Retrying may take a while, so do it in the background
+                    // rather than holding up the rest of startup
+                    promise::spawn::spawn(attach_with_retries_in_background(
+                        Arc::clone(&domain),
+                        retries,
+                        delay,
+                    ))
+                    .detach();
+                }
             }
         }
     }
@@ -782,11 +823,49 @@ fn main() {
     frontend::shutdown();
 }
 
+/// How problems encountered while loading the configuration
+/// should be surfaced to the user
+#[derive(Debug, PartialEq, Eq)]
+enum ConfigurationNotice {
+    /// There were no problems
+    None,
+    /// Only non-fatal warnings; show them unobtrusively
+    Warnings(String),
+    /// An error, possibly along with warnings; show them prominently
+    Error(String),
+}
+
+impl ConfigurationNotice {
+    fn new(error: Option<String>, warnings: Vec<String>) -> Self {
+        match error {
+            Some(error) => {
+                let mut problems = vec![error];
+                problems.extend(warnings);
+                Self::Error(problems.join("\n"))
+            }
+            None if warnings.is_empty() => Self::None,
+            None => Self::Warnings(warnings.join("\n")),
+        }
+    }
+}
+
 fn maybe_show_configuration_error_window() {
-    let warnings = config::configuration_warnings_and_errors();
-    if !warnings.is_empty() {
-        let err = warnings.join("\n");
-        mux::connui::show_configuration_error_message(&err);
+    match ConfigurationNotice::new(
+        config::configuration_error(),
+        config::configuration_warnings(),
+    ) {
+        ConfigurationNotice::None => {}
+        ConfigurationNotice::Warnings(warnings) => {
+            log::warn!("{}", warnings);
+            transient_toast_notification(
+                "Wezterm Configuration Warning",
+                &warnings,
+                std::time::Duration::from_secs(10),
+            );
+        }
+        ConfigurationNotice::Error(err) => {
+            mux::connui::show_configuration_error_message(&err);
+        }
     }
 }
 
@@ -794,6 +873,8 @@ fn run_show_keys(config: config::ConfigHandle, cmd: &ShowKeysCommand) -> anyhow:
     let map = crate::inputmap::InputMap::new(&config);
     if cmd.lua {
         map.dump_config(cmd.key_table.as_deref());
+    } else if cmd.json {
+        println!("{}", serde_json::to_string_pretty(&map.export_tables())?);
     } else {
         map.show_keys();
     }
@@ -1206,3 +1287,31 @@ fn run() -> anyhow::Result<()> {
         SubCommand::ShowKeys(cmd) => run_show_keys(config, &cmd),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn warnings_alone_are_not_fatal() {
+        assert_eq!(
+            ConfigurationNotice::new(None, vec!["deprecated option".to_string()]),
+            ConfigurationNotice::Warnings("deprecated option".to_string())
+        );
+        assert_eq!(
+            ConfigurationNotice::new(None, vec![]),
+            ConfigurationNotice::None
+        );
+    }
+
+    #[test]
+    fn errors_include_warnings() {
+        assert_eq!(
+            ConfigurationNotice::new(
+                Some("syntax error".to_string()),
+                vec!["deprecated option".to_string()]
+            ),
+            ConfigurationNotice::Error("syntax error\ndeprecated option".to_string())
+        );
+    }
+}
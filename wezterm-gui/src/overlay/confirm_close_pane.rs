@@ -9,6 +9,7 @@ use termwiz::color::ColorAttribute;
 use termwiz::input::{InputEvent, KeyCode, KeyEvent, MouseButtons, MouseEvent};
 use termwiz::surface::{Change, CursorVisibility, Position};
 use termwiz::terminal::Terminal;
+use window::WindowOps;
 
 fn run_confirmation_app(message: &str, term: &mut TermWizTerminal) -> anyhow::Result<bool> {
     term.set_raw_mode()?;
@@ -189,6 +190,24 @@ pub fn confirm_close_pane(
     Ok(())
 }
 
+pub fn confirm_clipboard_write(
+    pane_id: PaneId,
+    mut term: TermWizTerminal,
+    clipboard: ::window::Clipboard,
+    text: String,
+    window: ::window::Window,
+) -> anyhow::Result<()> {
+    if run_confirmation_app(
+        "📋 Allow this pane to set the clipboard contents?",
+        &mut term,
+    )? {
+        window.set_clipboard(clipboard, text);
+    }
+    TermWindow::schedule_cancel_overlay_for_pane(window, pane_id);
+
+    Ok(())
+}
+
 pub fn confirm_close_tab(
     tab_id: TabId,
     mut term: TermWizTerminal,
@@ -15,7 +15,7 @@ use mux::tab::TabId;
 use ordered_float::NotNan;
 use parking_lot::{MappedMutexGuard, Mutex};
 use rangeset::RangeSet;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::ops::Range;
 use std::sync::Arc;
 use std::time::Duration;
@@ -27,12 +27,69 @@ use url::Url;
 use wezterm_term::color::ColorPalette;
 use wezterm_term::{
     unicode_column_width, Clipboard, KeyCode, KeyModifiers, Line, MouseEvent, SemanticType,
-    StableRowIndex, TerminalSize,
+    SemanticZone, StableRowIndex, TerminalSize,
 };
 use window::{KeyCode as WKeyCode, Modifiers, WindowOps};
 
 lazy_static::lazy_static! {
-    static ref SAVED_PATTERN: Mutex<HashMap<TabId, Pattern>> = Mutex::new(HashMap::new());
+    static ref SAVED_PATTERN: Mutex<SavedPatterns> = Mutex::new(SavedPatterns::new());
+}
+
+/// Remembers the most recent search pattern used in each tab, so that
+/// it can be restored when copy mode is next activated in that tab.
+/// Entries are removed when their tab is closed, and if more tabs
+/// than the capacity passed to `insert` have saved patterns, the
+/// least recently used entries are evicted.
+struct SavedPatterns {
+    patterns: HashMap<TabId, Pattern>,
+    /// Tab ids ordered from least to most recently used
+    order: VecDeque<TabId>,
+}
+
+impl SavedPatterns {
+    fn new() -> Self {
+        Self {
+            patterns: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn touch(&mut self, tab_id: TabId) {
+        if let Some(idx) = self.order.iter().position(|&id| id == tab_id) {
+            self.order.remove(idx);
+        }
+        self.order.push_back(tab_id);
+    }
+
+    fn get(&mut self, tab_id: TabId) -> Option<Pattern> {
+        let pattern = self.patterns.get(&tab_id).cloned()?;
+        self.touch(tab_id);
+        Some(pattern)
+    }
+
+    fn insert(&mut self, tab_id: TabId, pattern: Pattern, capacity: usize) {
+        self.patterns.insert(tab_id, pattern);
+        self.touch(tab_id);
+        while self.patterns.len() > capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.patterns.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn remove(&mut self, tab_id: TabId) {
+        if self.patterns.remove(&tab_id).is_some() {
+            self.order.retain(|&id| id != tab_id);
+        }
+    }
+}
+
+/// Discards the saved search pattern for a tab that has been closed
+pub fn forget_saved_pattern(tab_id: TabId) {
+    SAVED_PATTERN.lock().remove(tab_id);
 }
 
 const SEARCH_CHUNK_SIZE: StableRowIndex = 1000;
@@ -68,6 +125,15 @@ struct CopyRenderable {
     pattern: Pattern,
     /// The most recently queried set of matches
     results: Vec<SearchResult>,
+    /// The maximum number of matches to collect
+    max_results: usize,
+    /// The number of rows of context to keep around the cursor
+    /// when scrolling the viewport
+    scroll_gap: usize,
+    /// The maximum number of tabs whose search pattern is remembered
+    max_saved_patterns: usize,
+    /// Set when the search stopped early because it reached max_results
+    results_truncated: bool,
     by_line: HashMap<StableRowIndex, Vec<MatchResult>>,
     last_result_seqno: SequenceNo,
     last_bar_pos: Option<StableRowIndex>,
@@ -76,10 +142,16 @@ struct CopyRenderable {
     height: usize,
     editing_search: bool,
     result_pos: Option<usize>,
+    /// Set when the most recent move to the next or prior match
+    /// wrapped around from one end of the results to the other
+    search_wrapped: bool,
     tab_id: TabId,
     /// Used to debounce queries while the user is typing
     typing_cookie: usize,
     searching: Option<Searching>,
+    /// Set when the user cancelled the search before all of
+    /// the scrollback had been searched
+    search_cancelled: bool,
     pending_jump: Option<PendingJump>,
     last_jump: Option<Jump>,
 }
@@ -132,6 +204,10 @@ impl CopyOverlay {
             start: None,
             viewport: term_window.get_viewport(pane.pane_id()),
             results: vec![],
+            max_results: term_window.config.copy_mode_max_search_results,
+            scroll_gap: term_window.config.copy_mode_scroll_gap,
+            max_saved_patterns: term_window.config.copy_mode_max_saved_patterns,
+            results_truncated: false,
             by_line: HashMap::new(),
             dirty_results: RangeSet::default(),
             width: dims.cols,
@@ -140,19 +216,17 @@ impl CopyOverlay {
             last_bar_pos: None,
             tab_id,
             pattern: if params.pattern.is_empty() {
-                SAVED_PATTERN
-                    .lock()
-                    .get(&tab_id)
-                    .map(|p| p.clone())
-                    .unwrap_or(params.pattern)
+                SAVED_PATTERN.lock().get(tab_id).unwrap_or(params.pattern)
             } else {
                 params.pattern
             },
             editing_search: params.editing_search,
             result_pos: None,
+            search_wrapped: false,
             selection_mode: SelectionMode::Cell,
             typing_cookie: 0,
             searching: None,
+            search_cancelled: false,
             pending_jump: None,
             last_jump: None,
         };
@@ -225,6 +299,9 @@ impl CopyRenderable {
     fn incrementally_recompute_results(&mut self, mut results: Vec<SearchResult>) {
         results.sort();
         results.reverse();
+        if truncate_search_results(&mut results, self.results.len(), self.max_results) {
+            self.results_truncated = true;
+        }
         for (result_index, res) in results.iter().enumerate() {
             let result_index = self.results.len() + result_index;
             for idx in res.start_y..=res.end_y {
@@ -290,12 +367,15 @@ impl CopyRenderable {
         }
 
         self.results.clear();
+        self.results_truncated = false;
         self.by_line.clear();
         self.result_pos.take();
+        self.search_wrapped = false;
+        self.search_cancelled = false;
 
         SAVED_PATTERN
             .lock()
-            .insert(self.tab_id, self.pattern.clone());
+            .insert(self.tab_id, self.pattern.clone(), self.max_saved_patterns);
 
         let bar_pos = self.compute_search_row();
         self.dirty_results.add(bar_pos);
@@ -350,7 +430,9 @@ impl CopyRenderable {
         range: Range<StableRowIndex>,
     ) {
         self.window.invalidate();
-        if pattern != self.pattern {
+        if pattern != self.pattern || self.searching.is_none() {
+            // Either the pattern changed or the search was cancelled
+            // while this chunk was in flight
             return;
         }
         let is_first = self.results.is_empty();
@@ -366,18 +448,22 @@ impl CopyRenderable {
         }
 
         let dims = self.delegate.get_dimensions();
-        if range.start == dims.scrollback_top {
-            self.searching.take();
-            return;
-        }
+        let range = match next_search_chunk(
+            self.searching.as_ref(),
+            range.start,
+            dims.scrollback_top,
+            self.results_truncated,
+        ) {
+            Some(range) => range,
+            None => {
+                self.searching.take();
+                return;
+            }
+        };
 
         // Search next chunk
         let pane: Arc<dyn Pane> = self.delegate.clone();
         let window = self.window.clone();
-        let end = range.start;
-        let range = end
-            .saturating_sub(SEARCH_CHUNK_SIZE)
-            .max(dims.scrollback_top)..end;
 
         self.searching.replace(Searching {
             remain: range.start - dims.scrollback_top,
@@ -405,6 +491,38 @@ impl CopyRenderable {
         .detach();
     }
 
+    /// Stops an in-progress search, keeping the results that were
+    /// found so far.  Any chunk that is already in flight is discarded
+    /// when it completes.
+    fn cancel_search(&mut self) {
+        if self.searching.take().is_some() {
+            self.search_cancelled = true;
+            let bar_pos = self.compute_search_row();
+            self.dirty_results.add(bar_pos);
+            self.window.invalidate();
+        }
+    }
+
+    /// Returns the number of matches for display in the search bar,
+    /// indicating when there may be more than were collected
+    fn result_count_label(&self) -> String {
+        if self.results_truncated {
+            format!("{}+", self.results.len())
+        } else {
+            self.results.len().to_string()
+        }
+    }
+
+    /// Returns the text to show in the search bar to indicate that
+    /// the search wrapped around
+    fn wrapped_label(&self) -> &'static str {
+        if self.search_wrapped {
+            ", wrapped"
+        } else {
+            ""
+        }
+    }
+
     fn clear_selection(&mut self) {
         let pane_id = self.delegate.pane_id();
         self.window
@@ -417,6 +535,7 @@ impl CopyRenderable {
 
     fn activate_match_number(&mut self, n: usize) {
         self.result_pos.replace(n);
+        self.search_wrapped = false;
         let result = self.results[n].clone();
         self.cursor.y = result.end_y;
         self.cursor.x = result.end_x.saturating_sub(1);
@@ -510,13 +629,8 @@ impl CopyRenderable {
     }
 
     fn dimensions(&self) -> Dimensions {
-        const VERTICAL_GAP: isize = 5;
         let dims = self.delegate.get_dimensions();
-        let vertical_gap = if dims.physical_top <= VERTICAL_GAP {
-            1
-        } else {
-            VERTICAL_GAP
-        };
+        let vertical_gap = vertical_gap(self.scroll_gap, dims.physical_top, dims.viewport_rows);
         let top = self.viewport.unwrap_or_else(|| dims.physical_top);
         Dimensions {
             vertical_gap,
@@ -527,23 +641,13 @@ impl CopyRenderable {
 
     fn adjust_viewport_for_cursor_position(&self) {
         let dims = self.dimensions();
-
-        if dims.top > self.cursor.y {
-            // Cursor is off the top of the viewport; adjust
-            self.set_viewport(Some(self.cursor.y.saturating_sub(dims.vertical_gap)));
-            return;
-        }
-
-        let top_gap = self.cursor.y - dims.top;
-        if top_gap < dims.vertical_gap {
-            // Increase the gap so we can "look ahead"
-            self.set_viewport(Some(self.cursor.y.saturating_sub(dims.vertical_gap)));
-            return;
-        }
-
-        let bottom_gap = (dims.dims.viewport_rows as isize).saturating_sub(top_gap);
-        if bottom_gap < dims.vertical_gap {
-            self.set_viewport(Some(dims.top + dims.vertical_gap - bottom_gap));
+        if let Some(top) = viewport_for_cursor(
+            self.cursor.y,
+            dims.top,
+            dims.dims.viewport_rows,
+            dims.vertical_gap,
+        ) {
+            self.set_viewport(Some(top));
         }
     }
 
@@ -570,25 +674,19 @@ impl CopyRenderable {
 
     /// Move to next match
     fn next_match(&mut self) {
-        if let Some(cur) = self.result_pos.as_ref() {
-            let prior = if *cur > 0 {
-                cur - 1
-            } else {
-                self.results.len() - 1
-            };
+        if let Some(cur) = self.result_pos {
+            let (prior, wrapped) = step_match_index(cur, self.results.len(), false);
             self.activate_match_number(prior);
+            self.search_wrapped = wrapped;
         }
     }
 
     /// Move to prior match
     fn prior_match(&mut self) {
-        if let Some(cur) = self.result_pos.as_ref() {
-            let next = if *cur + 1 >= self.results.len() {
-                0
-            } else {
-                *cur + 1
-            };
+        if let Some(cur) = self.result_pos {
+            let (next, wrapped) = step_match_index(cur, self.results.len(), true);
             self.activate_match_number(next);
+            self.search_wrapped = wrapped;
         }
     }
 
@@ -800,6 +898,48 @@ impl CopyRenderable {
         }
     }
 
+    /// Moves the cursor to the top left of the selection, leaving
+    /// the starting point of the selection where it is
+    fn move_to_selection_start(&mut self) {
+        if let Some(start) = self.start {
+            let ((x, y), _) = selection_bounds(
+                start,
+                (self.cursor.x, self.cursor.y),
+                self.selection_mode == SelectionMode::Block,
+            );
+            self.cursor.x = x;
+            self.cursor.y = y;
+            self.select_to_cursor_pos();
+        }
+    }
+
+    /// Moves the cursor to the bottom right of the selection, leaving
+    /// the starting point of the selection where it is
+    fn move_to_selection_end(&mut self) {
+        if let Some(start) = self.start {
+            let (_, (x, y)) = selection_bounds(
+                start,
+                (self.cursor.x, self.cursor.y),
+                self.selection_mode == SelectionMode::Block,
+            );
+            self.cursor.x = x;
+            self.cursor.y = y;
+            self.select_to_cursor_pos();
+        }
+    }
+
+    /// Selects the whole of the semantic zone that contains the
+    /// cursor, leaving the cursor at the end of that zone
+    fn select_current_semantic_zone(&mut self) {
+        let zones = self.delegate.get_semantic_zones().unwrap_or_default();
+        let (start, (x, y)) = current_semantic_zone(&zones, (self.cursor.x, self.cursor.y));
+        self.start.replace(start);
+        self.selection_mode = SelectionMode::SemanticZone;
+        self.cursor.x = x;
+        self.cursor.y = y;
+        self.select_to_cursor_pos();
+    }
+
     fn move_backward_one_word(&mut self) {
         let y = if self.cursor.x == 0 && self.cursor.y > 0 {
             self.cursor.x = usize::max_value();
@@ -1178,6 +1318,9 @@ impl Pane for CopyOverlay {
                     MoveToStartOfNextLine => render.move_to_start_of_next_line(),
                     MoveToSelectionOtherEnd => render.move_to_selection_other_end(),
                     MoveToSelectionOtherEndHoriz => render.move_to_selection_other_end_horiz(),
+                    MoveToSelectionStart => render.move_to_selection_start(),
+                    MoveToSelectionEnd => render.move_to_selection_end(),
+                    SelectCurrentSemanticZone => render.select_current_semantic_zone(),
                     MoveBackwardWord => render.move_backward_one_word(),
                     MoveForwardWord => render.move_forward_one_word(),
                     MoveForwardWordEnd => render.move_to_end_of_word(),
@@ -1195,6 +1338,7 @@ impl Pane for CopyOverlay {
                     NextMatchPage => render.next_match_page(),
                     CycleMatchType => render.cycle_match_type(),
                     ClearPattern => render.clear_pattern(),
+                    CancelSearch => render.cancel_search(),
                     EditPattern => render.edit_pattern(),
                     AcceptPattern => render.accept_pattern(),
                     SetSelectionMode(mode) => render.set_selection_mode(mode),
@@ -1351,17 +1495,21 @@ impl Pane for CopyOverlay {
                             Some(Searching { remain, .. }) => {
                                 format!(" searching {remain} lines")
                             }
+                            None if self.renderer.search_cancelled => {
+                                ", search cancelled".to_string()
+                            }
                             None => String::new(),
                         };
 
                         line.overlay_text_with_attribute(
                             0,
                             &format!(
-                                "Search: {} ({}/{} matches. {}{remain})",
+                                "Search: {} ({}/{} matches. {}{}{remain})",
                                 *self.renderer.pattern,
                                 self.renderer.result_pos.map(|x| x + 1).unwrap_or(0),
-                                self.renderer.results.len(),
-                                mode
+                                self.renderer.result_count_label(),
+                                mode,
+                                self.renderer.wrapped_label(),
                             ),
                             rev,
                             SEQ_ZERO,
@@ -1450,11 +1598,12 @@ impl Pane for CopyOverlay {
                 line.overlay_text_with_attribute(
                     0,
                     &format!(
-                        "Search: {} ({}/{} matches. {})",
+                        "Search: {} ({}/{} matches. {}{})",
                         *renderer.pattern,
                         renderer.result_pos.map(|x| x + 1).unwrap_or(0),
-                        renderer.results.len(),
-                        mode
+                        renderer.result_count_label(),
+                        mode,
+                        renderer.wrapped_label(),
                     ),
                     rev,
                     SEQ_ZERO,
@@ -1507,6 +1656,19 @@ impl Pane for CopyOverlay {
     }
 }
 
+/// Truncates `results` so that, together with the `existing` number
+/// of results that have already been collected, there are no more
+/// than `max` in total.  Returns true if any results were dropped.
+fn truncate_search_results(results: &mut Vec<SearchResult>, existing: usize, max: usize) -> bool {
+    let room = max.saturating_sub(existing);
+    if results.len() > room {
+        results.truncate(room);
+        true
+    } else {
+        false
+    }
+}
+
 fn is_whitespace_word(word: &str) -> bool {
     if let Some(c) = word.chars().next() {
         c.is_whitespace()
@@ -1905,3 +2067,339 @@ pub fn copy_key_table() -> KeyTable {
     }
     table
 }
+
+/// Returns the index of the match adjacent to `cur` amongst `len`
+/// matches, moving towards the end of the results if `forwards` is
+/// true, together with whether that wrapped around to the other end
+fn step_match_index(cur: usize, len: usize, forwards: bool) -> (usize, bool) {
+    if forwards {
+        if cur + 1 >= len {
+            (0, true)
+        } else {
+            (cur + 1, false)
+        }
+    } else if cur > 0 {
+        (cur - 1, false)
+    } else {
+        (len.saturating_sub(1), true)
+    }
+}
+
+/// Given the starting point of the selection and the `(x, y)` cursor
+/// position, returns the `(x, y)` positions of the earlier and later
+/// of the two, in that order.
+/// When `rectangular` is true, the top left and bottom right corners
+/// of the block spanned by the two are returned instead.
+fn selection_bounds(
+    start: SelectionCoordinate,
+    cursor: (usize, StableRowIndex),
+    rectangular: bool,
+) -> ((usize, StableRowIndex), (usize, StableRowIndex)) {
+    let start = (
+        match start.x {
+            SelectionX::Cell(x) => x,
+            SelectionX::BeforeZero => 0,
+        },
+        start.y,
+    );
+    if rectangular {
+        (
+            (start.0.min(cursor.0), start.1.min(cursor.1)),
+            (start.0.max(cursor.0), start.1.max(cursor.1)),
+        )
+    } else if (start.1, start.0) <= (cursor.1, cursor.0) {
+        (start, cursor)
+    } else {
+        (cursor, start)
+    }
+}
+
+/// Returns the starting point of the semantic zone amongst `zones`
+/// that contains the `(x, y)` cursor position, and the `(x, y)`
+/// position of its end.  If there is no such zone, the cursor
+/// position is used for both.
+fn current_semantic_zone(
+    zones: &[SemanticZone],
+    cursor: (usize, StableRowIndex),
+) -> (SelectionCoordinate, (usize, StableRowIndex)) {
+    let range = SelectionRange::zone_around_in(SelectionCoordinate::x_y(cursor.0, cursor.1), zones);
+    let end_x = match range.end.x {
+        SelectionX::Cell(x) => x,
+        SelectionX::BeforeZero => 0,
+    };
+    (range.start, (end_x, range.end.y))
+}
+
+/// Computes the number of rows of context to keep around the cursor
+/// when scrolling.  The `configured` gap is clamped so that the top and
+/// bottom gaps cannot overlap in a short viewport, and is reduced to a
+/// single row when there is little or no scrollback above the screen.
+fn vertical_gap(configured: usize, physical_top: StableRowIndex, viewport_rows: usize) -> isize {
+    let max_gap = viewport_rows.saturating_sub(1) / 2;
+    let gap = configured.min(max_gap) as isize;
+    if physical_top <= gap {
+        gap.min(1)
+    } else {
+        gap
+    }
+}
+
+/// Computes the new top of the viewport needed to keep `cursor_y` at
+/// least `vertical_gap` rows away from the edges of the viewport that
+/// currently starts at `top`.  Returns None if no adjustment is needed.
+fn viewport_for_cursor(
+    cursor_y: StableRowIndex,
+    top: StableRowIndex,
+    viewport_rows: usize,
+    vertical_gap: isize,
+) -> Option<StableRowIndex> {
+    if top > cursor_y {
+        // Cursor is off the top of the viewport; adjust
+        return Some(cursor_y.saturating_sub(vertical_gap));
+    }
+
+    let top_gap = cursor_y - top;
+    if top_gap < vertical_gap {
+        // Increase the gap so we can "look ahead"
+        return Some(cursor_y.saturating_sub(vertical_gap));
+    }
+
+    let bottom_gap = (viewport_rows as isize).saturating_sub(top_gap);
+    if bottom_gap < vertical_gap {
+        return Some(top + vertical_gap - bottom_gap);
+    }
+
+    None
+}
+
+/// Computes the range of rows to search next, after having searched
+/// the chunk starting at `searched_start`.
+/// Returns None once the top of the scrollback has been reached, the
+/// results have been truncated, or the search has been cancelled.
+fn next_search_chunk(
+    searching: Option<&Searching>,
+    searched_start: StableRowIndex,
+    scrollback_top: StableRowIndex,
+    results_truncated: bool,
+) -> Option<Range<StableRowIndex>> {
+    if searching.is_none() || results_truncated || searched_start <= scrollback_top {
+        return None;
+    }
+    let end = searched_start;
+    Some(end.saturating_sub(SEARCH_CHUNK_SIZE).max(scrollback_top)..end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn results_for_rows(rows: Range<StableRowIndex>) -> Vec<SearchResult> {
+        rows.map(|y| SearchResult {
+            start_y: y,
+            start_x: 0,
+            end_y: y,
+            end_x: 3,
+            match_id: 0,
+        })
+        .collect()
+    }
+
+    #[test]
+    fn search_results_are_capped() {
+        let max = 1000;
+
+        // A chunk with fewer matches than the cap is left alone
+        let mut results = results_for_rows(0..600);
+        assert!(!truncate_search_results(&mut results, 0, max));
+        assert_eq!(results.len(), 600);
+
+        // The next chunk only contributes enough to reach the cap
+        let mut more = results_for_rows(600..1200);
+        assert!(truncate_search_results(&mut more, results.len(), max));
+        assert_eq!(more.len(), 400);
+        assert_eq!(more.last().unwrap().start_y, 999);
+        results.append(&mut more);
+        assert_eq!(results.len(), max);
+
+        // Once the cap is reached, nothing further is accumulated
+        let mut more = results_for_rows(1200..1300);
+        assert!(truncate_search_results(&mut more, results.len(), max));
+        assert!(more.is_empty());
+    }
+
+    #[test]
+    fn selection_bounds_are_ordered() {
+        // Selecting downwards from the starting point
+        let start = SelectionCoordinate::x_y(5, 2);
+        assert_eq!(selection_bounds(start, (3, 4), false), ((5, 2), (3, 4)));
+
+        // Selecting upwards from the starting point
+        assert_eq!(selection_bounds(start, (8, 0), false), ((8, 0), (5, 2)));
+
+        // On the same line, the column decides
+        assert_eq!(selection_bounds(start, (1, 2), false), ((1, 2), (5, 2)));
+
+        let start = SelectionCoordinate {
+            x: SelectionX::BeforeZero,
+            y: 3,
+        };
+        assert_eq!(selection_bounds(start, (0, 1), false), ((0, 1), (0, 3)));
+    }
+
+    #[test]
+    fn block_selection_bounds_are_corners() {
+        let start = SelectionCoordinate::x_y(5, 2);
+        // Down and to the left of the starting point
+        assert_eq!(selection_bounds(start, (3, 4), true), ((3, 2), (5, 4)));
+        // Up and to the right
+        assert_eq!(selection_bounds(start, (8, 0), true), ((5, 0), (8, 2)));
+        // Up and to the left; the same as the non-rectangular case
+        assert_eq!(selection_bounds(start, (1, 1), true), ((1, 1), (5, 2)));
+    }
+
+    #[test]
+    fn step_match_index_wraps() {
+        assert_eq!(step_match_index(0, 3, true), (1, false));
+        assert_eq!(step_match_index(1, 3, true), (2, false));
+        // Moving past the end wraps around to the start
+        assert_eq!(step_match_index(2, 3, true), (0, true));
+
+        assert_eq!(step_match_index(2, 3, false), (1, false));
+        // and vice versa
+        assert_eq!(step_match_index(0, 3, false), (2, true));
+
+        // A single match wraps onto itself
+        assert_eq!(step_match_index(0, 1, true), (0, true));
+    }
+
+    #[test]
+    fn current_semantic_zone_is_selected() {
+        fn zone(
+            start: (usize, StableRowIndex),
+            end: (usize, StableRowIndex),
+            semantic_type: SemanticType,
+        ) -> SemanticZone {
+            SemanticZone {
+                start_x: start.0,
+                start_y: start.1,
+                end_x: end.0,
+                end_y: end.1,
+                semantic_type,
+            }
+        }
+
+        // A prompt, the command that was entered, and its output
+        let zones = vec![
+            zone((0, 0), (1, 0), SemanticType::Prompt),
+            zone((2, 0), (9, 0), SemanticType::Input),
+            zone((0, 1), (4, 3), SemanticType::Output),
+        ];
+
+        let (start, end) = current_semantic_zone(&zones, (3, 2));
+        assert_eq!(start, SelectionCoordinate::x_y(0, 1));
+        assert_eq!(end, (4, 3));
+
+        let (start, end) = current_semantic_zone(&zones, (5, 0));
+        assert_eq!(start, SelectionCoordinate::x_y(2, 0));
+        assert_eq!(end, (9, 0));
+
+        // Outside of any zone, only the cursor position is selected
+        let (start, end) = current_semantic_zone(&zones, (0, 7));
+        assert_eq!(start, SelectionCoordinate::x_y(0, 7));
+        assert_eq!(end, (0, 7));
+    }
+
+    #[test]
+    fn cancelled_search_stops_chunking() {
+        let top = 0;
+        let end = SEARCH_CHUNK_SIZE * 4;
+        let mut searching = Some(Searching {
+            remain: end - SEARCH_CHUNK_SIZE,
+        });
+
+        let first = end - SEARCH_CHUNK_SIZE..end;
+        let second = next_search_chunk(searching.as_ref(), first.start, top, false).unwrap();
+        assert_eq!(second, end - 2 * SEARCH_CHUNK_SIZE..first.start);
+
+        // Cancelling means that no further chunks are scheduled
+        searching.take();
+        assert_eq!(
+            next_search_chunk(searching.as_ref(), second.start, top, false),
+            None
+        );
+
+        // Whereas an uncancelled search continues through to the top
+        let searching = Some(Searching { remain: 0 });
+        let third = next_search_chunk(searching.as_ref(), second.start, top, false).unwrap();
+        let fourth = next_search_chunk(searching.as_ref(), third.start, top, false).unwrap();
+        assert_eq!(fourth, top..SEARCH_CHUNK_SIZE);
+        assert_eq!(
+            next_search_chunk(searching.as_ref(), fourth.start, top, false),
+            None
+        );
+    }
+
+    #[test]
+    fn saved_patterns_are_pruned() {
+        let pattern = |s: &str| Pattern::CaseSensitiveString(s.to_string());
+        let mut saved = SavedPatterns::new();
+
+        saved.insert(1, pattern("one"), 2);
+        saved.insert(2, pattern("two"), 2);
+        assert_eq!(saved.get(1), Some(pattern("one")));
+
+        // Removing a tab drops its saved pattern
+        saved.remove(2);
+        assert_eq!(saved.get(2), None);
+
+        // Exceeding the capacity evicts the least recently used entry,
+        // which is tab 3 because tab 1 is used after it is inserted
+        saved.insert(3, pattern("three"), 2);
+        assert_eq!(saved.get(1), Some(pattern("one")));
+        saved.insert(4, pattern("four"), 2);
+        assert_eq!(saved.get(3), None);
+        assert_eq!(saved.get(1), Some(pattern("one")));
+        assert_eq!(saved.get(4), Some(pattern("four")));
+
+        // Lowering the capacity evicts down to the new limit
+        saved.insert(5, pattern("five"), 1);
+        assert_eq!(saved.get(1), None);
+        assert_eq!(saved.get(4), None);
+        assert_eq!(saved.get(5), Some(pattern("five")));
+    }
+
+    #[test]
+    fn configured_scroll_gap() {
+        // The gap is clamped relative to the viewport height, and
+        // collapses to a single row without enough scrollback
+        assert_eq!(vertical_gap(5, 100, 24), 5);
+        assert_eq!(vertical_gap(2, 100, 24), 2);
+        assert_eq!(vertical_gap(20, 100, 24), 11);
+        assert_eq!(vertical_gap(5, 3, 24), 1);
+        assert_eq!(vertical_gap(0, 100, 24), 0);
+
+        // With the default gap, a cursor 4 rows from the bottom of the
+        // viewport scrolls the viewport down; with a gap of 2 it doesn't
+        let top = 100;
+        let cursor_y = top + 20;
+        assert_eq!(
+            viewport_for_cursor(cursor_y, top, 24, vertical_gap(5, top, 24)),
+            Some(top + 1)
+        );
+        assert_eq!(
+            viewport_for_cursor(cursor_y, top, 24, vertical_gap(2, top, 24)),
+            None
+        );
+
+        // Likewise near the top of the viewport
+        let cursor_y = top + 3;
+        assert_eq!(
+            viewport_for_cursor(cursor_y, top, 24, vertical_gap(5, top, 24)),
+            Some(top - 2)
+        );
+        assert_eq!(
+            viewport_for_cursor(cursor_y, top, 24, vertical_gap(2, top, 24)),
+            None
+        );
+    }
+}
@@ -15,7 +15,8 @@ pub mod quickselect;
 pub mod selector;
 
 pub use confirm_close_pane::{
-    confirm_close_pane, confirm_close_tab, confirm_close_window, confirm_quit_program,
+    confirm_clipboard_write, confirm_close_pane, confirm_close_tab, confirm_close_window,
+    confirm_quit_program,
 };
 pub use copy::{CopyModeParams, CopyOverlay};
 pub use debug::show_debug_overlay;
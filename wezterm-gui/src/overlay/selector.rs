@@ -31,38 +31,43 @@ struct SelectorState {
     labels: Vec<String>,
 }
 
-impl SelectorState {
-    fn update_filter(&mut self) {
-        if self.filter_term.is_empty() {
-            self.filtered_entries = self.args.choices.clone();
-            return;
-        }
+/// Returns the entries from `choices` that fuzzy match `filter_term`,
+/// ordered from best to worst match.
+/// An empty `filter_term` matches everything in the original order.
+fn filter_choices(choices: &[InputSelectorEntry], filter_term: &str) -> Vec<InputSelectorEntry> {
+    if filter_term.is_empty() {
+        return choices.to_vec();
+    }
 
-        self.filtered_entries.clear();
+    let matcher = SkimMatcherV2::default();
 
-        let matcher = SkimMatcherV2::default();
+    struct MatchResult {
+        row_idx: usize,
+        score: i64,
+    }
 
-        struct MatchResult {
-            row_idx: usize,
-            score: i64,
-        }
+    let mut scores: Vec<MatchResult> = choices
+        .iter()
+        .enumerate()
+        .filter_map(|(row_idx, entry)| {
+            let score = matcher.fuzzy_match(&entry.label, filter_term)?;
+            Some(MatchResult { row_idx, score })
+        })
+        .collect();
 
-        let mut scores: Vec<MatchResult> = self
-            .args
-            .choices
-            .iter()
-            .enumerate()
-            .filter_map(|(row_idx, entry)| {
-                let score = matcher.fuzzy_match(&entry.label, &self.filter_term)?;
-                Some(MatchResult { row_idx, score })
-            })
-            .collect();
+    scores.sort_by(|a, b| a.score.cmp(&b.score).reverse());
 
-        scores.sort_by(|a, b| a.score.cmp(&b.score).reverse());
+    scores
+        .into_iter()
+        .map(|result| choices[result.row_idx].clone())
+        .collect()
+}
 
-        for result in scores {
-            self.filtered_entries
-                .push(self.args.choices[result.row_idx].clone());
+impl SelectorState {
+    fn update_filter(&mut self) {
+        self.filtered_entries = filter_choices(&self.args.choices, &self.filter_term);
+        if self.filter_term.is_empty() {
+            return;
         }
 
         self.active_idx = 0;
@@ -400,3 +405,48 @@ pub fn selector(
     state.render(&mut term)?;
     state.run_loop(&mut term)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entries(labels: &[&str]) -> Vec<InputSelectorEntry> {
+        labels
+            .iter()
+            .map(|label| InputSelectorEntry {
+                label: label.to_string(),
+                id: None,
+            })
+            .collect()
+    }
+
+    fn labels(entries: &[InputSelectorEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.label.as_str()).collect()
+    }
+
+    #[test]
+    fn empty_filter_keeps_all_choices_in_order() {
+        let choices = entries(&["zsh", "bash", "fish"]);
+        assert_eq!(
+            labels(&filter_choices(&choices, "")),
+            vec!["zsh", "bash", "fish"]
+        );
+    }
+
+    #[test]
+    fn filter_excludes_non_matching_choices() {
+        let choices = entries(&["zsh", "bash", "fish"]);
+        assert_eq!(labels(&filter_choices(&choices, "sh")).len(), 3);
+        assert_eq!(labels(&filter_choices(&choices, "fi")), vec!["fish"]);
+        assert!(filter_choices(&choices, "nope").is_empty());
+    }
+
+    #[test]
+    fn filter_orders_best_match_first() {
+        let choices = entries(&["bxaxsxh", "bash"]);
+        assert_eq!(
+            labels(&filter_choices(&choices, "bash")),
+            vec!["bash", "bxaxsxh"]
+        );
+    }
+}
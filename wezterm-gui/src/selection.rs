@@ -197,11 +197,15 @@ impl SelectionRange {
     }
 
     pub fn zone_around(start: SelectionCoordinate, pane: &dyn mux::pane::Pane) -> Self {
-        let zones = match pane.get_semantic_zones() {
-            Ok(z) => z,
-            Err(_) => return Self { start, end: start },
-        };
+        match pane.get_semantic_zones() {
+            Ok(zones) => Self::zone_around_in(start, &zones),
+            Err(_) => Self { start, end: start },
+        }
+    }
 
+    /// Computes the selection range for the zone amongst `zones` that
+    /// contains the specified coords, which must be ordered by position
+    pub fn zone_around_in(start: SelectionCoordinate, zones: &[SemanticZone]) -> Self {
         fn find_zone(start: &SelectionCoordinate, zone: &SemanticZone) -> Ordering {
             match zone.start_y.cmp(&start.y) {
                 Ordering::Greater => return Ordering::Greater,
@@ -354,4 +358,154 @@ impl SelectionRange {
             }
         }
     }
+
+    /// Returns the text covered by this range in `pane`.
+    /// When `rectangular` is true the same columns are taken from each
+    /// row, as for a block selection; see `block_text_from_pane`.
+    /// Otherwise the text runs from the start to the end coordinate;
+    /// rows that were wrapped are joined together, logical lines are
+    /// separated by newlines and trailing whitespace is trimmed.
+    pub fn text_from_pane(&self, pane: &dyn Pane, rectangular: bool) -> String {
+        if rectangular {
+            return self.block_text_from_pane(pane, false);
+        }
+
+        let mut s = String::new();
+        let sel = self.normalize();
+        let mut last_was_wrapped = false;
+        let first_row = sel.rows().start;
+        let last_row = sel.rows().end;
+
+        for line in pane.get_logical_lines(sel.rows()) {
+            if !s.is_empty() && !last_was_wrapped {
+                s.push('\n');
+            }
+            let last_idx = line.physical_lines.len().saturating_sub(1);
+            for (idx, phys) in line.physical_lines.iter().enumerate() {
+                let this_row = line.first_row + idx as StableRowIndex;
+                if this_row >= first_row && this_row < last_row {
+                    let last_phys_idx = phys.len().saturating_sub(1);
+                    let cols = sel.cols_for_row(this_row, false);
+                    let last_col_idx = cols.end.saturating_sub(1).min(last_phys_idx);
+                    let col_span = phys.columns_as_str(cols);
+                    // Only trim trailing whitespace if we are the last line
+                    // in a wrapped sequence
+                    if idx == last_idx {
+                        s.push_str(col_span.trim_end());
+                    } else {
+                        s.push_str(&col_span);
+                    }
+
+                    last_was_wrapped = last_col_idx == last_phys_idx
+                        && phys
+                            .get_cell(last_col_idx)
+                            .map(|c| c.attrs().wrapped())
+                            .unwrap_or(false);
+                }
+            }
+        }
+
+        s
+    }
+
+    /// Returns the text covered by this range in `pane`, treating it
+    /// as a block selection.
+    /// Each row yields its content with trailing whitespace trimmed,
+    /// and rows are separated by a newline, regardless of whether they
+    /// were wrapped.  The last row is only followed by a newline when
+    /// `trailing_newline` is true.
+    pub fn block_text_from_pane(&self, pane: &dyn Pane, trailing_newline: bool) -> String {
+        let sel = self.normalize();
+        let (first_row, lines) = pane.get_lines(sel.rows());
+
+        let mut s = String::new();
+        for (idx, line) in lines.iter().enumerate() {
+            if idx > 0 {
+                s.push('\n');
+            }
+            let cols = sel.cols_for_row(first_row + idx as StableRowIndex, true);
+            s.push_str(line.columns_as_str(cols).trim_end());
+        }
+        if trailing_newline && !lines.is_empty() {
+            s.push('\n');
+        }
+
+        s
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mux::pane::FakePane;
+    use parking_lot::Mutex;
+    use termwiz::surface::Line;
+
+    fn fake_pane(text: &[&str]) -> FakePane {
+        FakePane {
+            lines: Mutex::new(
+                text.iter()
+                    .map(|s| Line::from_text(s, &Default::default(), 1, None))
+                    .collect(),
+            ),
+            viewport_rows: text.len(),
+            ..Default::default()
+        }
+    }
+
+    fn range(start: (usize, StableRowIndex), end: (usize, StableRowIndex)) -> SelectionRange {
+        SelectionRange {
+            start: SelectionCoordinate::x_y(start.0, start.1),
+            end: SelectionCoordinate::x_y(end.0, end.1),
+        }
+    }
+
+    #[test]
+    fn rectangular_text() {
+        let pane = fake_pane(&["hello world", "foo bar baz", "last line"]);
+        assert_eq!(
+            range((2, 0), (4, 2)).text_from_pane(&pane, true),
+            "llo\no b\nst"
+        );
+        // The corners may be given in either order
+        assert_eq!(
+            range((4, 2), (2, 0)).text_from_pane(&pane, true),
+            "llo\no b\nst"
+        );
+    }
+
+    #[test]
+    fn block_text_newlines() {
+        let pane = fake_pane(&["one   ", "two", "three"]);
+        // Wrapping doesn't affect how the rows of a block are joined
+        pane.lines.lock()[0].set_last_cell_was_wrapped(true, 1);
+        let sel = range((0, 0), (9, 2));
+
+        let text = sel.block_text_from_pane(&pane, false);
+        assert_eq!(text, "one\ntwo\nthree");
+        assert_eq!(text.matches('\n').count(), 2);
+
+        assert_eq!(sel.block_text_from_pane(&pane, true), "one\ntwo\nthree\n");
+
+        // Leading blank rows are preserved
+        let pane = fake_pane(&["", "two", "three"]);
+        assert_eq!(sel.text_from_pane(&pane, true), "\ntwo\nthree");
+    }
+
+    #[test]
+    fn linear_text() {
+        let pane = fake_pane(&["hello world", "foo bar baz", "last line"]);
+        assert_eq!(
+            range((6, 0), (3, 2)).text_from_pane(&pane, false),
+            "world\nfoo bar baz\nlast"
+        );
+
+        // Rows that were wrapped are joined without a newline
+        let pane = fake_pane(&["wrapped ", "line", "next"]);
+        pane.lines.lock()[0].set_last_cell_was_wrapped(true, 1);
+        assert_eq!(
+            range((0, 0), (3, 2)).text_from_pane(&pane, false),
+            "wrapped line\nnext"
+        );
+    }
 }
@@ -2,7 +2,7 @@ use anyhow::{anyhow, bail, Context};
 use config::keyassignment::SpawnCommand;
 use config::TermConfig;
 use mux::activity::Activity;
-use mux::domain::SplitSource;
+use mux::domain::{InitialInput, SplitSource};
 use mux::tab::SplitRequest;
 use mux::window::WindowId as MuxWindowId;
 use mux::Mux;
@@ -43,6 +43,8 @@ pub async fn spawn_command_internal(
     src_window_id: Option<MuxWindowId>,
     term_config: Arc<TermConfig>,
 ) -> anyhow::Result<()> {
+    spawn.validate().map_err(|err| anyhow!(err))?;
+
     let mux = Mux::get();
     let activity = Activity::new();
 
@@ -82,6 +84,7 @@ pub async fn spawn_command_internal(
     };
 
     let workspace = mux.active_workspace().clone();
+    let initial_input = InitialInput::from_spawn_command(&spawn);
 
     match spawn_where {
         SpawnWhere::SplitPane(direction) => {
@@ -105,6 +108,7 @@ pub async fn spawn_command_internal(
                             command_dir: cwd,
                         },
                         spawn.domain,
+                        initial_input,
                     )
                     .await
                     .context("split_pane")?;
@@ -127,6 +131,7 @@ pub async fn spawn_command_internal(
                     current_pane_id,
                     workspace,
                     spawn.position,
+                    initial_input,
                 )
                 .await
                 .context("spawn_tab_or_window")?;
@@ -1,11 +1,52 @@
+use crate::overlay::{confirm_clipboard_write, start_overlay_pane};
 use crate::termwindow::TermWindowNotif;
 use crate::TermWindow;
 use config::keyassignment::{ClipboardCopyDestination, ClipboardPasteSource};
-use mux::pane::Pane;
+use mlua::FromLua;
+use mux::pane::{Pane, PaneId};
 use mux::Mux;
+use mux_lua::emit_pane_text_event;
 use std::sync::Arc;
 use window::{Clipboard, WindowOps};
 
+/// Emits `osc52-clipboard-write` for `text`.
+/// Returns Some(None) if there is no handler or it returned nil,
+/// None if it returned false, and Some(Some(text)) if it returned
+/// replacement text.
+fn call_osc52_clipboard_write(
+    lua: &mlua::Lua,
+    pane_id: PaneId,
+    text: &str,
+) -> mlua::Result<Option<Option<String>>> {
+    let v = emit_pane_text_event(lua, "osc52-clipboard-write", pane_id, text)?;
+    match &v {
+        mlua::Value::Nil => Ok(Some(None)),
+        mlua::Value::Boolean(false) => Ok(None),
+        _ => Ok(Some(Some(String::from_lua(v, lua)?))),
+    }
+}
+
+/// Gives the `osc52-clipboard-write` event a chance to inspect and
+/// rewrite the text that the application in `pane_id` wants to place
+/// in the clipboard.
+/// Returns None if the write should be discarded.
+pub fn filter_osc52_clipboard_write(pane_id: PaneId, text: String) -> Option<String> {
+    match config::run_immediate_with_lua_config(|lua| match lua {
+        Some(lua) => Ok(call_osc52_clipboard_write(&lua, pane_id, &text)?),
+        None => Ok(Some(None)),
+    }) {
+        Ok(Some(replacement)) => Some(replacement.unwrap_or(text)),
+        Ok(None) => {
+            log::debug!("osc52-clipboard-write discarded the write from pane {pane_id}");
+            None
+        }
+        Err(err) => {
+            log::warn!("osc52-clipboard-write: {:#}", err);
+            Some(text)
+        }
+    }
+}
+
 impl TermWindow {
     pub fn copy_to_clipboard(&self, clipboard: ClipboardCopyDestination, text: String) {
         let clipboard = match clipboard {
@@ -23,6 +64,45 @@ impl TermWindow {
         }
     }
 
+    /// Asks the user whether the application in `pane_id` may place
+    /// `text` in the clipboard, applying it if they agree.
+    /// The write is refused if the pane is already showing an overlay.
+    pub fn confirm_osc52_clipboard_write(
+        &mut self,
+        pane_id: PaneId,
+        clipboard: Clipboard,
+        text: String,
+    ) {
+        let pane = match Mux::get().get_pane(pane_id) {
+            Some(pane) => pane,
+            None => return,
+        };
+        let window = match self.window.clone() {
+            Some(window) => window,
+            None => {
+                log::error!(
+                    "Cannot confirm clipboard write from pane {} as its window has gone",
+                    pane_id
+                );
+                return;
+            }
+        };
+        if self.pane_state(pane_id).overlay.is_some() {
+            // Replacing the active overlay would lose whatever the user
+            // was doing in it, so the write is refused instead
+            log::warn!(
+                "Ignoring clipboard write from pane {} as it already has an overlay",
+                pane_id
+            );
+            return;
+        }
+        let (overlay, future) = start_overlay_pane(self, &pane, move |pane_id, term| {
+            confirm_clipboard_write(pane_id, term, clipboard, text, window)
+        });
+        self.assign_overlay_for_pane(pane_id, overlay);
+        promise::spawn::spawn(future).detach();
+    }
+
     pub fn paste_from_clipboard(&mut self, pane: &Arc<dyn Pane>, clipboard: ClipboardPasteSource) {
         let pane_id = pane.pane_id();
         log::trace!(
@@ -208,6 +208,29 @@ impl super::TermWindow {
         }
     }
 
+    /// Sends `key` with `mods` to the pane, as for the `SendComposedKey`
+    /// key assignment.  Kitty protocol encoding happens here, as it does
+    /// for regular key presses; the other encodings are left to the
+    /// terminal so that modes such as application cursor keys apply.
+    pub fn send_composed_key(
+        &mut self,
+        pane: &Arc<dyn Pane>,
+        key: &KeyCode,
+        mods: Modifiers,
+    ) -> anyhow::Result<()> {
+        if let Some(encoded) = self.encode_kitty_input(pane, &composed_key_event(key, mods)) {
+            if self.config.debug_key_events {
+                log::info!("kitty: Encoded composed key as {:?}", encoded);
+            }
+            pane.writer()
+                .write_all(encoded.as_bytes())
+                .context("sending kitty encoded data")?;
+        } else if let Key::Code(key) = self.win_key_code_to_termwiz_key_code(key) {
+            pane.key_down(key, mods)?;
+        }
+        Ok(())
+    }
+
     fn lookup_key(
         &mut self,
         pane: &Arc<dyn Pane>,
@@ -867,3 +890,34 @@ impl super::TermWindow {
         Key::Code(code)
     }
 }
+
+/// Returns the key press event that `SendComposedKey` sends for `key`
+/// with `mods`
+fn composed_key_event(key: &KeyCode, mods: Modifiers) -> KeyEvent {
+    KeyEvent {
+        key: key.clone(),
+        modifiers: mods,
+        leds: KeyboardLedStatus::empty(),
+        repeat_count: 1,
+        key_is_down: true,
+        raw: None,
+        #[cfg(windows)]
+        win32_uni_char: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use termwiz::escape::csi::KittyKeyboardFlags;
+
+    #[test]
+    fn composed_function_key() {
+        let mods = Modifiers::CTRL | Modifiers::ALT;
+        assert_eq!(
+            composed_key_event(&KeyCode::Function(1), mods)
+                .encode_kitty(KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES),
+            "\x1b[11;7~"
+        );
+    }
+}
@@ -74,6 +74,7 @@ pub mod keyevent;
 pub mod modal;
 mod mouseevent;
 pub mod palette;
+pub mod pane_title;
 pub mod paneselect;
 mod prevcursor;
 pub mod render;
@@ -105,6 +106,31 @@ pub fn get_window_class() -> String {
     WINDOW_CLASS.lock().unwrap().clone()
 }
 
+/// Given the ascending list of prompt `rows` and the row at the top of
+/// the viewport, returns the prompt row that is `amount` prompts away
+fn relative_prompt_row(
+    rows: &[StableRowIndex],
+    position: StableRowIndex,
+    amount: isize,
+) -> Option<StableRowIndex> {
+    let idx = match rows.binary_search(&position) {
+        Ok(idx) | Err(idx) => idx,
+    };
+    let idx = ((idx as isize) + amount).max(0) as usize;
+    rows.get(idx).cloned()
+}
+
+/// Validates the uri passed to the `OpenUri` key assignment and
+/// passes it on to `opener`
+fn open_uri_assignment(uri: &str, opener: impl FnOnce(String)) -> anyhow::Result<()> {
+    let uri = uri.trim();
+    if uri.is_empty() {
+        anyhow::bail!("OpenUri requires a non-empty URI");
+    }
+    opener(uri.to_string());
+    Ok(())
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum MouseCapture {
     UI,
@@ -1154,11 +1180,15 @@ impl TermWindow {
                     self.update_title();
                 }
                 MuxNotification::Alert {
-                    alert: Alert::PaletteChanged,
+                    alert: Alert::PaletteChanged | Alert::SynchronizedOutputChanged(false),
                     pane_id,
                 } => {
                     self.mux_pane_output_event(pane_id);
                 }
+                MuxNotification::Alert {
+                    alert: Alert::SynchronizedOutputChanged(true),
+                    ..
+                } => {}
                 MuxNotification::Alert {
                     alert: Alert::Bell,
                     pane_id,
@@ -1181,6 +1211,17 @@ impl TermWindow {
                     alert: Alert::ToastNotification { .. },
                     ..
                 } => {}
+                MuxNotification::Alert {
+                    alert: Alert::StealFocus,
+                    pane_id,
+                } => {
+                    let mux = Mux::get();
+                    if let Some((_domain, window_id, _tab_id)) = mux.resolve_pane_id(pane_id) {
+                        if window_id == self.mux_window_id {
+                            window.focus();
+                        }
+                    }
+                }
                 MuxNotification::TabAddedToWindow {
                     window_id: _,
                     tab_id,
@@ -1235,6 +1276,9 @@ impl TermWindow {
                 MuxNotification::TabResized(_) => {
                     // Handled by wezterm-client
                 }
+                MuxNotification::TabRemoved(_) => {
+                    // Handled by frontend
+                }
                 MuxNotification::TabTitleChanged { .. } => {
                     self.update_title_post_status();
                 }
@@ -1383,6 +1427,8 @@ impl TermWindow {
                     | Alert::TabTitleChanged(_)
                     | Alert::IconTitleChanged(_)
                     | Alert::SetUserVar { .. }
+                    | Alert::StealFocus
+                    | Alert::SynchronizedOutputChanged(_)
                     | Alert::Bell,
             }
             | MuxNotification::PaneOutput(pane_id) => {
@@ -1425,6 +1471,7 @@ impl TermWindow {
             | MuxNotification::SaveToDownloads { .. }
             | MuxNotification::PaneFocused(_)
             | MuxNotification::TabResized(_)
+            | MuxNotification::TabRemoved(_)
             | MuxNotification::TabTitleChanged { .. }
             | MuxNotification::WindowTitleChanged { .. }
             | MuxNotification::PaneRemoved(_)
@@ -2102,6 +2149,25 @@ impl TermWindow {
         Ok(())
     }
 
+    fn activate_tab_by_title(
+        &mut self,
+        substring: &str,
+        case_insensitive: bool,
+    ) -> anyhow::Result<()> {
+        let mux = Mux::get();
+        let window = mux
+            .get_window(self.mux_window_id)
+            .ok_or_else(|| anyhow!("no such window"))?;
+
+        let tab_idx = window.idx_by_title(substring, case_insensitive);
+        drop(window);
+
+        match tab_idx {
+            Some(tab_idx) => self.activate_tab(tab_idx as isize),
+            None => Ok(()),
+        }
+    }
+
     fn activate_tab_relative(&mut self, delta: isize, wrap: bool) -> anyhow::Result<()> {
         let mux = Mux::get();
         let window = mux
@@ -2131,6 +2197,20 @@ impl TermWindow {
         self.activate_tab(tab)
     }
 
+    fn activate_tab_most_recently_used(&mut self, delta: isize) -> anyhow::Result<()> {
+        let mux = Mux::get();
+        let window = mux
+            .get_window(self.mux_window_id)
+            .ok_or_else(|| anyhow!("no such window"))?;
+
+        let idx = window.get_mru_tab_idx(delta);
+        drop(window);
+        match idx {
+            Some(idx) => self.activate_tab(idx as isize),
+            None => Ok(()),
+        }
+    }
+
     fn activate_last_tab(&mut self) -> anyhow::Result<()> {
         let mux = Mux::get();
         let window = mux
@@ -2311,6 +2391,15 @@ impl TermWindow {
 
         let seqno = pane.get_current_seqno();
         if cache.seqno != seqno {
+            // Prefer the rows recorded by the terminal as prompts were
+            // started, as they are precise
+            let rows = pane.get_prompt_rows().unwrap_or_else(|_| vec![]);
+            if !rows.is_empty() {
+                cache.zones = rows;
+                cache.seqno = seqno;
+                return &cache.zones;
+            }
+
             let zones = pane.get_semantic_zones().unwrap_or_else(|_| vec![]);
             let mut zones: Vec<StableRowIndex> = zones
                 .into_iter()
@@ -2340,11 +2429,7 @@ impl TermWindow {
             .unwrap_or(dims.physical_top);
         let zone = {
             let zones = self.get_semantic_prompt_zones(&pane);
-            let idx = match zones.binary_search(&position) {
-                Ok(idx) | Err(idx) => idx,
-            };
-            let idx = ((idx as isize) + amount).max(0) as usize;
-            zones.get(idx).cloned()
+            relative_prompt_row(zones, position, amount)
         };
         if let Some(zone) = zone {
             self.set_viewport(pane.pane_id(), Some(zone), dims);
@@ -2356,6 +2441,30 @@ impl TermWindow {
         Ok(())
     }
 
+    fn scroll_to_prompt_filtered(
+        &mut self,
+        amount: isize,
+        only_errors: bool,
+        pane: &Arc<dyn Pane>,
+    ) -> anyhow::Result<()> {
+        if !only_errors {
+            return self.scroll_to_prompt(amount, pane);
+        }
+        let dims = pane.get_dimensions();
+        let position = self
+            .get_viewport(pane.pane_id())
+            .unwrap_or(dims.physical_top);
+        let rows = pane.get_error_prompt_rows().unwrap_or_else(|_| vec![]);
+        if let Some(row) = relative_prompt_row(&rows, position, amount) {
+            self.set_viewport(pane.pane_id(), Some(row), dims);
+        }
+
+        if let Some(win) = self.window.as_ref() {
+            win.invalidate();
+        }
+        Ok(())
+    }
+
     fn scroll_by_page(&mut self, amount: f64, pane: &Arc<dyn Pane>) -> anyhow::Result<()> {
         let dims = pane.get_dimensions();
         let position = self
@@ -2558,6 +2667,7 @@ impl TermWindow {
             ActivateTabRelativeNoWrap(n) => {
                 self.activate_tab_relative(*n, false)?;
             }
+            ActivateTabMostRecentlyUsed(n) => self.activate_tab_most_recently_used(*n)?,
             ActivateLastTab => self.activate_last_tab()?,
             DecreaseFontSize => {
                 if let Some(w) = window.as_ref() {
@@ -2582,6 +2692,12 @@ impl TermWindow {
             ActivateTab(n) => {
                 self.activate_tab(*n)?;
             }
+            ActivateTabByTitle {
+                substring,
+                case_insensitive,
+            } => {
+                self.activate_tab_by_title(substring, *case_insensitive)?;
+            }
             ActivateWindow(n) => {
                 self.activate_window(*n)?;
             }
@@ -2601,6 +2717,10 @@ impl TermWindow {
                     pane.key_down(key, mods)?;
                 }
             }
+            SendComposedKey { key, mods } => {
+                let key = key.resolve(self.config.key_map_preference);
+                self.send_composed_key(&pane, &key, *mods)?;
+            }
             Hide => {
                 if let Some(w) = window.as_ref() {
                     w.hide();
@@ -2621,6 +2741,9 @@ impl TermWindow {
             ScrollByLine(n) => self.scroll_by_line(*n, pane)?,
             ScrollByCurrentEventWheelDelta => self.scroll_by_current_event_wheel_delta(pane)?,
             ScrollToPrompt(n) => self.scroll_to_prompt(*n, pane)?,
+            ScrollToPromptFiltered { delta, only_errors } => {
+                self.scroll_to_prompt_filtered(*delta, *only_errors, pane)?
+            }
             ScrollToTop => self.scroll_to_top(pane),
             ScrollToBottom => self.scroll_to_bottom(pane),
             ShowTabNavigator => self.show_tab_navigator(),
@@ -2697,6 +2820,11 @@ impl TermWindow {
                 let window = self.window.as_ref().unwrap();
                 window.invalidate();
             }
+            ClearScreenAndScrollback { send_redraw } => {
+                mux::pane::clear_screen_and_scrollback(&**pane, *send_redraw)?;
+                let window = self.window.as_ref().unwrap();
+                window.invalidate();
+            }
             Search(pattern) => {
                 if let Some(pane) = self.get_active_pane_or_overlay() {
                     let mut replace_current = false;
@@ -2842,6 +2970,18 @@ impl TermWindow {
                 };
                 tab.set_zoomed(*zoomed);
             }
+            SetTabTitle(title) => {
+                let mux = Mux::get();
+                if let Some(tab) = mux.get_active_tab_for_window(self.mux_window_id) {
+                    tab.set_title_override(title.clone());
+                }
+            }
+            SetWindowTitle(title) => {
+                let mux = Mux::get();
+                if let Some(mut window) = mux.get_window_mut(self.mux_window_id) {
+                    window.set_title_override(title.clone());
+                }
+            }
             SwitchWorkspaceRelative(delta) => {
                 let mux = Mux::get();
                 let workspace = mux.active_workspace();
@@ -2940,6 +3080,19 @@ impl TermWindow {
                     RotationDirection::CounterClockwise => tab.rotate_counter_clockwise(),
                 }
             }
+            RotateTabs(direction) => {
+                let mux = Mux::get();
+                let mut window = match mux.get_window_mut(self.mux_window_id) {
+                    Some(window) => window,
+                    None => return Ok(PerformAssignmentResult::Handled),
+                };
+                match direction {
+                    RotationDirection::Clockwise => window.rotate_tabs_clockwise(),
+                    RotationDirection::CounterClockwise => window.rotate_tabs_counter_clockwise(),
+                }
+                drop(window);
+                self.update_title();
+            }
             SplitPane(split) => {
                 log::trace!("SplitPane {:?}", split);
                 self.spawn_command(
@@ -2984,61 +3137,74 @@ impl TermWindow {
                     termwiz::escape::Esc::Code(termwiz::escape::EscCode::FullReset),
                 )]);
             }
-            OpenUri(link) => {
-                wezterm_open_url::open_url(link);
-            }
+            OpenUri(link) => open_uri_assignment(link, |uri| self.open_uri(pane, uri))?,
             ActivateCommandPalette => {
                 let modal = crate::termwindow::palette::CommandPalette::new(self);
                 self.set_modal(Rc::new(modal));
             }
             PromptInputLine(args) => self.show_prompt_input_line(args),
             InputSelector(args) => self.show_input_selector(args),
+            PromptInputSelector(args) => self.show_input_selector(&args.into()),
+            DumpPane { scrollback, format } => {
+                let path = mux::pane::dump_pane_to_file(
+                    &**pane,
+                    *scrollback,
+                    *format,
+                    &config::RUNTIME_DIR,
+                )?;
+                log::info!("Dumped pane {} to {}", pane.pane_id(), path.display());
+            }
         };
         Ok(PerformAssignmentResult::Handled)
     }
 
     fn do_open_link_at_mouse_cursor(&self, pane: &Arc<dyn Pane>) {
         // They clicked on a link, so let's open it!
+        if let Some(link) = self.current_highlight.as_ref().cloned() {
+            self.open_uri(pane, link.uri().to_string());
+        }
+    }
+
+    /// Opens `link`, first giving the `open-uri` event a chance to handle it.
+    fn open_uri(&self, pane: &Arc<dyn Pane>, link: String) {
         // We need to ensure that we spawn the `open` call outside of the context
         // of our window loop; on Windows it can cause a panic due to
         // triggering our WndProc recursively.
         // We get that assurance for free as part of the async dispatch that we
         // perform below; here we allow the user to define an `open-uri` event
         // handler that can bypass the normal `open_url` functionality.
-        if let Some(link) = self.current_highlight.as_ref().cloned() {
-            let window = GuiWin::new(self);
-            let pane = MuxPane(pane.pane_id());
-
-            async fn open_uri(
-                lua: Option<Rc<mlua::Lua>>,
-                window: GuiWin,
-                pane: MuxPane,
-                link: String,
-            ) -> anyhow::Result<()> {
-                let default_click = match lua {
-                    Some(lua) => {
-                        let args = lua.pack_multi((window, pane, link.clone()))?;
-                        config::lua::emit_event(&lua, ("open-uri".to_string(), args))
-                            .await
-                            .map_err(|e| {
-                                log::error!("while processing open-uri event: {:#}", e);
-                                e
-                            })?
-                    }
-                    None => true,
-                };
-                if default_click {
-                    log::info!("clicking {}", link);
-                    wezterm_open_url::open_url(&link);
+        let window = GuiWin::new(self);
+        let pane = MuxPane(pane.pane_id());
+
+        async fn open_uri(
+            lua: Option<Rc<mlua::Lua>>,
+            window: GuiWin,
+            pane: MuxPane,
+            link: String,
+        ) -> anyhow::Result<()> {
+            let default_click = match lua {
+                Some(lua) => {
+                    let args = lua.pack_multi((window, pane, link.clone()))?;
+                    config::lua::emit_event(&lua, ("open-uri".to_string(), args))
+                        .await
+                        .map_err(|e| {
+                            log::error!("while processing open-uri event: {:#}", e);
+                            e
+                        })?
                 }
-                Ok(())
+                None => true,
+            };
+            if default_click {
+                log::info!("clicking {}", link);
+                wezterm_open_url::open_url(&link);
             }
-
-            promise::spawn::spawn(config::with_lua_config_on_main_thread(move |lua| {
-                open_uri(lua, window, pane, link.uri().to_string())
-            }))
-            .detach();
+            Ok(())
         }
+
+        promise::spawn::spawn(config::with_lua_config_on_main_thread(move |lua| {
+            open_uri(lua, window, pane, link)
+        }))
+        .detach();
     }
     fn close_current_pane(&mut self, confirm: bool) {
         let mux_window_id = self.mux_window_id;
@@ -3118,7 +3284,12 @@ impl TermWindow {
 
     pub fn pane_state(&self, pane_id: PaneId) -> RefMut<PaneState> {
         RefMut::map(self.pane_state.borrow_mut(), |state| {
-            state.entry(pane_id).or_insert_with(PaneState::default)
+            state.entry(pane_id).or_insert_with(|| PaneState {
+                // The mux remembers the viewport across detaching
+                // and reattaching the domain of the pane
+                viewport: Mux::get().get_pane_viewport(pane_id),
+                ..PaneState::default()
+            })
         })
     }
 
@@ -3182,6 +3353,7 @@ impl TermWindow {
         let mut state = self.pane_state(pane_id);
         if pos != state.viewport {
             state.viewport = pos;
+            Mux::get().set_pane_viewport(pane_id, pos);
 
             // This is a bit gross.  If we add other overlays that need this information,
             // this should get extracted out into a trait
@@ -3279,7 +3451,7 @@ impl TermWindow {
             height: pos.height,
             pixel_width: pos.pixel_width,
             pixel_height: pos.pixel_height,
-            title: pos.pane.get_title(),
+            title: pane_title::format_pane_title(pos.pane.pane_id(), pos.pane.get_title()),
             user_vars: pos.pane.copy_user_vars(),
         }
     }
@@ -3453,3 +3625,42 @@ impl Drop for TermWindow {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn open_uri_assignment_invokes_opener() {
+        let mut opened = vec![];
+        open_uri_assignment("https://wezfurlong.org/wezterm/", |uri| opened.push(uri)).unwrap();
+        assert_eq!(opened, vec!["https://wezfurlong.org/wezterm/".to_string()]);
+
+        assert!(open_uri_assignment("", |uri| opened.push(uri)).is_err());
+        assert!(open_uri_assignment("  ", |uri| opened.push(uri)).is_err());
+        assert_eq!(opened.len(), 1);
+    }
+
+    #[test]
+    fn relative_prompt_row_from_viewport() {
+        let rows = [5, 10, 20];
+
+        // The viewport is at a prompt
+        assert_eq!(relative_prompt_row(&rows, 10, -1), Some(5));
+        assert_eq!(relative_prompt_row(&rows, 10, 0), Some(10));
+        assert_eq!(relative_prompt_row(&rows, 10, 1), Some(20));
+
+        // The viewport is between prompts
+        assert_eq!(relative_prompt_row(&rows, 15, -1), Some(10));
+
+        // All prompts are below the viewport
+        assert_eq!(relative_prompt_row(&rows, 0, -1), Some(5));
+        assert_eq!(relative_prompt_row(&rows, 0, 1), Some(10));
+
+        // All prompts are above the viewport
+        assert_eq!(relative_prompt_row(&rows, 30, -1), Some(20));
+        assert_eq!(relative_prompt_row(&rows, 30, 1), None);
+
+        assert_eq!(relative_prompt_row(&[], 10, -1), None);
+    }
+}
@@ -0,0 +1,58 @@
+//! Applies the `format-pane-title` event to the titles that panes
+//! report, so that they can be rewritten before they are displayed.
+use mux::pane::PaneId;
+use mux_lua::call_format_pane_title;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+struct FormattedTitle {
+    generation: usize,
+    raw: String,
+    formatted: String,
+}
+
+lazy_static::lazy_static! {
+    static ref FORMATTED_TITLES: Mutex<HashMap<PaneId, FormattedTitle>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Returns the title that should be displayed for the pane whose
+/// terminal reports `raw` as its title.
+/// The event is only emitted when the title or the configuration
+/// changes; otherwise the previously formatted title is returned.
+pub fn format_pane_title(pane_id: PaneId, raw: String) -> String {
+    let generation = config::configuration().generation();
+    if let Some(entry) = FORMATTED_TITLES.lock().get(&pane_id) {
+        if entry.generation == generation && entry.raw == raw {
+            return entry.formatted.clone();
+        }
+    }
+
+    // Note that the lock must not be held while the event runs,
+    // as the handler may itself ask for pane titles.
+    let formatted = match config::run_immediate_with_lua_config(|lua| match lua {
+        Some(lua) => Ok(call_format_pane_title(&lua, pane_id, &raw)?),
+        None => Ok(None),
+    }) {
+        Ok(title) => title.unwrap_or_else(|| raw.clone()),
+        Err(err) => {
+            log::warn!("format-pane-title: {}", err);
+            raw.clone()
+        }
+    };
+
+    FORMATTED_TITLES.lock().insert(
+        pane_id,
+        FormattedTitle {
+            generation,
+            raw,
+            formatted: formatted.clone(),
+        },
+    );
+    formatted
+}
+
+/// Discards the cached title of a pane that has been removed
+pub fn forget_pane_title(pane_id: PaneId) {
+    FORMATTED_TITLES.lock().remove(&pane_id);
+}
@@ -146,6 +146,7 @@ where
                 stream.flush().await.context("flushing PDU to client")?;
             }
             Ok(Item::Notif(MuxNotification::WindowRemoved(_window_id))) => {}
+            Ok(Item::Notif(MuxNotification::TabRemoved(_tab_id))) => {}
             Ok(Item::Notif(MuxNotification::WindowCreated(_window_id))) => {}
             Ok(Item::Notif(MuxNotification::WindowInvalidated(_window_id))) => {}
             Ok(Item::Notif(MuxNotification::WindowWorkspaceChanged(window_id))) => {
@@ -1032,7 +1032,13 @@ async fn split_pane(split: SplitPane, client_id: Option<Arc<ClientId>>) -> anyho
     };
 
     let (pane, size) = mux
-        .split_pane(split.pane_id, split.split_request, source, split.domain)
+        .split_pane(
+            split.pane_id,
+            split.split_request,
+            source,
+            split.domain,
+            None, // optional initial input
+        )
         .await?;
 
     Ok::<Pdu, anyhow::Error>(Pdu::SpawnResponse(SpawnResponse {
@@ -1057,6 +1063,7 @@ async fn domain_spawn_v2(spawn: SpawnV2, client_id: Option<Arc<ClientId>>) -> an
             None, // optional current pane_id
             spawn.workspace,
             None, // optional gui window position
+            None, // optional initial input
         )
         .await?;
 
@@ -55,3 +55,15 @@ pub fn persistent_toast_notification(title: &str, message: &str) {
         timeout: None,
     });
 }
+
+/// Show a notification that is automatically dismissed after `timeout`,
+/// for things that the user should know about but that don't require
+/// any action on their part.
+pub fn transient_toast_notification(title: &str, message: &str, timeout: std::time::Duration) {
+    show(ToastNotification {
+        title: title.to_string(),
+        message: message.to_string(),
+        url: None,
+        timeout: Some(timeout),
+    });
+}